@@ -42,8 +42,9 @@ class BaseDraggableWidget extends StatelessWidget {
   final LocationIsDraggable isLocationDraggable;
 
   /// On iOS this method is called when user taps draggable widget
-  /// during existing drag sessions. It can be used to provide additional
-  /// dragging item for current session.
+  /// during existing drag sessions. On Linux it is called when user clicks
+  /// while dragging. It can be used to provide additional dragging item for
+  /// current session.
   final AdditionalItemsProvider additionalItems;
 
   static Future<List<DragConfigurationItem>?> _defaultAdditionalItems(
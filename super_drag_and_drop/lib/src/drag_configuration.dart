@@ -32,11 +32,15 @@ class DragConfigurationItem {
     required this.item,
     required this.image,
     this.liftImage,
+    this.shadow,
   });
 
   final DragItem item;
   final TargetedWidgetSnapshot image;
   final TargetedWidgetSnapshot? liftImage;
+
+  /// Overrides [DragOptions.shadow] for this item.
+  final ShadowStyle? shadow;
 }
 
 /// Additional options for drag session.
@@ -44,6 +48,8 @@ class DragOptions {
   const DragOptions({
     this.animatesToStartingPositionOnCancelOrFail = true,
     this.prefersFullSizePreviews = true,
+    this.shadow,
+    this.dragImageOpacity = 0.8,
   });
 
   /// macOS specific
@@ -51,6 +57,12 @@ class DragOptions {
 
   /// iOS specific
   final bool prefersFullSizePreviews;
+
+  /// Shadow of drag images on Linux and Windows.
+  final ShadowStyle? shadow;
+
+  /// Linux specific. Opacity of the drag image.
+  final double dragImageOpacity;
 }
 
 /// Initial configuration of a drag session.
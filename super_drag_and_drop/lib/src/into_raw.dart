@@ -22,6 +22,7 @@ extension DragItemsIntoRaw on List<DragConfigurationItem> {
         image: item.image,
         liftImage: item.liftImage,
         localData: item.item.localData,
+        shadow: item.shadow,
       ));
     }
     return items;
@@ -36,6 +37,8 @@ extension DragConfigurationIntoRaw on DragConfiguration {
       animatesToStartingPositionOnCancelOrFail:
           options.animatesToStartingPositionOnCancelOrFail,
       prefersFullSizePreviews: options.prefersFullSizePreviews,
+      shadow: options.shadow,
+      dragImageOpacity: options.dragImageOpacity,
     );
   }
 }
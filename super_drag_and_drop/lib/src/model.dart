@@ -1,2 +1,2 @@
 export 'package:super_native_extensions/raw_drag_drop.dart'
-    show TargetedWidgetSnapshot, DropOperation, DragSession;
+    show TargetedWidgetSnapshot, DropOperation, DragSession, ShadowStyle;
@@ -11,6 +11,7 @@ class DataProvider {
   DataProvider({
     required this.representations,
     this.suggestedName,
+    this.lazyDataTimeout,
   });
 
   /// Registers this source with native code. The source data will be kept alive
@@ -21,6 +22,11 @@ class DataProvider {
 
   final List<DataRepresentation> representations;
   final String? suggestedName;
+
+  /// Maximum amount of time to wait for lazy representations of this
+  /// provider. Overrides the default set through
+  /// [DataProviderManager.setLazyDataTimeout].
+  final Duration? lazyDataTimeout;
 }
 
 sealed class DataRepresentation {
@@ -36,10 +42,12 @@ sealed class DataRepresentation {
   static DataRepresentationLazy lazy({
     required String format,
     required FutureOr<Object?> Function() dataProvider,
+    VoidCallback? onCancel,
   }) =>
       DataRepresentationLazy._(
         format: format,
         dataProvider: dataProvider,
+        onCancel: onCancel,
       );
 
   static DataRepresentationVirtualFile virtualFile({
@@ -83,6 +91,7 @@ class DataRepresentationLazy extends DataRepresentation {
   DataRepresentationLazy._({
     required this.format,
     required this.dataProvider,
+    this.onCancel,
   }) : id = _nextId++;
 
   @override
@@ -96,6 +105,10 @@ class DataRepresentationLazy extends DataRepresentation {
   @override
   final String format;
   final FutureOr<Object?> Function() dataProvider;
+
+  /// Invoked when the receiver gave up waiting for the data, i.e. because
+  /// the request timed out. The result of [dataProvider] will be ignored.
+  final VoidCallback? onCancel;
 }
 
 /// Progress of a write operation.
@@ -10,4 +10,23 @@ abstract class DataProviderManager {
 
   FutureOr<DataProviderHandle> registerDataProvider(DataProvider provider);
   FutureOr<void> unregisterDataProvider(int providerId);
+
+  /// Sets the default maximum amount of time to wait for lazy data. After the
+  /// timeout elapses [DataRepresentationLazy.onCancel] is invoked and the
+  /// request fails with `dataProviderTimedOut` error where the platform can
+  /// report it, otherwise it is answered with empty data. There is no timeout
+  /// by default; passing `null` disables the timeout.
+  FutureOr<void> setLazyDataTimeout(Duration? timeout);
+
+  /// Configures the cache of resolved lazy data values. Caching is disabled
+  /// by default, in which case providers are asked for every request. Once
+  /// enabled, values are cached until their data provider is unregistered
+  /// and providers are only asked once per value. When cached values exceed
+  /// [maxMemoryUsage] bytes, least recently used values are written to
+  /// temporary files (up to [maxDiskUsage] bytes) or discarded if
+  /// [maxDiskUsage] is `null`.
+  FutureOr<void> setLazyDataCacheConfiguration({
+    required int maxMemoryUsage,
+    int? maxDiskUsage,
+  });
 }
@@ -18,6 +18,8 @@ class DragConfiguration {
     required this.allowedOperations,
     this.animatesToStartingPositionOnCancelOrFail = true,
     this.prefersFullSizePreviews = false,
+    this.shadow,
+    this.dragImageOpacity = 0.8,
   });
 
   final List<DragItem> items;
@@ -29,6 +31,14 @@ class DragConfiguration {
   /// iOS specific
   final bool prefersFullSizePreviews;
 
+  /// Shadow of drag images on platforms where the drag image is rendered by
+  /// the plugin (Linux, Windows). Can be overridden for individual items.
+  /// Default shadow is used when not specified.
+  final ShadowStyle? shadow;
+
+  /// Linux specific. Opacity of the drag image composited from item images.
+  final double dragImageOpacity;
+
   DragConfiguration clone() {
     return DragConfiguration(
       items: items.map((e) => e).toList(),
@@ -36,6 +46,8 @@ class DragConfiguration {
       animatesToStartingPositionOnCancelOrFail:
           animatesToStartingPositionOnCancelOrFail,
       prefersFullSizePreviews: prefersFullSizePreviews,
+      shadow: shadow,
+      dragImageOpacity: dragImageOpacity,
     );
   }
 
@@ -52,6 +64,7 @@ class DragItem {
     required this.image,
     required this.liftImage,
     this.localData,
+    this.shadow,
   });
 
   final DataProviderHandle dataProvider;
@@ -64,22 +77,96 @@ class DragItem {
 
   final Object? localData;
 
+  /// Overrides [DragConfiguration.shadow] for this item.
+  final ShadowStyle? shadow;
+
   void disposeImages() {
     image.dispose();
     liftImage?.dispose();
   }
 }
 
+/// Shadow drawn around drag image. Offset, radius and spread are in logical
+/// pixels.
+class ShadowStyle {
+  const ShadowStyle({
+    this.color = const Color(0xFF000000),
+    this.offset = Offset.zero,
+    this.opacity = 0.5,
+    this.radius = 10,
+    this.spread = 0,
+  });
+
+  /// Shadow color. Color alpha is multiplied by [opacity].
+  final Color color;
+  final Offset offset;
+  final double opacity;
+
+  /// Blur radius.
+  final double radius;
+
+  /// Amount by which the shadow is grown (or shrunk if negative) before
+  /// blurring.
+  final double spread;
+}
+
+enum DragStartMode {
+  /// Drag started by pressing and moving pointer.
+  pointer,
+
+  /// Drag started from keyboard or assistive technology. There is no
+  /// pointer event backing the drag; drop location is chosen with arrow keys
+  /// and confirmed with Return or space. Supported on Linux only.
+  keyboard,
+}
+
 class DragRequest {
   DragRequest({
     required this.configuration,
     required this.position,
     this.combinedDragImage,
+    this.startMode = DragStartMode.pointer,
   });
 
   final DragConfiguration configuration;
   final ui.Offset position;
   final TargetedImageData? combinedDragImage;
+  final DragStartMode startMode;
+}
+
+enum DropDestinationKind {
+  /// Target consumed items as URI list (i.e. file manager or desktop).
+  uriList,
+
+  /// Items were dropped on trash.
+  trash,
+
+  /// Target asked for items to be saved into a directory (XDS).
+  directSave,
+}
+
+/// Destination of drag that ended outside of the application.
+class DropDestination {
+  DropDestination({
+    required this.kind,
+    required this.filePaths,
+  });
+
+  final DropDestinationKind kind;
+
+  /// File paths reported by or provided to the target.
+  final List<String> filePaths;
+
+  static DropDestination deserialize(dynamic destination) {
+    final map = destination as Map;
+    return DropDestination(
+      kind: DropDestinationKind.values.byName(map['kind']),
+      filePaths: (map['filePaths'] as List).cast<String>(),
+    );
+  }
+
+  @override
+  String toString() => 'DropDestination(kind: $kind, filePaths: $filePaths)';
 }
 
 /// Represents a drag session. Allows inspecting local drag data and
@@ -96,6 +183,11 @@ abstract class DragSession {
   /// drag finished with.
   ValueListenable<DropOperation?> get dragCompleted;
 
+  /// Destination of the drop, if it happened outside of the application and
+  /// platform could determine it. Available when [dragCompleted] fires.
+  /// Supported on Linux only.
+  DropDestination? get dropDestination => null;
+
   /// Updated when drag session moves. On mobile and web you will only
   /// get notified when moving over application Window.
   /// On desktop platforms the notification covers entire screen.
@@ -154,5 +246,22 @@ abstract class DragContext {
     required DragConfiguration configuration,
     required ui.Offset position,
     TargetedWidgetSnapshot? combinedDragImage,
+    DragStartMode startMode = DragStartMode.pointer,
+  });
+
+  /// Cancels drag session that is in progress. The session completes with
+  /// [DropOperation.none] ([DropOperation.userCancelled] is only used when
+  /// user cancels the drag). Supported on Linux and Windows.
+  Future<void> cancelDrag(DragSession session);
+
+  /// Returns sessions of this context that are currently being dragged.
+  Future<List<DragSession>> getActiveDragSessions();
+
+  /// Replaces the drag image of an active session. The image is positioned
+  /// relative to the location where drag started, same as images of dragged
+  /// items. Currently only supported on Linux.
+  Future<void> updateDragImage({
+    required DragSession session,
+    required TargetedWidgetSnapshot image,
   });
 }
@@ -67,6 +67,38 @@ class DropItem {
       }.toString();
 }
 
+class DropModifiers {
+  const DropModifiers({
+    required this.shift,
+    required this.alt,
+    required this.control,
+    required this.meta,
+  });
+
+  static DropModifiers deserialize(dynamic modifiers) {
+    final map = modifiers as Map;
+    return DropModifiers(
+      shift: map['shift'] as bool,
+      alt: map['alt'] as bool,
+      control: map['control'] as bool,
+      meta: map['meta'] as bool,
+    );
+  }
+
+  final bool shift;
+  final bool alt;
+  final bool control;
+  final bool meta;
+
+  @override
+  String toString() => {
+        'shift': shift,
+        'alt': alt,
+        'control': control,
+        'meta': meta,
+      }.toString();
+}
+
 class DropEvent extends BaseDropEvent {
   DropEvent({
     required super.sessionId,
@@ -74,6 +106,11 @@ class DropEvent extends BaseDropEvent {
     required this.allowedOperations,
     required this.items,
     this.acceptedOperation,
+    this.autoScrollVelocity,
+    this.modifiers,
+    this.pointerButton,
+    this.locationOnScreen,
+    this.sourceDragSessionId,
   });
 
   final ui.Offset locationInView;
@@ -81,6 +118,34 @@ class DropEvent extends BaseDropEvent {
   final List<DropItem> items;
   final DropOperation? acceptedOperation;
 
+  /// Present when [locationInView] is within edge band of a region
+  /// registered with [DropContext.setAutoScrollRegions]. Each component is
+  /// between -1.0 and 1.0 and grows towards the edge. While the pointer rests
+  /// in the band, drop updates are repeated periodically so that the view
+  /// can keep scrolling.
+  final ui.Offset? autoScrollVelocity;
+
+  /// Keyboard modifiers pressed during the event.
+  ///
+  /// Supported on: Linux.
+  final DropModifiers? modifiers;
+
+  /// Pressed pointer button, 1 being the primary button.
+  ///
+  /// Supported on: Linux.
+  final int? pointerButton;
+
+  /// Pointer location in screen coordinates.
+  ///
+  /// Supported on: Linux.
+  final ui.Offset? locationOnScreen;
+
+  /// Identifier of drag session that started the drag if the drag
+  /// originated in this process, possibly in another engine (window).
+  ///
+  /// Supported on: Linux.
+  final int? sourceDragSessionId;
+
   @override
   String toString() => {
         'sessionId': sessionId,
@@ -89,18 +154,85 @@ class DropEvent extends BaseDropEvent {
         'allowedOperation':
             allowedOperations.map((e) => e.name).toList(growable: false),
         'acceptedOperation': acceptedOperation?.name,
+        'autoScrollVelocity': autoScrollVelocity?.serialize(),
+        'modifiers': modifiers?.toString(),
+        'pointerButton': pointerButton,
+        'locationOnScreen': locationOnScreen?.serialize(),
+        'sourceDragSessionId': sourceDragSessionId,
+      }.toString();
+}
+
+class AutoScrollRegion {
+  const AutoScrollRegion({
+    required this.rect,
+    required this.edgeSize,
+  });
+
+  /// Scrollable area in view coordinates.
+  final ui.Rect rect;
+
+  /// Width of the band along each edge of [rect] where auto-scrolling is
+  /// active.
+  final double edgeSize;
+}
+
+class SpringLoadEvent extends BaseDropEvent {
+  SpringLoadEvent({
+    required super.sessionId,
+    required this.locationInView,
+    required this.forced,
+  });
+
+  static SpringLoadEvent deserialize(dynamic event) {
+    final map = event as Map;
+    return SpringLoadEvent(
+      sessionId: map['sessionId'] as int,
+      locationInView: OffsetExt.deserialize(map['locationInView']),
+      forced: map['forced'] as bool,
+    );
+  }
+
+  final ui.Offset locationInView;
+
+  /// Whether spring-loading was triggered by user pressing a key instead
+  /// of hovering.
+  final bool forced;
+
+  @override
+  String toString() => {
+        'sessionId': sessionId,
+        'locationInView': locationInView.serialize(),
+        'forced': forced,
       }.toString();
 }
 
+class SpringLoadingConfiguration {
+  const SpringLoadingConfiguration({
+    this.enabled = true,
+    this.delay = const Duration(milliseconds: 700),
+    this.regionSize = 8.0,
+  });
+
+  final bool enabled;
+
+  /// How long the pointer must rest over same location before spring-load
+  /// event is sent.
+  final Duration delay;
+
+  /// Maximum distance (in logical pixels) the pointer can move while still
+  /// being considered at the same location.
+  final double regionSize;
+}
+
 class ItemPreview {
   ItemPreview({
     required this.destinationRect,
     this.destinationImage,
 
-    /// iOS only
+    /// iOS and Linux only
     this.fadeOutDelay,
 
-    /// iOS only
+    /// iOS and Linux only
     this.fadeOutDuration,
   });
 
@@ -156,8 +288,15 @@ abstract class DropContextDelegate {
   Future<void> onDropLeave(BaseDropEvent event);
   Future<void> onDropEnded(BaseDropEvent event);
 
-  /// macOS and iOS only.
+  /// macOS, iOS and Linux only. On Linux only called for drags originating
+  /// within the application.
   Future<ItemPreview?> onGetItemPreview(ItemPreviewRequest request);
+
+  /// Called when drag hovers over same location without the drop being
+  /// accepted for the duration configured through
+  /// [DropContext.setSpringLoadingConfiguration], or when user presses Tab
+  /// (Linux only). Drop targets such as folders or tabs can open in response.
+  Future<void> onSpringLoad(SpringLoadEvent event) async {}
 }
 
 abstract class DropContext {
@@ -176,6 +315,14 @@ abstract class DropContext {
 
   Future<void> registerDropFormats(List<String> formats);
 
+  /// Registers regions for which drop events include auto-scroll velocity.
+  /// Passing empty list disables auto-scrolling.
+  Future<void> setAutoScrollRegions(List<AutoScrollRegion> regions);
+
+  /// Configures when [DropContextDelegate.onSpringLoad] is called.
+  Future<void> setSpringLoadingConfiguration(
+      SpringLoadingConfiguration configuration);
+
   DropContextDelegate? delegate;
 
   static DropContext? _instance;
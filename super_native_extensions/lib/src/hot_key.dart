@@ -1,3 +1,5 @@
+import 'package:flutter/services.dart';
+
 import 'native/hot_key.dart' if (dart.library.js_interop) 'web/hot_key.dart';
 
 class HotKeyDefinition {
@@ -22,12 +24,113 @@ class HotKeyDefinition {
         'meta': meta,
         'control': control,
       };
+
+  static HotKeyDefinition deserialize(dynamic definition) {
+    final map = definition as Map;
+    return HotKeyDefinition(
+      platformCode: map['platformCode'],
+      alt: map['alt'],
+      shift: map['shift'],
+      meta: map['meta'],
+      control: map['control'],
+    );
+  }
+}
+
+/// Hot key specified by logical key. Platform code is resolved using current
+/// keyboard layout and the hot key is re-registered when layout changes.
+class LogicalHotKeyDefinition {
+  final LogicalKeyboardKey logicalKey;
+  final bool alt;
+  final bool shift;
+  final bool meta;
+  final bool control;
+
+  LogicalHotKeyDefinition({
+    required this.logicalKey,
+    required this.alt,
+    required this.shift,
+    required this.meta,
+    required this.control,
+  });
+
+  dynamic serialize() => {
+        'logicalKey': logicalKey.keyId,
+        'alt': alt,
+        'shift': shift,
+        'meta': meta,
+        'control': control,
+      };
+}
+
+/// Hot key registered by current isolate.
+class HotKeyInfo {
+  final int handle;
+
+  /// Definition of the hot key or first chord of hot key sequence.
+  final HotKeyDefinition definition;
+
+  /// Remaining chords of hot key sequence; empty for single chord hot keys.
+  final List<HotKeyDefinition> followUpChords;
+
+  /// Logical key for hot keys created with
+  /// [HotKeyManager.createLogicalHotKey].
+  final LogicalKeyboardKey? logicalKey;
+
+  HotKeyInfo({
+    required this.handle,
+    required this.definition,
+    this.followUpChords = const [],
+    this.logicalKey,
+  });
+
+  static HotKeyInfo deserialize(dynamic info) {
+    final map = info as Map;
+    return HotKeyInfo(
+      handle: map['handle'],
+      definition: HotKeyDefinition.deserialize(map),
+      followUpChords: (map['followUpChords'] as List)
+          .map(HotKeyDefinition.deserialize)
+          .toList(),
+      logicalKey: map['logicalKey'] != null
+          ? LogicalKeyboardKey(map['logicalKey'])
+          : null,
+    );
+  }
+}
+
+/// Thrown by [HotKeyManager.createHotKey] when the combination is already
+/// registered by this or another application.
+class HotKeyAlreadyRegisteredException implements Exception {
+  final HotKeyDefinition definition;
+
+  HotKeyAlreadyRegisteredException(this.definition);
+
+  @override
+  String toString() => 'HotKeyAlreadyRegisteredException';
+}
+
+/// Thrown by [HotKeyManager.createLogicalHotKey] when the combination the
+/// logical key resolves to is already registered by this or another
+/// application.
+class LogicalHotKeyAlreadyRegisteredException implements Exception {
+  final LogicalHotKeyDefinition definition;
+
+  LogicalHotKeyAlreadyRegisteredException(this.definition);
+
+  @override
+  String toString() => 'LogicalHotKeyAlreadyRegisteredException';
 }
 
 abstract class HotKeyManagerDelegate {
   /// Invoked when hot key with given handle is pressed.
   void onHotKeyPressed(int handle);
   void onHotKeyReleased(int handle);
+
+  /// Invoked when hot key created with [HotKeyManager.createLogicalHotKey]
+  /// could not be re-registered after keyboard layout change. The hot key
+  /// keeps its previous registration.
+  void onHotKeyRegistrationFailed(int handle) {}
 }
 
 abstract class HotKeyManager {
@@ -36,11 +139,39 @@ abstract class HotKeyManager {
   static HotKeyManager get instance => _instance;
 
   /// Creates HotKey for given definition. Returns null if not supported on
-  /// this platform.
+  /// this platform. Throws [HotKeyAlreadyRegisteredException] if the
+  /// combination is already taken.
   Future<int?> createHotKey(HotKeyDefinition definition);
 
-  /// Destroys hot key with given handle;
+  /// Creates HotKey for given logical key. The key is resolved using current
+  /// keyboard layout and re-registered when the layout changes so that the
+  /// hot key keeps producing the same character. Shift is added to the hot
+  /// key if the character can only be produced with shift pressed. Returns
+  /// null if not supported on this platform or if keyboard layout is not
+  /// available. Throws [LogicalHotKeyAlreadyRegisteredException] if the
+  /// combination is already taken.
+  Future<int?> createLogicalHotKey(LogicalHotKeyDefinition definition);
+
+  /// Creates multi-stroke hot key (i.e. Ctrl+K, Ctrl+S). The sequence is
+  /// cancelled if next chord is not pressed within [timeout]. Only the
+  /// completed sequence is reported to delegate. Returns null if not
+  /// supported on this platform. Throws [HotKeyAlreadyRegisteredException]
+  /// if the sequence conflicts with existing hot key.
+  Future<int?> createHotKeySequence(
+    List<HotKeyDefinition> chords, {
+    Duration timeout = const Duration(seconds: 1),
+  });
+
+  /// Destroys hot key or hot key sequence with given handle;
   Future<void> destroyHotKey(int handle);
 
+  /// Returns whether hot key with given definition can be registered without
+  /// actually registering it. Returns false if not supported on this
+  /// platform.
+  Future<bool> canRegister(HotKeyDefinition definition);
+
+  /// Returns hot keys registered by current isolate.
+  Future<List<HotKeyInfo>> listHotKeys();
+
   set delegate(HotKeyManagerDelegate? delegate);
 }
@@ -22,11 +22,67 @@ abstract class KeyboardLayoutManager {
 
   /// Event fired when current system keyboard layout changes.
   Listenable get onLayoutChanged;
+
+  /// Returns current layout as a JSON snapshot that can be later loaded with
+  /// [overrideLayout] or [KeyboardLayout.fromSnapshot].
+  String exportCurrentLayout() => currentLayout.toSnapshot();
+
+  /// Replaces current system layout with layout loaded from [snapshot]
+  /// until [clearLayoutOverride] is called. System layout changes are not
+  /// reported while the override is active.
+  ///
+  /// The override only applies to this isolate. Hot keys are always
+  /// resolved using the system layout.
+  Future<void> overrideLayout(String snapshot);
+
+  /// Restores current system keyboard layout.
+  Future<void> clearLayoutOverride();
 }
 
 /// Represents a keyboard layout. Allows converting between platform specific
 /// key codes, [PhysicalKeyboardKey]s and [LogicalKeyboardKey]s.
 class KeyboardLayout {
+  /// Creates layout from JSON snapshot returned by
+  /// [KeyboardLayoutManager.exportCurrentLayout].
+  factory KeyboardLayout.fromSnapshot(String snapshot) {
+    return KeyboardLayout.fromModel(
+        model.KeyboardLayout.fromSnapshot(snapshot));
+  }
+
+  factory KeyboardLayout.fromModel(model.KeyboardLayout? layout) {
+    final platformToKey = <int, model.KeyboardKey>{};
+    final physicalToKey = <int, model.KeyboardKey>{};
+    final logicalToKey = <int, model.KeyboardKey>{};
+
+    for (final key in layout?.keys ?? <model.KeyboardKey>[]) {
+      platformToKey[key.platform] = key;
+      physicalToKey[key.physical] = key;
+      if (key.logicalAltShift != null) {
+        logicalToKey[key.logicalAltShift!] = key;
+      }
+      if (key.logicalAlt != null) {
+        logicalToKey[key.logicalAlt!] = key;
+      }
+      if (key.logicalShift != null) {
+        logicalToKey[key.logicalShift!] = key;
+      }
+      if (key.logicalMeta != null) {
+        logicalToKey[key.logicalMeta!] = key;
+      }
+      if (key.logical != null) {
+        logicalToKey[key.logical!] = key;
+      }
+    }
+
+    return KeyboardLayout(platformToKey, physicalToKey, logicalToKey);
+  }
+
+  /// Encodes this layout as JSON snapshot.
+  String toSnapshot() {
+    return model.KeyboardLayout(keys: _physicalToKey.values.toList())
+        .toSnapshot();
+  }
+
   /// Returns the platform specific key code for given [KeyboardKey] for this
   /// keyboard layout or `null` if the code could not have been determined.
   int? getPlatformKeyCode(KeyboardKey key) {
@@ -84,6 +140,45 @@ class KeyboardLayout {
     }
   }
 
+  /// Returns whether the given [PhysicalKeyboardKey] is a dead key at any
+  /// modifier level for this keyboard layout.
+  ///
+  /// Dead keys are currently only reported on Linux. Compose sequences
+  /// (i.e. Compose key followed by `'` and `e`) are not part of the
+  /// keyboard layout and are not reported; the Compose key itself is not
+  /// considered a dead key.
+  bool isDeadKey(PhysicalKeyboardKey physicalKey) {
+    return _physicalToKey[physicalKey.usbHidUsage]?.isDeadKey ?? false;
+  }
+
+  /// Returns the combining character (i.e. U+0301 for dead acute) produced
+  /// by given [PhysicalKeyboardKey] and modifiers, or `null` if the key is
+  /// not a dead key at this level.
+  ///
+  /// Dead keys are currently only reported on Linux. Only the combining
+  /// character of the dead key is returned; characters composed from dead
+  /// key sequences (i.e. dead acute followed by `e` producing `é`) depend on
+  /// the input method's compose table and are not available.
+  int? getDeadKeyCombiningCharacter(
+    PhysicalKeyboardKey physicalKey, {
+    bool shift = false,
+    bool alt = false,
+  }) {
+    final key = _physicalToKey[physicalKey.usbHidUsage];
+    if (key == null) {
+      return null;
+    }
+    if (shift && alt) {
+      return key.deadKeyAltShift;
+    } else if (shift) {
+      return key.deadKeyShift;
+    } else if (alt) {
+      return key.deadKeyAlt;
+    } else {
+      return key.deadKey;
+    }
+  }
+
   final Map<int, model.KeyboardKey> _platformToKey;
   final Map<int, model.KeyboardKey> _physicalToKey;
   final Map<int, model.KeyboardKey> _logicalToKey;
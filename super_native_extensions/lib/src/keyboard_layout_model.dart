@@ -1,3 +1,5 @@
+import 'dart:convert';
+
 class KeyboardKey {
   KeyboardKey({
     required this.platform,
@@ -7,6 +9,11 @@ class KeyboardKey {
     this.logicalAlt,
     this.logicalAltShift,
     this.logicalMeta,
+    this.isDeadKey = false,
+    this.deadKey,
+    this.deadKeyShift,
+    this.deadKeyAlt,
+    this.deadKeyAltShift,
   });
 
   final int platform;
@@ -16,6 +23,11 @@ class KeyboardKey {
   final int? logicalAlt;
   final int? logicalAltShift;
   final int? logicalMeta;
+  final bool isDeadKey;
+  final int? deadKey;
+  final int? deadKeyShift;
+  final int? deadKeyAlt;
+  final int? deadKeyAltShift;
 
   static KeyboardKey deserialize(dynamic value) {
     final map = value as Map;
@@ -26,8 +38,28 @@ class KeyboardKey {
         logicalShift: map['logicalShift'],
         logicalAlt: map['logicalAlt'],
         logicalAltShift: map['logicalAltShift'],
-        logicalMeta: map['logicalMeta']);
+        logicalMeta: map['logicalMeta'],
+        isDeadKey: map['isDeadKey'] ?? false,
+        deadKey: map['deadKey'],
+        deadKeyShift: map['deadKeyShift'],
+        deadKeyAlt: map['deadKeyAlt'],
+        deadKeyAltShift: map['deadKeyAltShift']);
   }
+
+  Map<String, dynamic> serialize() => {
+        'platform': platform,
+        'physical': physical,
+        'logical': logical,
+        'logicalShift': logicalShift,
+        'logicalAlt': logicalAlt,
+        'logicalAltShift': logicalAltShift,
+        'logicalMeta': logicalMeta,
+        'isDeadKey': isDeadKey,
+        'deadKey': deadKey,
+        'deadKeyShift': deadKeyShift,
+        'deadKeyAlt': deadKeyAlt,
+        'deadKeyAltShift': deadKeyAltShift,
+      };
 }
 
 class KeyboardLayout {
@@ -45,4 +77,32 @@ class KeyboardLayout {
     final keys = map['keys'] as List;
     return KeyboardLayout(keys: keys.map(KeyboardKey.deserialize).toList());
   }
+
+  Map<String, dynamic> serialize() => {
+        'keys': keys.map((k) => k.serialize()).toList(),
+      };
+
+  static const _snapshotVersion = 1;
+
+  /// Encodes layout as JSON snapshot. Keys are sorted by physical key code
+  /// so that snapshots of same layout are identical.
+  String toSnapshot() {
+    final keys = List.of(this.keys)
+      ..sort((a, b) => a.physical.compareTo(b.physical));
+    return const JsonEncoder.withIndent('  ').convert({
+      'version': _snapshotVersion,
+      'keys': keys
+          .map((k) => k.serialize()..removeWhere((_, value) => value == null))
+          .toList(),
+    });
+  }
+
+  /// Decodes layout from JSON snapshot created by [toSnapshot].
+  static KeyboardLayout fromSnapshot(String snapshot) {
+    final map = jsonDecode(snapshot);
+    if (map is! Map || map['version'] != _snapshotVersion) {
+      throw const FormatException('Unsupported keyboard layout snapshot');
+    }
+    return deserialize(map)!;
+  }
 }
@@ -20,6 +20,7 @@ extension DataProviderExt on DataProvider {
   dynamic serialize() => {
         'representations': representations.map((e) => e.serialize()),
         'suggestedName': suggestedName,
+        'lazyDataTimeoutMillis': lazyDataTimeout?.inMilliseconds,
       };
 }
 
@@ -60,17 +61,45 @@ class DataProviderManagerImpl implements DataProviderManager {
     }
   }
 
+  @override
+  Future<void> setLazyDataTimeout(Duration? timeout) async {
+    await _channel.invokeMethod("setLazyDataTimeout", {
+      'timeoutMillis': timeout?.inMilliseconds,
+    });
+  }
+
+  @override
+  Future<void> setLazyDataCacheConfiguration({
+    required int maxMemoryUsage,
+    int? maxDiskUsage,
+  }) async {
+    await _channel.invokeMethod("setLazyDataCacheConfiguration", {
+      'maxMemoryUsage': maxMemoryUsage,
+      'maxDiskUsage': maxDiskUsage,
+    });
+  }
+
   Future<dynamic> _onMethodCall(MethodCall call) async {
     if (call.method == 'getLazyData') {
       final args = call.arguments as Map;
       final valueId = args["valueId"] as int;
+      final requestId = args["requestId"] as int;
       final lazyData = _lazyData[valueId];
       if (lazyData != null) {
-        return _ValuePromiseResult.ok(await lazyData.dataProvider())
-            .serialize();
+        _lazyDataRequests[requestId] = lazyData;
+        try {
+          final value = await lazyData.dataProvider();
+          return _ValuePromiseResult.ok(value).serialize();
+        } finally {
+          _lazyDataRequests.remove(requestId);
+        }
       } else {
         return _ValuePromiseResult.cancelled().serialize();
       }
+    } else if (call.method == 'cancelLazyData') {
+      final args = call.arguments as Map;
+      final requestId = args["requestId"] as int;
+      _lazyDataRequests.remove(requestId)?.onCancel?.call();
     } else if (call.method == 'getVirtualFile') {
       final args = call.arguments;
       final sessionId = args['sessionId'] as int;
@@ -165,6 +194,7 @@ class DataProviderManagerImpl implements DataProviderManager {
 
   final _handles = <int, DataProviderHandle>{};
   final _lazyData = <int, DataRepresentationLazy>{};
+  final _lazyDataRequests = <int, DataRepresentationLazy>{};
   final _virtualFile = <int, DataRepresentationVirtualFile>{};
   final _virtualSessions = <int, _VirtualSession>{};
 }
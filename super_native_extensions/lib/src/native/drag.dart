@@ -23,6 +23,8 @@ extension DragConfigurationExt on DragConfiguration {
         'animatesToStartingPositionOnCancelOrFail':
             animatesToStartingPositionOnCancelOrFail,
         'prefersFullSizePreviews': prefersFullSizePreviews,
+        'shadow': shadow?.serialize(),
+        'dragImageOpacity': dragImageOpacity,
       };
 }
 
@@ -31,7 +33,20 @@ extension DragItemExt on DragItem {
         'dataProviderId': dataProvider.id,
         'localData': localData,
         'image': (await image.intoRaw()).serialize(),
-        'liftImage': (await liftImage?.intoRaw())?.serialize()
+        'liftImage': (await liftImage?.intoRaw())?.serialize(),
+        'shadow': shadow?.serialize(),
+      };
+}
+
+extension ShadowStyleExt on ShadowStyle {
+  dynamic serialize() => {
+        // ignore: deprecated_member_use
+        'color': color.value,
+        'offsetX': offset.dx,
+        'offsetY': offset.dy,
+        'opacity': opacity,
+        'radius': radius,
+        'spread': spread,
       };
 }
 
@@ -40,6 +55,7 @@ extension DragRequestExt on DragRequest {
         'configuration': await configuration.serialize(),
         'position': position.serialize(),
         'combinedDragImage': combinedDragImage?.serialize(),
+        'startMode': startMode.name,
       };
 }
 
@@ -59,6 +75,9 @@ class DragSessionImpl extends DragSession {
   @override
   ValueListenable<ui.Offset?> get lastScreenLocation => _lastScreenLocation;
 
+  @override
+  DropDestination? get dropDestination => _dropDestination;
+
   int? sessionId;
 
   @override
@@ -79,6 +98,7 @@ class DragSessionImpl extends DragSession {
   final _dragging = ValueNotifier<bool>(false);
   final _dragCompleted = ValueNotifier<DropOperation?>(null);
   final _lastScreenLocation = ValueNotifier<ui.Offset?>(null);
+  DropDestination? _dropDestination;
 }
 
 final _channel =
@@ -184,8 +204,12 @@ class DragContextImpl extends DragContext {
         final sessionId = arguments['sessionId'];
         final dropOperation =
             DropOperation.values.byName(arguments['dropOperation']);
+        final destination = arguments['destination'];
         final session = _sessions.remove(sessionId);
         if (session != null) {
+          session._dropDestination = destination != null
+              ? DropDestination.deserialize(destination)
+              : null;
           session._dragging.value = false;
           session._dragCompleted.value = dropOperation;
           session.dispose();
@@ -218,6 +242,43 @@ class DragContextImpl extends DragContext {
     });
   }
 
+  @override
+  Future<void> cancelDrag(DragSession session) async {
+    final sessionId = (session as DragSessionImpl).sessionId;
+    if (sessionId == null) {
+      return;
+    }
+    await _channel.invokeMethod('cancelDrag', {
+      'sessionId': sessionId,
+    });
+  }
+
+  @override
+  Future<List<DragSession>> getActiveDragSessions() async {
+    final sessionIds =
+        (await _channel.invokeMethod('getActiveDragSessions') as List)
+            .cast<int>();
+    return sessionIds
+        .map((id) => _sessions[id])
+        .nonNulls
+        .toList(growable: false);
+  }
+
+  @override
+  Future<void> updateDragImage({
+    required DragSession session,
+    required TargetedWidgetSnapshot image,
+  }) async {
+    final sessionId = (session as DragSessionImpl).sessionId;
+    if (sessionId == null) {
+      return;
+    }
+    await _channel.invokeMethod('updateDragImage', {
+      'sessionId': sessionId,
+      'image': (await image.intoRaw()).serialize(),
+    });
+  }
+
   @override
   Future<void> startDrag({
     required BuildContext buildContext,
@@ -225,6 +286,7 @@ class DragContextImpl extends DragContext {
     required DragConfiguration configuration,
     required Offset position,
     TargetedWidgetSnapshot? combinedDragImage,
+    DragStartMode startMode = DragStartMode.pointer,
   }) async {
     final needsCombinedDragImage =
         (await _channel.invokeMethod('needsCombinedDragImage')) as bool;
@@ -235,6 +297,7 @@ class DragContextImpl extends DragContext {
           ? (await combinedDragImage?.intoRaw()) ??
               await combineDragImage(configuration)
           : null,
+      startMode: startMode,
     );
 
     final sessionId =
@@ -60,6 +60,11 @@ class DropEventImpl extends DropEvent {
     required super.allowedOperations,
     required super.items,
     super.acceptedOperation,
+    super.autoScrollVelocity,
+    super.modifiers,
+    super.pointerButton,
+    super.locationOnScreen,
+    super.sourceDragSessionId,
     this.reader,
   });
 
@@ -69,6 +74,9 @@ class DropEventImpl extends DropEvent {
       dynamic event, ReaderProvider readerProvider) async {
     final map = event as Map;
     final acceptedOperation = map['acceptedOperation'];
+    final autoScrollVelocity = map['autoScrollVelocity'];
+    final modifiers = map['modifiers'];
+    final locationOnScreen = map['locationOnScreen'];
     final sessionId = map['sessionId'] as int;
     DataReader? getReader() {
       final reader = map['reader'];
@@ -98,6 +106,16 @@ class DropEventImpl extends DropEvent {
       acceptedOperation: acceptedOperation != null
           ? DropOperation.values.byName(acceptedOperation)
           : null,
+      autoScrollVelocity: autoScrollVelocity != null
+          ? OffsetExt.deserialize(autoScrollVelocity)
+          : null,
+      modifiers:
+          modifiers != null ? DropModifiers.deserialize(modifiers) : null,
+      pointerButton: map['pointerButton'] as int?,
+      locationOnScreen: locationOnScreen != null
+          ? OffsetExt.deserialize(locationOnScreen)
+          : null,
+      sourceDragSessionId: map['sourceDragSessionId'] as int?,
       reader: reader,
     );
   }
@@ -175,6 +193,18 @@ class DropContextImpl extends DropContext {
           return null;
         }
       }, () => null);
+    } else if (call.method == 'onSpringLoad') {
+      return handleError(() async {
+        final event = SpringLoadEvent.deserialize(call.arguments);
+        final session = _sessions[event.sessionId];
+        if (session != null) {
+          return session.mutex.protect(() async {
+            return await delegate?.onSpringLoad(event);
+          });
+        } else {
+          return null;
+        }
+      }, () => null);
     } else if (call.method == 'getPreviewForItem') {
       return handleError(() async {
         final request = ItemPreviewRequest.deserialize(call.arguments);
@@ -199,4 +229,26 @@ class DropContextImpl extends DropContext {
   Future<void> registerDropFormats(List<String> formats) {
     return _channel.invokeMethod("registerDropFormats", {'formats': formats});
   }
+
+  @override
+  Future<void> setAutoScrollRegions(List<AutoScrollRegion> regions) {
+    return _channel.invokeMethod('setAutoScrollRegions', {
+      'regions': regions
+          .map((r) => {
+                'rect': r.rect.serialize(),
+                'edgeSize': r.edgeSize,
+              })
+          .toList(growable: false),
+    });
+  }
+
+  @override
+  Future<void> setSpringLoadingConfiguration(
+      SpringLoadingConfiguration configuration) {
+    return _channel.invokeMethod('setSpringLoadingConfiguration', {
+      'enabled': configuration.enabled,
+      'delay': configuration.delay.inSecondsDouble,
+      'regionSize': configuration.regionSize,
+    });
+  }
 }
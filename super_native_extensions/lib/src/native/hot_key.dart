@@ -14,12 +14,64 @@ class HotKeyManagerImpl extends HotKeyManager {
       _delegate?.onHotKeyPressed(call.arguments as int);
     } else if (call.method == 'onHotKeyReleased') {
       _delegate?.onHotKeyReleased(call.arguments as int);
+    } else if (call.method == 'onHotKeyRegistrationFailed') {
+      _delegate?.onHotKeyRegistrationFailed(call.arguments as int);
     }
   }
 
   @override
   Future<int?> createHotKey(HotKeyDefinition definition) async {
-    return _channel.invokeMethod('createHotKey', definition.serialize());
+    try {
+      return await _channel.invokeMethod(
+          'createHotKey', definition.serialize());
+    } on PlatformException catch (e) {
+      if (e.details == 'hotKeyAlreadyRegistered') {
+        throw HotKeyAlreadyRegisteredException(definition);
+      }
+      rethrow;
+    }
+  }
+
+  @override
+  Future<int?> createLogicalHotKey(LogicalHotKeyDefinition definition) async {
+    try {
+      return await _channel.invokeMethod(
+          'createLogicalHotKey', definition.serialize());
+    } on PlatformException catch (e) {
+      if (e.details == 'hotKeyAlreadyRegistered') {
+        throw LogicalHotKeyAlreadyRegisteredException(definition);
+      }
+      rethrow;
+    }
+  }
+
+  @override
+  Future<int?> createHotKeySequence(
+    List<HotKeyDefinition> chords, {
+    Duration timeout = const Duration(seconds: 1),
+  }) async {
+    try {
+      return await _channel.invokeMethod('createHotKeySequence', {
+        'chords': chords.map((c) => c.serialize()).toList(),
+        'timeout': timeout.inMilliseconds / 1000.0,
+      });
+    } on PlatformException catch (e) {
+      if (e.details == 'hotKeyAlreadyRegistered') {
+        throw HotKeyAlreadyRegisteredException(chords.first);
+      }
+      rethrow;
+    }
+  }
+
+  @override
+  Future<bool> canRegister(HotKeyDefinition definition) async {
+    return await _channel.invokeMethod('canRegister', definition.serialize());
+  }
+
+  @override
+  Future<List<HotKeyInfo>> listHotKeys() async {
+    final hotKeys = await _channel.invokeMethod('listHotKeys') as List;
+    return hotKeys.map(HotKeyInfo.deserialize).toList();
   }
 
   @override
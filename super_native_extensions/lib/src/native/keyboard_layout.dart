@@ -41,34 +41,25 @@ class KeyboardLayoutManagerImpl extends KeyboardLayoutManager {
   }
 
   void _update(model.KeyboardLayout? layout) {
-    final platformToKey = <int, model.KeyboardKey>{};
-    final physicalToKey = <int, model.KeyboardKey>{};
-    final logicalToKey = <int, model.KeyboardKey>{};
-
-    for (final key in layout?.keys ?? []) {
-      platformToKey[key.platform] = key;
-      physicalToKey[key.physical] = key;
-      if (key.logicalAltShift != null) {
-        logicalToKey[key.logicalAltShift!] = key;
-      }
-      if (key.logicalAlt != null) {
-        logicalToKey[key.logicalAlt!] = key;
-      }
-      if (key.logicalShift != null) {
-        logicalToKey[key.logicalShift!] = key;
-      }
-      if (key.logicalMeta != null) {
-        logicalToKey[key.logicalMeta!] = key;
-      }
-      if (key.logical != null) {
-        logicalToKey[key.logical!] = key;
-      }
-    }
-
-    _currentLayout = KeyboardLayout(platformToKey, physicalToKey, logicalToKey);
+    _currentLayout = KeyboardLayout.fromModel(layout);
     _supported = layout != null;
   }
 
+  @override
+  Future<void> overrideLayout(String snapshot) async {
+    final layout = model.KeyboardLayout.fromSnapshot(snapshot);
+    await _channel.invokeMethod('setLayoutOverride', layout.serialize());
+    // Listeners are notified by onLayoutChanged call.
+    _update(layout);
+  }
+
+  @override
+  Future<void> clearLayoutOverride() async {
+    await _channel.invokeMethod('clearLayoutOverride');
+    _update(model.KeyboardLayout.deserialize(
+        await _channel.invokeMethod('getKeyboardLayout')));
+  }
+
   @override
   KeyboardLayout get currentLayout => _currentLayout;
 
@@ -11,4 +11,13 @@ class DataProviderManagerImpl extends DataProviderManager {
 
   @override
   FutureOr<void> unregisterDataProvider(int providerId) {}
+
+  @override
+  FutureOr<void> setLazyDataTimeout(Duration? timeout) {}
+
+  @override
+  FutureOr<void> setLazyDataCacheConfiguration({
+    required int maxMemoryUsage,
+    int? maxDiskUsage,
+  }) {}
 }
@@ -297,6 +297,7 @@ class DragContextImpl extends DragContext {
     required DragConfiguration configuration,
     required Offset position,
     TargetedWidgetSnapshot? combinedDragImage,
+    DragStartMode startMode = DragStartMode.pointer,
   }) async {
     final session_ = session as DragSessionImpl;
     session_.init(
@@ -306,4 +307,16 @@ class DragContextImpl extends DragContext {
       combinedDragImage,
     );
   }
+
+  @override
+  Future<void> cancelDrag(DragSession session) async {}
+
+  @override
+  Future<List<DragSession>> getActiveDragSessions() async => [];
+
+  @override
+  Future<void> updateDragImage({
+    required DragSession session,
+    required TargetedWidgetSnapshot image,
+  }) async {}
 }
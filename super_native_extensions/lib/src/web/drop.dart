@@ -260,6 +260,13 @@ class DropContextImpl extends DropContext {
   @override
   Future<void> registerDropFormats(List<String> formats) async {}
 
+  @override
+  Future<void> setAutoScrollRegions(List<AutoScrollRegion> regions) async {}
+
+  @override
+  Future<void> setSpringLoadingConfiguration(
+      SpringLoadingConfiguration configuration) async {}
+
   DropEvent _createLocalDropEvent({
     required DragConfiguration configuration,
     required Offset position,
@@ -6,6 +6,29 @@ class HotKeyManagerImpl extends HotKeyManager {
     return null;
   }
 
+  @override
+  Future<int?> createLogicalHotKey(LogicalHotKeyDefinition definition) async {
+    return null;
+  }
+
+  @override
+  Future<int?> createHotKeySequence(
+    List<HotKeyDefinition> chords, {
+    Duration timeout = const Duration(seconds: 1),
+  }) async {
+    return null;
+  }
+
+  @override
+  Future<bool> canRegister(HotKeyDefinition definition) async {
+    return false;
+  }
+
+  @override
+  Future<List<HotKeyInfo>> listHotKeys() async {
+    return [];
+  }
+
   @override
   set delegate(HotKeyManagerDelegate? delegate) {}
 
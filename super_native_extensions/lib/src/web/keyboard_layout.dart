@@ -16,4 +16,10 @@ class KeyboardLayoutManagerImpl extends KeyboardLayoutManager {
 
   @override
   bool get supported => false;
+
+  @override
+  Future<void> overrideLayout(String snapshot) async {}
+
+  @override
+  Future<void> clearLayoutOverride() async {}
 }
@@ -467,6 +467,9 @@ fn get_data_for_uri<'a>(
                                         return byte_array_from_value(env, &value);
                                     }
                                     ValuePromiseResult::Cancelled => return Ok(JObject::null()),
+                                    ValuePromiseResult::TimedOut => {
+                                        return Err(NativeExtensionsError::DataProviderTimedOut)
+                                    }
                                 }
                             }
                             None => return Ok(JObject::null()),
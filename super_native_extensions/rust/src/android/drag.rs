@@ -11,12 +11,16 @@ use jni::{objects::JObject, sys::jsize, JNIEnv};
 
 use crate::{
     android::{DRAG_DROP_HELPER, JAVA_VM},
-    api_model::{DataProviderId, DragConfiguration, DragRequest, DropOperation, ImageData, Point},
+    api_model::{
+        DataProviderId, DragConfiguration, DragRequest, DropOperation, ImageData, Point,
+        TargettedImage,
+    },
     data_provider_manager::DataProviderHandle,
     drag_manager::{
         DataProviderEntry, DragSessionId, PlatformDragContextDelegate, PlatformDragContextId,
     },
     error::{NativeExtensionsError, NativeExtensionsResult},
+    image_data::PixelFormat,
 };
 
 use super::{
@@ -65,22 +69,13 @@ impl PlatformDragContext {
         env: &mut JNIEnv<'a>,
         image: &ImageData,
     ) -> NativeExtensionsResult<JObject<'a>> {
-        let mut tmp = vec![0i32; (image.width * image.height) as usize];
-
-        for y in 0..image.height as usize {
-            for x in 0..image.width as usize {
-                let pos = y * image.bytes_per_row as usize + x * 4;
-                let r = image.data[pos] as i32;
-                let g = image.data[pos + 1] as i32;
-                let b = image.data[pos + 2] as i32;
-                let a = image.data[pos + 3] as i32;
-                // Contrary to what ARGB_8888 documentation says the colors are
-                // indeed encoded in ARGB order.
-                let color =
-                    ((a & 0xff) << 24) | ((r & 0xff) << 16) | ((g & 0xff) << 8) | (b & 0xff);
-                tmp[y * image.width as usize + x] = color;
-            }
-        }
+        // Contrary to what ARGB_8888 documentation says the colors are
+        // indeed encoded in ARGB order, which is BGRA in little endian.
+        let tmp: Vec<i32> = image
+            .to_packed(PixelFormat::BGRA_STRAIGHT)?
+            .chunks_exact(4)
+            .map(|p| i32::from_le_bytes([p[0], p[1], p[2], p[3]]))
+            .collect();
 
         let colors = env.new_int_array(tmp.len() as jsize)?;
         env.set_int_array_region(&colors, 0, &tmp)?;
@@ -115,6 +110,10 @@ impl PlatformDragContext {
         true
     }
 
+    pub fn supports_keyboard_drag() -> bool {
+        false
+    }
+
     pub async fn start_drag(
         &self,
         request: DragRequest,
@@ -228,6 +227,22 @@ impl PlatformDragContext {
         Ok(session.configuration.get_local_data())
     }
 
+    pub fn get_active_sessions(&self) -> Vec<DragSessionId> {
+        self.sessions.borrow().keys().cloned().collect()
+    }
+
+    pub fn cancel_drag(&self, _session_id: DragSessionId) -> NativeExtensionsResult<()> {
+        Err(NativeExtensionsError::UnsupportedOperation)
+    }
+
+    pub fn update_drag_image(
+        &self,
+        _session_id: DragSessionId,
+        _image: TargettedImage,
+    ) -> NativeExtensionsResult<()> {
+        Err(NativeExtensionsError::UnsupportedOperation)
+    }
+
     pub fn get_data_provider_handles(
         &self,
         session_id: DragSessionId,
@@ -278,6 +293,7 @@ impl DragSession {
                     self.platform_context_id,
                     session_id,
                     operation,
+                    None,
                 );
             }
             Ok(HandleEventResult::RemoveSession)
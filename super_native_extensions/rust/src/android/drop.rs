@@ -190,6 +190,11 @@ impl PlatformDropContext {
             items,
             accepted_operation,
             reader: reader.map(|r| r.1),
+            auto_scroll_velocity: None,
+            modifiers: None,
+            pointer_button: None,
+            location_on_screen: None,
+            source_drag_session_id: None,
         })
     }
 
@@ -19,6 +19,7 @@ use url::Url;
 use crate::{
     android::{CLIP_DATA_HELPER, CONTEXT, JAVA_VM},
     error::{NativeExtensionsError, NativeExtensionsResult},
+    format_conversion::{self, FormatConversion},
     reader_manager::{ReadProgress, VirtualFileReader},
     util::DropNotifier,
 };
@@ -245,6 +246,14 @@ impl PlatformDataReader {
         Self::from_clip_data(&env, clip_data, None)
     }
 
+    pub fn format_conversions() -> Vec<FormatConversion> {
+        vec![FormatConversion {
+            source: "text/html",
+            target: "text/plain",
+            convert: format_conversion::html_to_text,
+        }]
+    }
+
     pub fn item_format_is_synthesized(
         &self,
         _item: i64,
@@ -100,6 +100,12 @@ impl ImageData {
 #[derive(Debug, TryFromValue, IntoValue, Clone, Copy, PartialEq, Hash, Eq)]
 pub struct DataProviderValueId(i64);
 
+impl From<i64> for DataProviderValueId {
+    fn from(value: i64) -> Self {
+        Self(value)
+    }
+}
+
 #[derive(Debug, TryFromValue, IntoValue, Clone, Copy, PartialEq, Hash, Eq)]
 pub struct DataProviderId(i64);
 
@@ -156,6 +162,8 @@ impl DataRepresentation {
 pub struct DataProvider {
     pub representations: Vec<DataRepresentation>,
     pub suggested_name: Option<String>,
+    /// Overrides default timeout for lazy representations.
+    pub lazy_data_timeout_millis: Option<i64>,
 }
 
 //
@@ -184,6 +192,39 @@ pub struct DragItem {
     pub lift_image: Option<TargettedImage>,
     pub image: TargettedImage,
     pub local_data: Value,
+    /// Overrides shadow style from drag configuration.
+    pub shadow: Option<ShadowStyle>,
+}
+
+/// Shadow drawn around drag image on platforms where the drag image is
+/// rendered by the plugin (Linux, Windows). Offset, radius and spread are in
+/// logical pixels.
+#[derive(TryFromValue, Debug, Clone, PartialEq)]
+#[irondash(rename_all = "camelCase")]
+pub struct ShadowStyle {
+    /// ARGB color. Alpha is multiplied by `opacity`.
+    pub color: i64,
+    pub offset_x: f64,
+    pub offset_y: f64,
+    pub opacity: f64,
+    /// Blur radius.
+    pub radius: f64,
+    /// Amount by which the shadow is grown (or shrunk if negative) before
+    /// blurring.
+    pub spread: f64,
+}
+
+impl Default for ShadowStyle {
+    fn default() -> Self {
+        Self {
+            color: 0xFF000000,
+            offset_x: 0.0,
+            offset_y: 0.0,
+            opacity: 0.5,
+            radius: 10.0,
+            spread: 0.0,
+        }
+    }
 }
 
 #[derive(TryFromValue, Debug)]
@@ -193,12 +234,33 @@ pub struct DragConfiguration {
     pub allowed_operations: Vec<DropOperation>,
     pub animates_to_starting_position_on_cancel_or_fail: bool,
     pub prefers_full_size_previews: bool,
+    pub shadow: Option<ShadowStyle>,
+    pub drag_image_opacity: f64,
 }
 
 impl DragConfiguration {
     pub fn get_local_data(&self) -> Vec<Value> {
         self.items.iter().map(|i| i.local_data.clone()).collect()
     }
+
+    /// Shadow style for item at given index. Item style takes precedence
+    /// over configuration style.
+    pub fn shadow_for_item(&self, index: usize) -> ShadowStyle {
+        self.items
+            .get(index)
+            .and_then(|item| item.shadow.clone())
+            .or_else(|| self.shadow.clone())
+            .unwrap_or_default()
+    }
+}
+
+#[derive(TryFromValue, Debug, Clone, Copy, PartialEq, Eq)]
+#[irondash(rename_all = "camelCase")]
+pub enum DragStartMode {
+    /// Drag started by pressing and moving pointer.
+    Pointer,
+    /// Drag started from keyboard shortcut or assistive technology.
+    Keyboard,
 }
 
 #[derive(TryFromValue)]
@@ -207,6 +269,7 @@ pub struct DragRequest {
     pub configuration: DragConfiguration,
     pub combined_drag_image: Option<TargettedImage>,
     pub position: Point,
+    pub start_mode: DragStartMode,
 }
 
 #[derive(Debug, TryFromValue, IntoValue, Copy, Clone, PartialEq, Eq)]
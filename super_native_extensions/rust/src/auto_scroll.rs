@@ -0,0 +1,168 @@
+use std::time::{Duration, Instant};
+
+use irondash_message_channel::TryFromValue;
+
+use crate::{
+    api_model::{Point, Rect},
+    drop_manager::DropEvent,
+};
+
+/// Interval between synthetic drop updates while pointer rests in edge band.
+pub const AUTO_SCROLL_INTERVAL: Duration = Duration::from_millis(33);
+
+#[derive(TryFromValue, Clone, Debug)]
+#[irondash(rename_all = "camelCase")]
+pub struct AutoScrollRegion {
+    /// Rect of scrollable area in view coordinates.
+    pub rect: Rect,
+    /// Width of the band along each edge where auto-scrolling is active.
+    pub edge_size: f64,
+}
+
+impl AutoScrollRegion {
+    /// Returns scroll velocity hint for given location. Each component is
+    /// between -1.0 and 1.0 and grows towards the edge. Returns None if the
+    /// location is outside of the region or outside of the edge bands.
+    pub fn velocity(&self, location: &Point) -> Option<Point> {
+        let rect = &self.rect;
+        if self.edge_size <= 0.0
+            || location.x < rect.x
+            || location.y < rect.y
+            || location.x > rect.x + rect.width
+            || location.y > rect.y + rect.height
+        {
+            return None;
+        }
+        let component = |position: f64, start: f64, length: f64| {
+            // Edge bands of each axis are limited to half of the region.
+            let edge_size = self.edge_size.min(length / 2.0);
+            let from_start = position - start;
+            let from_end = start + length - position;
+            if from_start < edge_size {
+                -(1.0 - from_start / edge_size)
+            } else if from_end < edge_size {
+                1.0 - from_end / edge_size
+            } else {
+                0.0
+            }
+        };
+        let velocity = Point {
+            x: component(location.x, rect.x, rect.width),
+            y: component(location.y, rect.y, rect.height),
+        };
+        if velocity.x == 0.0 && velocity.y == 0.0 {
+            None
+        } else {
+            Some(velocity)
+        }
+    }
+}
+
+/// Keeps last drop event of a drop context so that it can be repeated
+/// while the pointer rests in an edge band. Platforms usually only report
+/// drag updates on pointer movement.
+#[derive(Default)]
+pub struct AutoScroll {
+    regions: Vec<AutoScrollRegion>,
+    last_event: Option<DropEvent>,
+    last_update: Option<Instant>,
+    pub timer_scheduled: bool,
+}
+
+impl AutoScroll {
+    pub fn set_regions(&mut self, regions: Vec<AutoScrollRegion>) {
+        self.regions = regions;
+    }
+
+    pub fn velocity(&self, location: &Point) -> Option<Point> {
+        self.regions.iter().find_map(|r| r.velocity(location))
+    }
+
+    /// Called for every drop update sent by platform. Updates the velocity
+    /// hint of the event and returns whether timer needs to be started.
+    pub fn update(&mut self, event: &mut DropEvent) -> bool {
+        event.auto_scroll_velocity = self.velocity(&event.location_in_view);
+        self.last_update = Some(Instant::now());
+        if event.auto_scroll_velocity.is_some() {
+            self.last_event = Some(event.clone());
+            !self.timer_scheduled
+        } else {
+            self.last_event = None;
+            false
+        }
+    }
+
+    /// Whether the pointer is still within an edge band.
+    pub fn is_active(&self) -> bool {
+        self.last_event.is_some()
+    }
+
+    /// Returns event to be sent from timer, unless platform sent an update
+    /// recently.
+    pub fn synthetic_event(&self) -> Option<DropEvent> {
+        let recently_updated = self
+            .last_update
+            .map(|u| u.elapsed() < AUTO_SCROLL_INTERVAL)
+            .unwrap_or(false);
+        if recently_updated {
+            None
+        } else {
+            self.last_event.clone()
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.last_event = None;
+        self.last_update = None;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::api_model::{Point, Rect};
+
+    use super::AutoScrollRegion;
+
+    fn point(x: f64, y: f64) -> Point {
+        Point { x, y }
+    }
+
+    #[test]
+    fn test_velocity() {
+        let region = AutoScrollRegion {
+            rect: Rect::xywh(100.0, 100.0, 200.0, 400.0),
+            edge_size: 20.0,
+        };
+        assert_eq!(region.velocity(&point(50.0, 50.0)), None);
+        assert_eq!(region.velocity(&point(200.0, 300.0)), None);
+        assert_eq!(
+            region.velocity(&point(200.0, 100.0)),
+            Some(point(0.0, -1.0))
+        );
+        assert_eq!(
+            region.velocity(&point(200.0, 110.0)),
+            Some(point(0.0, -0.5))
+        );
+        assert_eq!(
+            region.velocity(&point(295.0, 300.0)),
+            Some(point(0.75, 0.0))
+        );
+        assert_eq!(
+            region.velocity(&point(105.0, 490.0)),
+            Some(point(-0.75, 0.5))
+        );
+    }
+
+    #[test]
+    fn test_small_region() {
+        let region = AutoScrollRegion {
+            rect: Rect::xywh(0.0, 0.0, 20.0, 1000.0),
+            edge_size: 50.0,
+        };
+        // Edge band is limited to half of the region on each axis.
+        assert_eq!(region.velocity(&point(10.0, 500.0)), None);
+        assert_eq!(region.velocity(&point(5.0, 500.0)), Some(point(-0.5, 0.0)));
+        assert_eq!(region.velocity(&point(10.0, 25.0)), Some(point(0.0, -0.5)));
+        assert_eq!(region.velocity(&point(5.0, 975.0)), Some(point(-0.5, 0.5)));
+    }
+}
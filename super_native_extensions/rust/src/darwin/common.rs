@@ -18,7 +18,10 @@ use core_graphics::{
 use objc2::{ffi::NSInteger, rc::Id, runtime::AnyObject, ClassType};
 use objc2_foundation::{ns_string, NSDictionary, NSError, NSString, NSURLTypeIdentifierKey, NSURL};
 
-use crate::api_model::ImageData;
+use crate::{
+    api_model::ImageData,
+    format_conversion::{self, FormatConversion},
+};
 
 pub struct NSURLSecurtyScopeAccess {
     url: Id<NSURL>,
@@ -75,6 +78,7 @@ pub unsafe fn format_from_url(url: &NSURL) -> Option<String> {
 }
 
 pub fn cg_image_from_image_data(image: ImageData) -> CGImage {
+    let image = image.validated();
     let data = CGDataProvider::from_buffer(Arc::new(image.data));
     let rgb = CGColorSpace::create_with_name(unsafe { kCGColorSpaceSRGB })
         .unwrap_or_else(CGColorSpace::create_device_rgb);
@@ -106,3 +110,35 @@ pub fn uti_conforms_to(uti: &str, conforms_to: &str) -> bool {
 
     conforms_to != 0
 }
+
+/// Conversions shared by macOS and iOS readers.
+pub fn pasteboard_format_conversions() -> Vec<FormatConversion> {
+    const UTF8_TEXT: &str = "public.utf8-plain-text";
+    vec![
+        FormatConversion {
+            source: "public.utf16-plain-text",
+            target: UTF8_TEXT,
+            convert: format_conversion::utf16_to_utf8,
+        },
+        FormatConversion {
+            source: UTF8_TEXT,
+            target: "public.utf16-plain-text",
+            convert: format_conversion::utf8_to_utf16,
+        },
+        FormatConversion {
+            source: "public.html",
+            target: UTF8_TEXT,
+            convert: format_conversion::html_to_text,
+        },
+        FormatConversion {
+            source: "public.rtf",
+            target: UTF8_TEXT,
+            convert: format_conversion::rtf_to_text,
+        },
+        FormatConversion {
+            source: "com.microsoft.bmp",
+            target: "public.png",
+            convert: format_conversion::bmp_to_png,
+        },
+    ]
+}
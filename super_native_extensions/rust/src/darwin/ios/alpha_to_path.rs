@@ -2,7 +2,7 @@ use objc2::rc::Id;
 use objc2_foundation::{CGPoint, CGRect, CGSize};
 use objc2_ui_kit::UIBezierPath;
 
-use crate::api_model::ImageData;
+use crate::{api_model::ImageData, log::OkLog};
 
 struct AlphaUtil<'a> {
     image_data: &'a ImageData,
@@ -86,9 +86,12 @@ impl<'a> AlphaUtil<'a> {
 }
 
 pub fn bezier_path_for_alpha(image_data: &ImageData) -> Id<UIBezierPath> {
+    let path = unsafe { UIBezierPath::bezierPath() };
+    if image_data.validate().ok_log().is_none() {
+        return path;
+    }
     let util = AlphaUtil { image_data };
     let rects = util.rects_for_alpha();
-    let path = unsafe { UIBezierPath::bezierPath() };
     for rect in rects.iter() {
         let ratio = image_data.device_pixel_ratio.unwrap_or(1.0);
         let rect = CGRect::new(
@@ -303,8 +303,10 @@ impl DataProviderSession {
                     let data = source_delegate
                         .get_lazy_data_async(source.isolate_id, id)
                         .await;
-                    let data = value_promise_res_to_nsdata(&data);
-                    callback(data.as_deref(), None);
+                    match value_promise_res_to_nsdata(&data) {
+                        Ok(data) => callback(data.as_deref(), None),
+                        Err(error) => callback(None, Some(&error)),
+                    }
                 });
             }
             None => {
@@ -342,8 +344,10 @@ impl DataProviderSession {
                         let precached = state.precached_values.get(id);
                         match precached {
                             Some(value) => {
-                                let data = value_promise_res_to_nsdata(value);
-                                callback(data.as_deref(), None);
+                                match value_promise_res_to_nsdata(value) {
+                                    Ok(data) => callback(data.as_deref(), None),
+                                    Err(error) => callback(None, Some(&error)),
+                                }
                                 return None;
                             }
                             None => return self.fetch_value(*id, callback),
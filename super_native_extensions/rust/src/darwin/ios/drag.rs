@@ -26,7 +26,9 @@ use objc2_ui_kit::{
 };
 
 use crate::{
-    api_model::{DataProviderId, DragConfiguration, DragRequest, DropOperation, Point},
+    api_model::{
+        DataProviderId, DragConfiguration, DragRequest, DropOperation, Point, TargettedImage,
+    },
     data_provider_manager::DataProviderHandle,
     drag_manager::{
         DataProviderEntry, DragSessionId, GetAdditionalItemsResult, GetDragConfigurationResult,
@@ -277,6 +279,7 @@ impl Session {
                 self.context_id,
                 self.session_id,
                 DropOperation::from_platform(operation),
+                None,
             );
         }
     }
@@ -319,6 +322,7 @@ impl Session {
                 self.context_id,
                 self.session_id,
                 DropOperation::None,
+                None,
             );
         }
     }
@@ -423,6 +427,7 @@ impl Drop for Session {
                     self.context_id,
                     self.session_id,
                     DropOperation::UserCancelled,
+                    None,
                 );
             }
         }
@@ -495,6 +500,10 @@ impl PlatformDragContext {
         false
     }
 
+    pub fn supports_keyboard_drag() -> bool {
+        false
+    }
+
     pub async fn start_drag(
         &self,
         _request: DragRequest,
@@ -749,6 +758,22 @@ impl PlatformDragContext {
         let data: Vec<_> = session.configuration.borrow().get_local_data();
         Ok(data)
     }
+
+    pub fn get_active_sessions(&self) -> Vec<DragSessionId> {
+        self.sessions.borrow().keys().cloned().collect()
+    }
+
+    pub fn cancel_drag(&self, _session_id: DragSessionId) -> NativeExtensionsResult<()> {
+        Err(NativeExtensionsError::UnsupportedOperation)
+    }
+
+    pub fn update_drag_image(
+        &self,
+        _session_id: DragSessionId,
+        _image: TargettedImage,
+    ) -> NativeExtensionsResult<()> {
+        Err(NativeExtensionsError::UnsupportedOperation)
+    }
 }
 
 impl Drop for PlatformDragContext {
@@ -161,6 +161,11 @@ impl Session {
             items,
             accepted_operation,
             reader,
+            auto_scroll_velocity: None,
+            modifiers: None,
+            pointer_button: None,
+            location_on_screen: None,
+            source_drag_session_id: None,
         })
     }
 
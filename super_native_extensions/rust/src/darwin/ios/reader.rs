@@ -31,9 +31,12 @@ use objc2_ui_kit::{UIDragItem, UIPasteboard};
 
 use crate::{
     error::{NativeExtensionsError, NativeExtensionsResult},
+    format_conversion::FormatConversion,
     log::OkLog,
     platform_impl::platform::{
-        common::{path_from_url, uti_conforms_to, NSURLSecurtyScopeAccess},
+        common::{
+            pasteboard_format_conversions, path_from_url, uti_conforms_to, NSURLSecurtyScopeAccess,
+        },
         progress_bridge::bridge_progress,
     },
     reader_manager::{ReadProgress, VirtualFileReader},
@@ -190,6 +193,10 @@ impl PlatformDataReader {
         Ok(res)
     }
 
+    pub fn format_conversions() -> Vec<FormatConversion> {
+        pasteboard_format_conversions()
+    }
+
     pub fn item_format_is_synthesized(
         &self,
         _item: i64,
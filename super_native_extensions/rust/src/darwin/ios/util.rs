@@ -17,7 +17,8 @@ use objc2_ui_kit::{CGAffineTransform, UIApplication, UIImage, UIImageOrientation
 use crate::{
     api_model::{ImageData, Point, Rect, Size},
     drag_manager::DragSessionId,
-    platform_impl::platform::common::cg_image_from_image_data,
+    error::NativeExtensionsError,
+    platform_impl::platform::common::{cg_image_from_image_data, to_nserror},
     util::Movable,
     value_coerce::{CoerceToData, StringFormat},
     value_promise::ValuePromiseResult,
@@ -107,10 +108,17 @@ pub fn value_to_nsdata(value: &Value) -> Option<Id<NSData>> {
     buf.map(NSData::from_vec)
 }
 
-pub fn value_promise_res_to_nsdata(value: &ValuePromiseResult) -> Option<Id<NSData>> {
+pub fn value_promise_res_to_nsdata(
+    value: &ValuePromiseResult,
+) -> Result<Option<Id<NSData>>, Id<NSError>> {
     match value {
-        ValuePromiseResult::Ok { value } => value_to_nsdata(value),
-        ValuePromiseResult::Cancelled => None,
+        ValuePromiseResult::Ok { value } => Ok(value_to_nsdata(value)),
+        ValuePromiseResult::Cancelled => Ok(None),
+        ValuePromiseResult::TimedOut => Err(to_nserror(
+            "super_dnd",
+            0,
+            &NativeExtensionsError::DataProviderTimedOut.to_string(),
+        )),
     }
 }
 
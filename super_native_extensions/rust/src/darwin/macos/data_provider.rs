@@ -32,7 +32,7 @@ use crate::{
     data_provider_manager::{
         DataProviderHandle, PlatformDataProviderDelegate, VirtualFileResult, VirtualSessionHandle,
     },
-    error::NativeExtensionsResult,
+    error::{NativeExtensionsError, NativeExtensionsResult},
     log::OkLog,
     platform_impl::platform::common::{path_from_url, to_nserror},
     value_promise::ValuePromiseResult,
@@ -67,6 +67,10 @@ pub struct PlatformDataProvider {
 
 thread_local! {
     static WAITING_FOR_PASTEBOARD_DATA: Cell<bool> = const { Cell::new(false) };
+    /// Errors of lazy data requested by pasteboard for each pending
+    /// [`PlatformDataProvider::read_pasteboard`] call, innermost last.
+    static PASTEBOARD_READS: RefCell<Vec<Option<NativeExtensionsError>>> =
+        const { RefCell::new(Vec::new()) };
 }
 
 impl PlatformDataProvider {
@@ -91,6 +95,30 @@ impl PlatformDataProvider {
         WAITING_FOR_PASTEBOARD_DATA.with(|f| f.get())
     }
 
+    /// Runs `read`, which reads pasteboard data, and returns error if lazy
+    /// data requested by pasteboard on behalf of the read failed. Pasteboard
+    /// has no way to report errors, so this lets in-process reader tell timed
+    /// out data from missing data. Each call tracks its own requests, so
+    /// nested reads do not affect each other.
+    pub fn read_pasteboard<R>(read: impl FnOnce() -> R) -> NativeExtensionsResult<R> {
+        PASTEBOARD_READS.with(|reads| reads.borrow_mut().push(None));
+        let res = read();
+        let error = PASTEBOARD_READS.with(|reads| reads.borrow_mut().pop().flatten());
+        match error {
+            Some(error) => Err(error),
+            None => Ok(res),
+        }
+    }
+
+    /// Records lazy data error for innermost pending pasteboard read.
+    fn report_pasteboard_read_error(error: NativeExtensionsError) {
+        PASTEBOARD_READS.with(|reads| {
+            if let Some(last) = reads.borrow_mut().last_mut() {
+                *last = Some(error);
+            }
+        });
+    }
+
     pub fn assign_weak_self(&self, weak_self: Weak<Self>) {
         self.weak_self.set(weak_self);
     }
@@ -239,7 +267,15 @@ impl ItemState {
                                                 ValuePromiseResult::Ok { value } => {
                                                     return value.to_objc().ok_log().flatten()
                                                 }
-                                                ValuePromiseResult::Cancelled => {
+                                                ValuePromiseResult::Cancelled => return None,
+                                                ValuePromiseResult::TimedOut => {
+                                                    log::warn!(
+                                                        "{} (value {id:?})",
+                                                        NativeExtensionsError::DataProviderTimedOut
+                                                    );
+                                                    PlatformDataProvider::report_pasteboard_read_error(
+                                                        NativeExtensionsError::DataProviderTimedOut,
+                                                    );
                                                     return None;
                                                 }
                                             }
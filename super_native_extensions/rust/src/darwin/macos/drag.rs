@@ -8,7 +8,7 @@ use std::{
 };
 
 use crate::{
-    api_model::{DataProviderId, DragConfiguration, DragRequest, DropOperation},
+    api_model::{DataProviderId, DragConfiguration, DragRequest, DropOperation, TargettedImage},
     data_provider_manager::DataProviderHandle,
     drag_manager::{
         DataProviderEntry, DragSessionId, PlatformDragContextDelegate, PlatformDragContextId,
@@ -163,6 +163,10 @@ impl PlatformDragContext {
         false
     }
 
+    pub fn supports_keyboard_drag() -> bool {
+        false
+    }
+
     pub async fn start_drag(
         &self,
         request: DragRequest,
@@ -375,7 +379,12 @@ impl PlatformDragContext {
             operation
         };
         if let Some(delegate) = self.delegate.upgrade() {
-            delegate.drag_session_did_end_with_operation(self.id, session.session_id, operation);
+            delegate.drag_session_did_end_with_operation(
+                self.id,
+                session.session_id,
+                operation,
+                None,
+            );
         }
 
         // Fix hover after mouse move
@@ -471,6 +480,26 @@ impl PlatformDragContext {
             .ok_or(NativeExtensionsError::DragSessionNotFound)?;
         Ok(session.configuration.get_local_data())
     }
+
+    pub fn get_active_sessions(&self) -> Vec<DragSessionId> {
+        self.sessions
+            .borrow()
+            .values()
+            .map(|s| s.session_id)
+            .collect()
+    }
+
+    pub fn cancel_drag(&self, _session_id: DragSessionId) -> NativeExtensionsResult<()> {
+        Err(NativeExtensionsError::UnsupportedOperation)
+    }
+
+    pub fn update_drag_image(
+        &self,
+        _session_id: DragSessionId,
+        _image: TargettedImage,
+    ) -> NativeExtensionsResult<()> {
+        Err(NativeExtensionsError::UnsupportedOperation)
+    }
 }
 
 impl Drop for PlatformDragContext {
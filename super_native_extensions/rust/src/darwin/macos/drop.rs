@@ -104,6 +104,11 @@ impl Session {
             accepted_operation,
             items,
             reader: Some(self.registered_reader.clone()),
+            auto_scroll_velocity: None,
+            modifiers: None,
+            pointer_button: None,
+            location_on_screen: None,
+            source_drag_session_id: None,
         })
     }
 
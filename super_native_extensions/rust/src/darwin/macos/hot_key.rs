@@ -10,15 +10,16 @@ use irondash_message_channel::Late;
 use log::warn;
 
 use crate::{
-    error::NativeExtensionsResult,
+    error::{NativeExtensionsError, NativeExtensionsResult},
     hot_key_manager::{HotKeyCreateRequest, HotKeyHandle, HotKeyManagerDelegate},
 };
 
 use super::hot_key_sys::{
-    kEventClassKeyboard, kEventHotKeyPressed, kEventHotKeyReleased, kEventParamDirectObject,
-    typeEventHotKeyID, EventHandlerCallRef, EventHandlerRef, EventHotKeyID, EventHotKeyRef,
-    EventRef, EventTypeSpec, GetEventDispatcherTarget, GetEventKind, GetEventParameter,
-    InstallEventHandler, RegisterEventHotKey, RemoveEventHandler, UnregisterEventHotKey,
+    eventHotKeyExistsErr, kEventClassKeyboard, kEventHotKeyPressed, kEventHotKeyReleased,
+    kEventParamDirectObject, typeEventHotKeyID, EventHandlerCallRef, EventHandlerRef,
+    EventHotKeyID, EventHotKeyRef, EventRef, EventTypeSpec, GetEventDispatcherTarget, GetEventKind,
+    GetEventParameter, InstallEventHandler, RegisterEventHotKey, RemoveEventHandler,
+    UnregisterEventHotKey,
 };
 
 const HOT_KEY_TAG: u32 = 1314080844; // NSHL
@@ -79,18 +80,27 @@ impl PlatformHotKeyManager {
         }
     }
 
+    // Delegate may register or unregister hot keys in response so hot_keys
+    // must not be borrowed while calling it.
+    fn handle_for_id(&self, hot_key_id: u32) -> Option<HotKeyHandle> {
+        self.hot_keys
+            .borrow()
+            .get(&hot_key_id)
+            .map(|key| key.handle)
+    }
+
     fn on_hot_key_pressed(&self, hot_key_id: u32) {
-        if let Some(key) = self.hot_keys.borrow().get(&hot_key_id) {
+        if let Some(handle) = self.handle_for_id(hot_key_id) {
             if let Some(delegate) = self.delegate.upgrade() {
-                delegate.on_hot_key_pressed(key.handle);
+                delegate.on_hot_key_pressed(handle);
             }
         }
     }
 
     fn on_hot_key_released(&self, hot_key_id: u32) {
-        if let Some(key) = self.hot_keys.borrow().get(&hot_key_id) {
+        if let Some(handle) = self.handle_for_id(hot_key_id) {
             if let Some(delegate) = self.delegate.upgrade() {
-                delegate.on_hot_key_released(key.handle);
+                delegate.on_hot_key_released(handle);
             }
         }
     }
@@ -124,7 +134,7 @@ impl PlatformHotKeyManager {
 
         let mut key_ref: EventHotKeyRef = std::ptr::null_mut();
 
-        unsafe {
+        let status = unsafe {
             RegisterEventHotKey(
                 request.platform_code as u32,
                 modifiers,
@@ -132,8 +142,17 @@ impl PlatformHotKeyManager {
                 GetEventDispatcherTarget(),
                 0,
                 &mut key_ref as *mut _,
-            );
+            )
         };
+        match status {
+            0 => {}
+            eventHotKeyExistsErr => return Err(NativeExtensionsError::HotKeyAlreadyRegistered),
+            status => {
+                return Err(NativeExtensionsError::OtherError(format!(
+                    "Failed to register hot key: {status}"
+                )))
+            }
+        }
 
         let key = HotKey { handle, key_ref };
 
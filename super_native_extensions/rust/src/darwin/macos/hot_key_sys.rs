@@ -81,6 +81,8 @@ extern "C" {
         outRef: *mut EventHotKeyRef,
     ) -> OSStatus;
 }
+#[allow(non_upper_case_globals)]
+pub const eventHotKeyExistsErr: OSStatus = -9878;
 extern "C" {
     pub fn UnregisterEventHotKey(inHotKey: EventHotKeyRef) -> OSStatus;
 }
@@ -80,6 +80,11 @@ impl PlatformKeyboardLayout {
                 logical_alt: None,
                 logical_alt_shift: None,
                 logical_meta: None,
+                is_dead_key: false,
+                dead_key: None,
+                dead_key_shift: None,
+                dead_key_alt: None,
+                dead_key_alt_shift: None,
             },
             None => {
                 let mut logical_key = None::<i64>;
@@ -204,6 +209,11 @@ impl PlatformKeyboardLayout {
                     logical_alt: logical_key_alt,
                     logical_alt_shift: logical_key_alt_shift,
                     logical_meta: logical_key_cmd,
+                    is_dead_key: false,
+                    dead_key: None,
+                    dead_key_shift: None,
+                    dead_key_alt: None,
+                    dead_key_alt_shift: None,
                 }
             }
         }
@@ -31,8 +31,11 @@ use objc2_foundation::{
 
 use crate::{
     error::{NativeExtensionsError, NativeExtensionsResult},
+    format_conversion::FormatConversion,
     log::OkLog,
-    platform_impl::platform::common::{format_from_url, path_from_url, uti_conforms_to},
+    platform_impl::platform::common::{
+        format_from_url, pasteboard_format_conversions, path_from_url, uti_conforms_to,
+    },
     reader_manager::{ReadProgress, VirtualFileReader},
 };
 
@@ -184,6 +187,10 @@ impl PlatformDataReader {
         has_tiff && !has_png
     }
 
+    pub fn format_conversions() -> Vec<FormatConversion> {
+        pasteboard_format_conversions()
+    }
+
     pub fn item_format_is_synthesized(
         &self,
         item: i64,
@@ -335,7 +342,7 @@ impl PlatformDataReader {
     fn schedule_do_get_data_for_item(
         item: Id<NSPasteboardItem>,
         data_type: String,
-        completer: FutureCompleter<Value>,
+        completer: FutureCompleter<NativeExtensionsResult<Value>>,
     ) {
         RunLoop::current()
             .schedule_next(move || {
@@ -346,41 +353,43 @@ impl PlatformDataReader {
                     Self::schedule_do_get_data_for_item(item.clone(), data_type, completer);
                     return;
                 }
-                let data = autoreleasepool(|_| unsafe {
-                    let pasteboard_item = item;
-                    let is_file_url = data_type == "public.file-url";
-                    let is_text = uti_conforms_to(&data_type, "public.text");
-                    let data_type = NSString::from_str(&data_type);
-                    // Try to get property list first, otherwise fallback to Data
-                    let mut data: Option<Id<NSObject>> = if is_text || is_file_url {
-                        pasteboard_item
-                            .stringForType(&data_type)
-                            .map(|i| Id::cast(i))
-                    } else {
-                        pasteboard_item
-                            .propertyListForType(&data_type)
-                            .map(|i| Id::cast(i))
-                    };
-                    if data.is_none() {
-                        // Ask for data here. It's better for Appkit to convert String to data,
-                        // then trying to convert data to String.
-                        data = pasteboard_item.dataForType(&data_type).map(|i| Id::cast(i));
-                    }
-                    let res = Value::from_objc(data).ok_log().unwrap_or_default();
-                    // Convert file:///.file/id=??? URLs to path URL
-                    if is_file_url {
-                        if let Value::String(url) = &res {
-                            let url = NSURL::URLWithString(&NSString::from_str(url));
-                            let url = url.and_then(|url| url.filePathURL());
-                            if let Some(url) = url {
-                                let string = url.absoluteString().unwrap();
-                                return Value::String(string.to_string());
+                let data = PlatformDataProvider::read_pasteboard(|| {
+                    autoreleasepool(|_| unsafe {
+                        let pasteboard_item = item;
+                        let is_file_url = data_type == "public.file-url";
+                        let is_text = uti_conforms_to(&data_type, "public.text");
+                        let data_type = NSString::from_str(&data_type);
+                        // Try to get property list first, otherwise fallback to Data
+                        let mut data: Option<Id<NSObject>> = if is_text || is_file_url {
+                            pasteboard_item
+                                .stringForType(&data_type)
+                                .map(|i| Id::cast(i))
+                        } else {
+                            pasteboard_item
+                                .propertyListForType(&data_type)
+                                .map(|i| Id::cast(i))
+                        };
+                        if data.is_none() {
+                            // Ask for data here. It's better for Appkit to convert String to data,
+                            // then trying to convert data to String.
+                            data = pasteboard_item.dataForType(&data_type).map(|i| Id::cast(i));
+                        }
+                        let res = Value::from_objc(data).ok_log().unwrap_or_default();
+                        // Convert file:///.file/id=??? URLs to path URL
+                        if is_file_url {
+                            if let Value::String(url) = &res {
+                                let url = NSURL::URLWithString(&NSString::from_str(url));
+                                let url = url.and_then(|url| url.filePathURL());
+                                if let Some(url) = url {
+                                    let string = url.absoluteString().unwrap();
+                                    return Value::String(string.to_string());
+                                }
                             }
                         }
-                    }
-                    res
+                        res
+                    })
                 });
-                completer.complete(data)
+                completer.complete(data);
             })
             .detach();
     }
@@ -407,7 +416,7 @@ impl PlatformDataReader {
         let pasteboard_item = unsafe { self.get_pasteboard_items()?.objectAtIndex(item as usize) };
         Self::schedule_do_get_data_for_item(pasteboard_item, data_type, completer);
 
-        let res = future.await;
+        let res = future.await?;
         let mut value_cache = self.value_cache.borrow_mut();
         value_cache.insert(cache_key, res.clone());
         Ok(res)
@@ -5,6 +5,7 @@ use std::{
     rc::{Rc, Weak},
     slice,
     sync::Arc,
+    time::Duration,
 };
 
 use async_trait::async_trait;
@@ -12,16 +13,17 @@ use irondash_message_channel::{
     AsyncMethodHandler, AsyncMethodInvoker, IntoPlatformResult, IntoValue, IsolateId, Late,
     MethodCall, PlatformError, PlatformResult, RegisteredAsyncMethodHandler, TryFromValue, Value,
 };
-use irondash_run_loop::spawn;
+use irondash_run_loop::{spawn, util::FutureCompleter, RunLoop};
 
 use crate::{
-    api_model::{DataProvider, DataProviderId, DataProviderValueId},
+    api_model::{DataProvider, DataProviderId, DataProviderValueId, DataRepresentation},
     context::Context,
     error::{NativeExtensionsError, NativeExtensionsResult},
+    lazy_data_cache::{LazyDataCache, LazyDataCacheConfiguration},
     log::OkLog,
     platform_impl::platform::{platform_stream_close, platform_stream_write, PlatformDataProvider},
     util::{DropNotifier, NextId},
-    value_promise::{ValuePromise, ValuePromiseResult, ValuePromiseSetCancel},
+    value_promise::{ValuePromise, ValuePromiseResult},
 };
 
 pub enum VirtualFileResult {
@@ -52,6 +54,11 @@ impl From<DropNotifier> for DataProviderHandle {
 
 #[async_trait(?Send)]
 pub trait PlatformDataProviderDelegate {
+    /// Requests lazy data. If the value is not provided within the timeout
+    /// the promise is resolved with [`ValuePromiseResult::TimedOut`] and Dart
+    /// is notified that the request was cancelled. The provider timeout is
+    /// used, falling back to the timeout set through `setLazyDataTimeout`.
+    /// There is no timeout by default.
     fn get_lazy_data(
         &self,
         isolate_id: IsolateId,
@@ -82,6 +89,8 @@ pub struct DataProviderManager {
     next_id: Cell<i64>,
     providers: RefCell<HashMap<DataProviderId, DataProviderEntry>>,
     virtual_sessions: RefCell<HashMap<VirtualSessionId, VirtualFileSession>>,
+    lazy_data_timeout: Cell<Option<Duration>>,
+    lazy_data_cache: RefCell<LazyDataCache>,
 }
 
 pub trait GetDataProviderManager {
@@ -97,6 +106,17 @@ impl GetDataProviderManager for Context {
 struct DataProviderEntry {
     isolate_id: IsolateId,
     platform_data_provider: Rc<PlatformDataProvider>,
+    lazy_data_timeout: Option<Duration>,
+    lazy_values: Vec<DataProviderValueId>,
+}
+
+#[derive(Debug, TryFromValue, IntoValue, Clone, Copy, PartialEq, Hash, Eq)]
+struct LazyDataRequestId(i64);
+
+impl From<i64> for LazyDataRequestId {
+    fn from(value: i64) -> Self {
+        Self(value)
+    }
 }
 
 #[derive(Debug, TryFromValue, IntoValue, Clone, Copy, PartialEq, Hash, Eq)]
@@ -124,6 +144,10 @@ impl DataProviderManager {
             next_id: Cell::new(1),
             providers: RefCell::new(HashMap::new()),
             virtual_sessions: RefCell::new(HashMap::new()),
+            lazy_data_timeout: Cell::new(None),
+            lazy_data_cache: RefCell::new(
+                LazyDataCache::new(LazyDataCacheConfiguration::default()),
+            ),
         }
         .register("DataProviderManager")
     }
@@ -144,6 +168,17 @@ impl DataProviderManager {
         source: DataProvider,
         isolate_id: IsolateId,
     ) -> NativeExtensionsResult<DataProviderId> {
+        let lazy_data_timeout = source
+            .lazy_data_timeout_millis
+            .map(|millis| Duration::from_millis(millis.max(0) as u64));
+        let lazy_values = source
+            .representations
+            .iter()
+            .filter_map(|r| match r {
+                DataRepresentation::Lazy { id, format: _ } => Some(*id),
+                _ => None,
+            })
+            .collect();
         let platform_data_source = Rc::new(PlatformDataProvider::new(
             self.weak_self.clone(),
             isolate_id,
@@ -156,13 +191,117 @@ impl DataProviderManager {
             DataProviderEntry {
                 isolate_id,
                 platform_data_provider: platform_data_source,
+                lazy_data_timeout,
+                lazy_values,
             },
         );
         Ok(id)
     }
 
+    fn timeout_for_lazy_data(
+        &self,
+        isolate_id: IsolateId,
+        data_id: DataProviderValueId,
+    ) -> Option<Duration> {
+        self.providers
+            .borrow()
+            .values()
+            .find(|e| e.isolate_id == isolate_id && e.lazy_values.contains(&data_id))
+            .and_then(|e| e.lazy_data_timeout)
+            .or(self.lazy_data_timeout.get())
+    }
+
+    async fn request_lazy_data(
+        &self,
+        isolate_id: IsolateId,
+        value_id: DataProviderValueId,
+        request_id: LazyDataRequestId,
+    ) -> ValuePromiseResult {
+        #[derive(IntoValue)]
+        #[irondash(rename_all = "camelCase")]
+        struct LazyDataRequest {
+            value_id: DataProviderValueId,
+            request_id: LazyDataRequestId,
+        }
+
+        let res = self
+            .invoker
+            .call_method_cv(
+                isolate_id,
+                "getLazyData",
+                LazyDataRequest {
+                    value_id,
+                    request_id,
+                },
+            )
+            .await;
+        match res {
+            Ok(res) => res,
+            Err(_) => ValuePromiseResult::Cancelled,
+        }
+    }
+
+    fn cancel_lazy_data(&self, isolate_id: IsolateId, request_id: LazyDataRequestId) {
+        #[derive(IntoValue)]
+        #[irondash(rename_all = "camelCase")]
+        struct LazyDataCancelRequest {
+            request_id: LazyDataRequestId,
+        }
+
+        self.invoker.call_method_sync(
+            isolate_id,
+            "cancelLazyData",
+            LazyDataCancelRequest { request_id },
+            |r| {
+                r.ok_log();
+            },
+        );
+    }
+
     fn unregister_provider(&self, source: DataProviderId) -> NativeExtensionsResult<()> {
-        self.providers.borrow_mut().remove(&source);
+        let entry = self.providers.borrow_mut().remove(&source);
+        if let Some(entry) = entry {
+            self.invalidate_cache(&entry);
+        }
+        Ok(())
+    }
+
+    fn invalidate_cache(&self, entry: &DataProviderEntry) {
+        let mut cache = self.lazy_data_cache.borrow_mut();
+        for id in &entry.lazy_values {
+            cache.remove(entry.isolate_id, *id);
+        }
+    }
+
+    fn is_lazy_value_registered(
+        &self,
+        isolate_id: IsolateId,
+        data_id: DataProviderValueId,
+    ) -> bool {
+        self.providers
+            .borrow()
+            .values()
+            .any(|e| e.isolate_id == isolate_id && e.lazy_values.contains(&data_id))
+    }
+
+    fn set_lazy_data_cache_configuration(
+        &self,
+        request: LazyDataCacheConfigurationRequest,
+    ) -> NativeExtensionsResult<()> {
+        self.lazy_data_cache
+            .borrow_mut()
+            .set_configuration(LazyDataCacheConfiguration {
+                max_memory_usage: request.max_memory_usage.max(0) as usize,
+                max_disk_usage: request.max_disk_usage.map(|s| s.max(0) as usize),
+            });
+        Ok(())
+    }
+
+    fn set_lazy_data_timeout(&self, request: LazyDataTimeoutRequest) -> NativeExtensionsResult<()> {
+        let timeout = request
+            .timeout_millis
+            .map(|millis| Duration::from_millis(millis.max(0) as u64));
+        self.lazy_data_timeout.set(timeout);
         Ok(())
     }
 
@@ -242,19 +381,57 @@ impl PlatformDataProviderDelegate for DataProviderManager {
         on_done: Option<Box<dyn FnOnce()>>,
     ) -> Arc<ValuePromise> {
         let res = Arc::new(ValuePromise::new());
-        let res_clone = res.clone();
+        let completion = Rc::new(LazyDataCompletion {
+            promise: res.clone(),
+            on_done: RefCell::new(on_done),
+            completed: Cell::new(false),
+        });
+        let cached = self.lazy_data_cache.borrow_mut().get(isolate_id, data_id);
+        if let Some(value) = cached {
+            // Callers expect the promise to be resolved asynchronously.
+            spawn(async move {
+                completion.complete(ValuePromiseResult::Ok { value });
+            });
+            return res;
+        }
+        let request_id: LazyDataRequestId = self.next_id.next_id().into();
+        // Whichever comes first (response or timeout) resolves the promise.
+        let timer = self
+            .timeout_for_lazy_data(isolate_id, data_id)
+            .map(|timeout| {
+                let completion = completion.clone();
+                let weak_self = self.weak_self.clone();
+                RunLoop::current().schedule(timeout, move || {
+                    if completion.complete(ValuePromiseResult::TimedOut) {
+                        if let Some(this) = weak_self.upgrade() {
+                            this.cancel_lazy_data(isolate_id, request_id);
+                        }
+                    }
+                })
+            });
         let weak_self = self.weak_self.clone();
         spawn(async move {
             let this = weak_self.upgrade();
-            if let Some(this) = this {
-                let res = this.get_lazy_data_async(isolate_id, data_id).await;
-                res_clone.set(res);
-                if let Some(on_done) = on_done {
-                    on_done();
+            let res = if let Some(this) = this {
+                let res = this
+                    .request_lazy_data(isolate_id, data_id, request_id)
+                    .await;
+                if let ValuePromiseResult::Ok { value } = &res {
+                    // Provider might have been unregistered in the meanwhile.
+                    if this.is_lazy_value_registered(isolate_id, data_id) {
+                        this.lazy_data_cache.borrow_mut().insert(
+                            isolate_id,
+                            data_id,
+                            value.clone(),
+                        );
+                    }
                 }
+                res
             } else {
-                res_clone.cancel();
-            }
+                ValuePromiseResult::Cancelled
+            };
+            drop(timer);
+            completion.complete(res);
         });
         res
     }
@@ -264,20 +441,14 @@ impl PlatformDataProviderDelegate for DataProviderManager {
         isolate_id: IsolateId,
         value_id: DataProviderValueId,
     ) -> ValuePromiseResult {
-        #[derive(IntoValue)]
-        #[irondash(rename_all = "camelCase")]
-        struct LazyDataRequest {
-            value_id: DataProviderValueId,
-        }
-
-        let res = self
-            .invoker
-            .call_method_cv(isolate_id, "getLazyData", LazyDataRequest { value_id })
-            .await;
-        match res {
-            Ok(res) => res,
-            Err(_) => ValuePromiseResult::Cancelled,
-        }
+        let (future, completer) = FutureCompleter::new();
+        let promise = self.get_lazy_data(
+            isolate_id,
+            value_id,
+            Some(Box::new(move || completer.complete(()))),
+        );
+        future.await;
+        promise.try_take().unwrap_or(ValuePromiseResult::Cancelled)
     }
 
     fn get_virtual_file(
@@ -331,6 +502,40 @@ impl PlatformDataProviderDelegate for DataProviderManager {
     }
 }
 
+#[derive(Debug, TryFromValue)]
+#[irondash(rename_all = "camelCase")]
+struct LazyDataCacheConfigurationRequest {
+    max_memory_usage: i64,
+    max_disk_usage: Option<i64>,
+}
+
+struct LazyDataCompletion {
+    promise: Arc<ValuePromise>,
+    on_done: RefCell<Option<Box<dyn FnOnce()>>>,
+    completed: Cell<bool>,
+}
+
+impl LazyDataCompletion {
+    /// Resolves the promise unless it has already been resolved. Returns
+    /// whether the result was used.
+    fn complete(&self, result: ValuePromiseResult) -> bool {
+        if self.completed.replace(true) {
+            return false;
+        }
+        self.promise.set(result);
+        if let Some(on_done) = self.on_done.take() {
+            on_done();
+        }
+        true
+    }
+}
+
+#[derive(Debug, TryFromValue)]
+#[irondash(rename_all = "camelCase")]
+struct LazyDataTimeoutRequest {
+    timeout_millis: Option<i64>,
+}
+
 #[derive(Debug, TryFromValue)]
 #[irondash(rename_all = "camelCase")]
 struct VirtualFileUpdateProgress {
@@ -374,6 +579,12 @@ impl AsyncMethodHandler for DataProviderManager {
             "unregisterDataProvider" => self
                 .unregister_provider(call.args.try_into()?)
                 .into_platform_result(),
+            "setLazyDataTimeout" => self
+                .set_lazy_data_timeout(call.args.try_into()?)
+                .into_platform_result(),
+            "setLazyDataCacheConfiguration" => self
+                .set_lazy_data_cache_configuration(call.args.try_into()?)
+                .into_platform_result(),
             "virtualFileUpdateProgress" => self
                 .virtual_file_update_progress(call.args.try_into()?)
                 .into_platform_result(),
@@ -421,6 +632,7 @@ impl AsyncMethodHandler for DataProviderManager {
         for source_id in providers_to_remove {
             providers.remove(&source_id);
         }
+        self.lazy_data_cache.borrow_mut().remove_isolate(isolate_id);
 
         let sessions_to_remove: Vec<_> = {
             self.virtual_sessions
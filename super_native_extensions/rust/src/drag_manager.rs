@@ -15,7 +15,10 @@ use irondash_run_loop::spawn;
 use log::warn;
 
 use crate::{
-    api_model::{DataProviderId, DragConfiguration, DragItem, DragRequest, DropOperation, Point},
+    api_model::{
+        DataProviderId, DragConfiguration, DragItem, DragRequest, DragStartMode, DropOperation,
+        Point, TargettedImage,
+    },
     context::Context,
     data_provider_manager::{DataProviderHandle, GetDataProviderManager},
     drop_manager::GetDropManager,
@@ -48,6 +51,21 @@ pub struct GetAdditionalItemsResult {
     pub providers: HashMap<DataProviderId, DataProviderEntry>,
 }
 
+/// Platform specific identifier of native drag operation that is the same
+/// on source and destination side, i.e. address of drag source window on
+/// Linux.
+pub type NativeDragId = i64;
+
+/// Drag session started in this process. Registered with [`DragManager`]
+/// so that drop contexts of any engine can find out which session and
+/// isolate the dragged data belongs to.
+#[derive(Clone, Debug)]
+pub struct LocalDragSession {
+    pub session_id: DragSessionId,
+    pub source_isolate: PlatformDragContextId,
+    pub local_data: Vec<Value>,
+}
+
 pub trait PlatformDragContextDelegate {
     fn get_platform_drop_contexts(&self) -> Vec<Rc<PlatformDropContext>>;
 
@@ -84,7 +102,15 @@ pub trait PlatformDragContextDelegate {
         id: PlatformDragContextId,
         session_id: DragSessionId,
         operation: DropOperation,
+        destination: Option<DropDestination>,
     );
+
+    /// Registers or updates local drag session for given native drag.
+    fn register_local_drag_session(&self, native_id: NativeDragId, session: LocalDragSession);
+
+    /// Unregisters local drag session for given native drag, unless the
+    /// native drag has since been registered by another session.
+    fn unregister_local_drag_session(&self, native_id: NativeDragId, session_id: DragSessionId);
 }
 
 #[derive(Debug, TryFromValue, IntoValue, Clone, Copy, PartialEq, Hash, Eq)]
@@ -102,11 +128,33 @@ impl From<DragSessionId> for i64 {
     }
 }
 
+#[derive(IntoValue, Debug, Clone, Copy, PartialEq, Eq)]
+#[irondash(rename_all = "camelCase")]
+pub enum DropDestinationKind {
+    /// Target consumed items as URI list (i.e. file manager or desktop).
+    UriList,
+    /// Items were dropped on trash.
+    Trash,
+    /// Target asked for items to be saved into a directory (XDS).
+    DirectSave,
+}
+
+/// Destination of drag that ended outside of the application, reported
+/// on platforms that can determine it.
+#[derive(IntoValue, Debug, Clone, PartialEq)]
+#[irondash(rename_all = "camelCase")]
+pub struct DropDestination {
+    pub kind: DropDestinationKind,
+    /// File paths reported by or provided to the target.
+    pub file_paths: Vec<String>,
+}
+
 pub struct DragManager {
     weak_self: Late<Weak<Self>>,
     invoker: Late<AsyncMethodInvoker>,
     contexts: RefCell<HashMap<PlatformDragContextId, Rc<PlatformDragContext>>>,
     next_session_id: Cell<i64>,
+    local_sessions: RefCell<HashMap<NativeDragId, LocalDragSession>>,
 }
 
 pub trait GetDragManager {
@@ -131,6 +179,21 @@ pub struct LocalDataRequest {
     session_id: DragSessionId,
 }
 
+#[derive(TryFromValue)]
+#[irondash(rename_all = "camelCase")]
+struct CancelDragRequest {
+    session_id: DragSessionId,
+}
+
+#[derive(TryFromValue)]
+#[irondash(rename_all = "camelCase")]
+struct UpdateDragImageRequest {
+    session_id: DragSessionId,
+    /// Positioned relative to the location where drag started, same as
+    /// images of dragged items.
+    image: TargettedImage,
+}
+
 impl DragManager {
     pub fn new() -> RegisteredAsyncMethodHandler<Self> {
         Self {
@@ -138,6 +201,7 @@ impl DragManager {
             invoker: Late::new(),
             contexts: RefCell::new(HashMap::new()),
             next_session_id: Cell::new(0),
+            local_sessions: RefCell::new(HashMap::new()),
         }
         .register("DragManager")
     }
@@ -166,6 +230,21 @@ impl DragManager {
         self.contexts.borrow().values().cloned().collect()
     }
 
+    /// Returns local drag session for native drag. If platform can not
+    /// determine the native drag (`native_id` is None) the only active local
+    /// session is returned, if any.
+    pub fn get_local_drag_session(
+        &self,
+        native_id: Option<NativeDragId>,
+    ) -> Option<LocalDragSession> {
+        let sessions = self.local_sessions.borrow();
+        match native_id {
+            Some(native_id) => sessions.get(&native_id).cloned(),
+            None if sessions.len() == 1 => sessions.values().next().cloned(),
+            None => None,
+        }
+    }
+
     fn build_data_provider_map(
         &self,
         isolate: IsolateId,
@@ -304,6 +383,11 @@ impl DragManager {
         isolate: IsolateId,
         request: DragRequest,
     ) -> NativeExtensionsResult<DragSessionId> {
+        if request.start_mode == DragStartMode::Keyboard
+            && !PlatformDragContext::supports_keyboard_drag()
+        {
+            return Err(NativeExtensionsError::UnsupportedOperation);
+        }
         let context = self
             .contexts
             .borrow()
@@ -336,6 +420,47 @@ impl DragManager {
         }
     }
 
+    fn update_drag_image(
+        &self,
+        isolate: IsolateId,
+        request: UpdateDragImageRequest,
+    ) -> NativeExtensionsResult<()> {
+        let context = self
+            .contexts
+            .borrow()
+            .get(&isolate)
+            .cloned()
+            .ok_or(NativeExtensionsError::PlatformContextNotFound)?;
+        context.update_drag_image(request.session_id, request.image)
+    }
+
+    fn cancel_drag(
+        &self,
+        isolate: IsolateId,
+        request: CancelDragRequest,
+    ) -> NativeExtensionsResult<()> {
+        let context = self
+            .contexts
+            .borrow()
+            .get(&isolate)
+            .cloned()
+            .ok_or(NativeExtensionsError::PlatformContextNotFound)?;
+        context.cancel_drag(request.session_id)
+    }
+
+    fn get_active_drag_sessions(
+        &self,
+        isolate: IsolateId,
+    ) -> NativeExtensionsResult<Vec<DragSessionId>> {
+        let context = self
+            .contexts
+            .borrow()
+            .get(&isolate)
+            .cloned()
+            .ok_or(NativeExtensionsError::PlatformContextNotFound)?;
+        Ok(context.get_active_sessions())
+    }
+
     fn release_data_provider(&self, isolate_id: IsolateId, provider_id: DataProviderId) {
         self.invoker
             .call_method_sync(isolate_id, "releaseDataProvider", provider_id, |r| {
@@ -372,12 +497,24 @@ impl AsyncMethodHandler for DragManager {
             "getLocalData" => self
                 .get_local_data(call.isolate, call.args.try_into()?)
                 .into_platform_result(),
+            "updateDragImage" => self
+                .update_drag_image(call.isolate, call.args.try_into()?)
+                .into_platform_result(),
+            "cancelDrag" => self
+                .cancel_drag(call.isolate, call.args.try_into()?)
+                .into_platform_result(),
+            "getActiveDragSessions" => self
+                .get_active_drag_sessions(call.isolate)
+                .into_platform_result(),
             _ => Ok(Value::Null),
         }
     }
 
     fn on_isolate_destroyed(&self, isolate: IsolateId) {
         self.contexts.borrow_mut().remove(&isolate);
+        self.local_sessions
+            .borrow_mut()
+            .retain(|_, s| s.source_isolate != isolate);
     }
 }
 
@@ -510,12 +647,14 @@ impl PlatformDragContextDelegate for DragManager {
         id: PlatformDragContextId,
         session_id: DragSessionId,
         operation: DropOperation,
+        destination: Option<DropDestination>,
     ) {
         #[derive(IntoValue)]
         #[irondash(rename_all = "camelCase")]
         struct DragEndRequest {
             session_id: DragSessionId,
             drop_operation: DropOperation,
+            destination: Option<DropDestination>,
         }
 
         self.invoker.call_method_sync(
@@ -524,10 +663,22 @@ impl PlatformDragContextDelegate for DragManager {
             DragEndRequest {
                 session_id,
                 drop_operation: operation,
+                destination,
             },
             |r| {
                 r.ok_log();
             },
         );
     }
+
+    fn register_local_drag_session(&self, native_id: NativeDragId, session: LocalDragSession) {
+        self.local_sessions.borrow_mut().insert(native_id, session);
+    }
+
+    fn unregister_local_drag_session(&self, native_id: NativeDragId, session_id: DragSessionId) {
+        let mut sessions = self.local_sessions.borrow_mut();
+        if sessions.get(&native_id).map(|s| s.session_id) == Some(session_id) {
+            sessions.remove(&native_id);
+        }
+    }
 }
@@ -15,12 +15,16 @@ use log::warn;
 
 use crate::{
     api_model::{DropOperation, ImageData, Point, Rect, Size},
+    auto_scroll::{AutoScroll, AutoScrollRegion, AUTO_SCROLL_INTERVAL},
     context::Context,
-    drag_manager::{GetDragManager, PlatformDragContextId},
+    drag_manager::{
+        DragSessionId, GetDragManager, LocalDragSession, NativeDragId, PlatformDragContextId,
+    },
     error::{NativeExtensionsError, NativeExtensionsResult},
     log::{OkLog, OkLogUnexpected},
     platform_impl::platform::{PlatformDataReader, PlatformDragContext, PlatformDropContext},
     reader_manager::{GetDataReaderManager, RegisteredDataReader},
+    spring_loading::{SpringLoadEvent, SpringLoadTracker, SpringLoadingConfiguration},
     value_promise::{Promise, PromiseResult},
 };
 
@@ -31,6 +35,8 @@ pub struct DropManager {
     weak_self: Late<Weak<Self>>,
     invoker: Late<AsyncMethodInvoker>,
     contexts: RefCell<HashMap<PlatformDropContextId, Rc<PlatformDropContext>>>,
+    spring_loading: RefCell<HashMap<PlatformDropContextId, SpringLoadTracker>>,
+    auto_scroll: RefCell<HashMap<PlatformDropContextId, AutoScroll>>,
 }
 
 pub trait GetDropManager {
@@ -55,6 +61,12 @@ struct RegisterDropFormatsRequest {
     formats: Vec<String>,
 }
 
+#[derive(TryFromValue)]
+#[irondash(rename_all = "camelCase")]
+struct SetAutoScrollRegionsRequest {
+    regions: Vec<AutoScrollRegion>,
+}
+
 #[derive(Debug, TryFromValue, IntoValue, Clone, Copy, PartialEq, Hash, Eq)]
 pub struct DropSessionId(i64);
 
@@ -79,7 +91,7 @@ impl From<i64> for DropItemId {
     }
 }
 
-#[derive(IntoValue, Debug)]
+#[derive(IntoValue, Debug, Clone)]
 #[irondash(rename_all = "camelCase")]
 pub struct DropItem {
     pub item_id: DropItemId, // unique ID within session, consistent between events
@@ -87,7 +99,16 @@ pub struct DropItem {
     pub local_data: Value,
 }
 
-#[derive(IntoValue, Debug)]
+#[derive(IntoValue, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[irondash(rename_all = "camelCase")]
+pub struct DropModifiers {
+    pub shift: bool,
+    pub alt: bool,
+    pub control: bool,
+    pub meta: bool,
+}
+
+#[derive(IntoValue, Debug, Clone)]
 #[irondash(rename_all = "camelCase")]
 pub struct DropEvent {
     pub session_id: DropSessionId,
@@ -96,6 +117,17 @@ pub struct DropEvent {
     pub accepted_operation: Option<DropOperation>,
     pub items: Vec<DropItem>,
     pub reader: Option<RegisteredDataReader>,
+    /// Set by DropManager when location is within edge band of registered
+    /// auto-scroll region.
+    pub auto_scroll_velocity: Option<Point>,
+    /// Keyboard modifiers pressed during the event (if known).
+    pub modifiers: Option<DropModifiers>,
+    /// Pressed pointer button, 1 being the primary button (if known).
+    pub pointer_button: Option<i64>,
+    /// Pointer location in screen coordinates (if known).
+    pub location_on_screen: Option<Point>,
+    /// Drag session that started the drag if it originated in this process.
+    pub source_drag_session_id: Option<DragSessionId>,
 }
 
 #[derive(IntoValue, Debug)]
@@ -132,6 +164,8 @@ pub struct ItemPreviewResponse {
 pub trait PlatformDropContextDelegate {
     fn get_platform_drag_contexts(&self) -> Vec<Rc<PlatformDragContext>>;
 
+    fn get_local_drag_session(&self, native_id: Option<NativeDragId>) -> Option<LocalDragSession>;
+
     fn send_drop_update(
         &self,
         id: PlatformDropContextId,
@@ -161,6 +195,10 @@ pub trait PlatformDropContextDelegate {
         id: PlatformDropContextId,
         request: ItemPreviewRequest,
     ) -> Arc<Promise<PromiseResult<ItemPreviewResponse>>>;
+
+    /// Sends spring-load event for current location immediately, i.e. when
+    /// user presses a key during drag.
+    fn force_spring_load(&self, id: PlatformDropContextId, session_id: DropSessionId);
 }
 
 impl DropManager {
@@ -169,6 +207,8 @@ impl DropManager {
             weak_self: Late::new(),
             invoker: Late::new(),
             contexts: RefCell::new(HashMap::new()),
+            spring_loading: RefCell::new(HashMap::new()),
+            auto_scroll: RefCell::new(HashMap::new()),
         }
         .register("DropManager")
     }
@@ -207,6 +247,133 @@ impl DropManager {
         Ok(())
     }
 
+    fn set_spring_loading_configuration(
+        &self,
+        isolate: IsolateId,
+        configuration: SpringLoadingConfiguration,
+    ) -> NativeExtensionsResult<()> {
+        self.spring_loading
+            .borrow_mut()
+            .entry(isolate)
+            .or_insert_with(|| SpringLoadTracker::new(Default::default()))
+            .set_configuration(configuration);
+        Ok(())
+    }
+
+    fn with_spring_load_tracker<R>(
+        &self,
+        id: PlatformDropContextId,
+        f: impl FnOnce(&mut SpringLoadTracker) -> R,
+    ) -> R {
+        let mut spring_loading = self.spring_loading.borrow_mut();
+        let tracker = spring_loading
+            .entry(id)
+            .or_insert_with(|| SpringLoadTracker::new(Default::default()));
+        f(tracker)
+    }
+
+    /// Schedules spring-load timer if the pointer entered new region.
+    fn update_spring_loading(&self, id: PlatformDropContextId, event: &DropEvent) {
+        let scheduled = self.with_spring_load_tracker(id, |t| {
+            t.update(event.session_id, event.location_in_view.clone())
+                .map(|generation| (generation, t.delay()))
+        });
+        if let Some((generation, delay)) = scheduled {
+            let weak_self = self.weak_self.clone();
+            RunLoop::current()
+                .schedule(delay, move || {
+                    if let Some(this) = weak_self.upgrade() {
+                        let event =
+                            this.with_spring_load_tracker(id, |t| t.timer_fired(generation));
+                        if let Some(event) = event {
+                            this.send_spring_load(id, event);
+                        }
+                    }
+                })
+                .detach();
+        }
+    }
+
+    fn reset_spring_loading(&self, id: PlatformDropContextId) {
+        self.with_spring_load_tracker(id, |t| t.reset());
+    }
+
+    fn send_spring_load(&self, id: PlatformDropContextId, event: SpringLoadEvent) {
+        self.invoker
+            .call_method_sync(id, "onSpringLoad", event, |r| {
+                r.ok_log();
+            });
+    }
+
+    fn set_auto_scroll_regions(
+        &self,
+        isolate: IsolateId,
+        request: SetAutoScrollRegionsRequest,
+    ) -> NativeExtensionsResult<()> {
+        self.auto_scroll
+            .borrow_mut()
+            .entry(isolate)
+            .or_default()
+            .set_regions(request.regions);
+        Ok(())
+    }
+
+    fn with_auto_scroll<R>(
+        &self,
+        id: PlatformDropContextId,
+        f: impl FnOnce(&mut AutoScroll) -> R,
+    ) -> R {
+        f(self.auto_scroll.borrow_mut().entry(id).or_default())
+    }
+
+    /// Fills auto-scroll velocity of the event and starts timer for synthetic
+    /// updates if the pointer entered an edge band.
+    fn update_auto_scroll(&self, id: PlatformDropContextId, event: &mut DropEvent) {
+        let start_timer = self.with_auto_scroll(id, |a| {
+            let start_timer = a.update(event);
+            if start_timer {
+                a.timer_scheduled = true;
+            }
+            start_timer
+        });
+        if start_timer {
+            self.schedule_auto_scroll(id);
+        }
+    }
+
+    fn schedule_auto_scroll(&self, id: PlatformDropContextId) {
+        let weak_self = self.weak_self.clone();
+        RunLoop::current()
+            .schedule(AUTO_SCROLL_INTERVAL, move || {
+                if let Some(this) = weak_self.upgrade() {
+                    this.on_auto_scroll_timer(id);
+                }
+            })
+            .detach();
+    }
+
+    fn on_auto_scroll_timer(&self, id: PlatformDropContextId) {
+        let (active, event) = self.with_auto_scroll(id, |a| {
+            a.timer_scheduled = a.is_active();
+            (a.is_active(), a.synthetic_event())
+        });
+        if let Some(event) = event {
+            // Result is ignored; operation is updated with next platform
+            // drop update.
+            self.invoker
+                .call_method_sync(id, "onDropUpdate", event, |r| {
+                    r.ok_log();
+                });
+        }
+        if active {
+            self.schedule_auto_scroll(id);
+        }
+    }
+
+    fn reset_auto_scroll(&self, id: PlatformDropContextId) {
+        self.with_auto_scroll(id, |a| a.reset());
+    }
+
     pub fn get_platform_drop_contexts(&self) -> Vec<Rc<PlatformDropContext>> {
         self.contexts.borrow().values().cloned().collect()
     }
@@ -243,12 +410,20 @@ impl AsyncMethodHandler for DropManager {
             "registerDropFormats" => self
                 .register_drop_formats(call.isolate, call.args.try_into()?)
                 .into_platform_result(),
+            "setSpringLoadingConfiguration" => self
+                .set_spring_loading_configuration(call.isolate, call.args.try_into()?)
+                .into_platform_result(),
+            "setAutoScrollRegions" => self
+                .set_auto_scroll_regions(call.isolate, call.args.try_into()?)
+                .into_platform_result(),
             _ => Ok(Value::Null),
         }
     }
 
     fn on_isolate_destroyed(&self, isolate: IsolateId) {
         self.contexts.borrow_mut().remove(&isolate);
+        self.spring_loading.borrow_mut().remove(&isolate);
+        self.auto_scroll.borrow_mut().remove(&isolate);
     }
 }
 
@@ -257,14 +432,35 @@ impl PlatformDropContextDelegate for DropManager {
         Context::get().drag_manager().get_platform_drag_contexts()
     }
 
+    fn get_local_drag_session(&self, native_id: Option<NativeDragId>) -> Option<LocalDragSession> {
+        Context::get()
+            .drag_manager()
+            .get_local_drag_session(native_id)
+    }
+
     fn send_drop_update(
         &self,
         id: PlatformDropContextId,
-        event: DropEvent,
+        mut event: DropEvent,
         res: Box<dyn FnOnce(Result<DropOperation, MethodCallError>)>,
     ) {
-        self.invoker
-            .call_method_sync_cv(id, "onDropUpdate", event, res);
+        self.update_spring_loading(id, &event);
+        self.update_auto_scroll(id, &mut event);
+        let session_id = event.session_id;
+        let weak_self = self.weak_self.clone();
+        self.invoker.call_method_sync_cv(
+            id,
+            "onDropUpdate",
+            event,
+            move |r: Result<DropOperation, MethodCallError>| {
+                if let (Ok(operation), Some(this)) = (&r, weak_self.upgrade()) {
+                    this.with_spring_load_tracker(id, |t| {
+                        t.update_operation(session_id, *operation)
+                    });
+                }
+                res(r)
+            },
+        );
     }
 
     fn send_perform_drop(
@@ -273,6 +469,8 @@ impl PlatformDropContextDelegate for DropManager {
         event: DropEvent,
         res: Box<dyn FnOnce(Result<(), MethodCallError>)>,
     ) {
+        self.reset_spring_loading(id);
+        self.reset_auto_scroll(id);
         self.invoker
             .call_method_sync_cv(id, "onPerformDrop", event, |r| {
                 // Delay result callback one run loop turn. This is necessary because
@@ -287,6 +485,8 @@ impl PlatformDropContextDelegate for DropManager {
     }
 
     fn send_drop_leave(&self, id: PlatformDropContextId, event: BaseDropEvent) {
+        self.reset_spring_loading(id);
+        self.reset_auto_scroll(id);
         self.invoker
             .call_method_sync(id, "onDropLeave", event, |r| {
                 r.ok_log();
@@ -294,6 +494,8 @@ impl PlatformDropContextDelegate for DropManager {
     }
 
     fn send_drop_ended(&self, id: PlatformDropContextId, event: BaseDropEvent) {
+        self.reset_spring_loading(id);
+        self.reset_auto_scroll(id);
         self.invoker
             .call_method_sync(id, "onDropEnded", event, |r| {
                 r.ok_log();
@@ -335,4 +537,11 @@ impl PlatformDropContextDelegate for DropManager {
         });
         res
     }
+
+    fn force_spring_load(&self, id: PlatformDropContextId, session_id: DropSessionId) {
+        let event = self.with_spring_load_tracker(id, |t| t.force(session_id));
+        if let Some(event) = event {
+            self.send_spring_load(id, event);
+        }
+    }
 }
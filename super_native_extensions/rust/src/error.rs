@@ -21,6 +21,8 @@ pub enum NativeExtensionsError {
     PlatformMenuNotFound,
     InvalidMenuElement,
     InvalidMenuConfigurationId,
+    DataProviderTimedOut,
+    HotKeyAlreadyRegistered,
 }
 
 pub type NativeExtensionsResult<T> = Result<T, NativeExtensionsError>;
@@ -55,6 +57,12 @@ impl Display for NativeExtensionsError {
             NativeExtensionsError::InvalidMenuConfigurationId => {
                 write!(f, "invalid menu configuration id")
             }
+            NativeExtensionsError::DataProviderTimedOut => {
+                write!(f, "data provider timed out")
+            }
+            NativeExtensionsError::HotKeyAlreadyRegistered => {
+                write!(f, "hot key is already registered")
+            }
         }
     }
 }
@@ -85,6 +93,8 @@ impl NativeExtensionsError {
             NativeExtensionsError::InvalidMenuConfigurationId => {
                 "invalidMenuConfigurationId".into()
             }
+            NativeExtensionsError::DataProviderTimedOut => "dataProviderTimedOut".into(),
+            NativeExtensionsError::HotKeyAlreadyRegistered => "hotKeyAlreadyRegistered".into(),
         }
     }
 }
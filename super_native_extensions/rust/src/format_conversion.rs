@@ -0,0 +1,577 @@
+use std::path::Path;
+
+use irondash_message_channel::Value;
+use url::Url;
+
+use crate::log::OkLog;
+
+/// Conversion used to synthesize format that is not provided by the data
+/// source from another format that is.
+pub struct FormatConversion {
+    pub source: &'static str,
+    pub target: &'static str,
+    pub convert: fn(Value) -> Option<Value>,
+}
+
+/// Shared by all readers. Platform specific conversions are provided by
+/// `PlatformDataReader::format_conversions`.
+pub struct FormatConversionRegistry {
+    conversions: Vec<FormatConversion>,
+}
+
+impl FormatConversionRegistry {
+    pub fn new(conversions: Vec<FormatConversion>) -> Self {
+        Self { conversions }
+    }
+
+    /// Returns formats that are not present in `formats` but can be
+    /// synthesized from them.
+    pub fn synthesized_formats(&self, formats: &[String]) -> Vec<String> {
+        let mut res = Vec::<String>::new();
+        for conversion in &self.conversions {
+            let target = conversion.target;
+            if formats.iter().any(|f| f == conversion.source)
+                && !formats.iter().any(|f| f == target)
+                && !res.iter().any(|f| f == target)
+            {
+                res.push(target.to_owned());
+            }
+        }
+        res
+    }
+
+    /// Returns first registered conversion that produces `target` from one
+    /// of `formats`.
+    pub fn find(&self, formats: &[String], target: &str) -> Option<&FormatConversion> {
+        self.conversions
+            .iter()
+            .find(|c| c.target == target && formats.iter().any(|f| f == c.source))
+    }
+}
+
+fn value_to_string(value: Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s),
+        Value::U8List(data) => Some(String::from_utf8_lossy(&data).into_owned()),
+        _ => None,
+    }
+}
+
+fn value_to_bytes(value: Value) -> Option<Vec<u8>> {
+    match value {
+        Value::String(s) => Some(s.into_bytes()),
+        Value::U8List(data) => Some(data),
+        _ => None,
+    }
+}
+
+/// UTF-16 (little endian unless there is byte order mark) to UTF-8 string.
+pub fn utf16_to_utf8(value: Value) -> Option<Value> {
+    let data = match value {
+        Value::String(s) => return Some(Value::String(s)),
+        Value::U8List(data) => data,
+        _ => return None,
+    };
+    let (data, big_endian) = match data.as_slice() {
+        [0xFE, 0xFF, rest @ ..] => (rest, true),
+        [0xFF, 0xFE, rest @ ..] => (rest, false),
+        data => (data, false),
+    };
+    let mut units: Vec<u16> = data
+        .chunks_exact(2)
+        .map(|c| {
+            if big_endian {
+                u16::from_be_bytes([c[0], c[1]])
+            } else {
+                u16::from_le_bytes([c[0], c[1]])
+            }
+        })
+        .collect();
+    // Null terminator
+    if let Some(terminator) = units.iter().position(|u| *u == 0) {
+        units.truncate(terminator);
+    }
+    Some(Value::String(String::from_utf16_lossy(&units)))
+}
+
+/// UTF-8 to UTF-16 little endian data.
+pub fn utf8_to_utf16(value: Value) -> Option<Value> {
+    let string = value_to_string(value)?;
+    let data: Vec<u8> = string
+        .encode_utf16()
+        .flat_map(|u| u.to_le_bytes())
+        .collect();
+    Some(Value::U8List(data))
+}
+
+/// Plain text from HTML. Also accepts Windows `HTML Format` data, in which
+/// case the header before first tag is ignored.
+pub fn html_to_text(value: Value) -> Option<Value> {
+    let html = value_to_string(value)?;
+    let html = match html.find('<') {
+        Some(start) if html.starts_with("Version:") => &html[start..],
+        _ => html.as_str(),
+    };
+    let mut res = String::new();
+    let mut pending_space = false;
+    let mut rest = html;
+    while let Some(c) = rest.chars().next() {
+        if c == '<' {
+            let end = rest.find('>').map(|i| i + 1).unwrap_or(rest.len());
+            let tag = rest[1..end].trim_end_matches('>').to_ascii_lowercase();
+            let name: String = tag
+                .trim_start_matches('/')
+                .chars()
+                .take_while(|c| c.is_ascii_alphanumeric())
+                .collect();
+            rest = &rest[end..];
+            if !tag.starts_with('/') && (name == "script" || name == "style") {
+                // Skip element content
+                let close = format!("</{name}");
+                let close_start = rest.to_ascii_lowercase().find(&close);
+                rest = match close_start {
+                    Some(i) => &rest[i..],
+                    None => "",
+                };
+                continue;
+            }
+            let is_block = matches!(
+                name.as_str(),
+                "br" | "p" | "div" | "li" | "tr" | "h1" | "h2" | "h3" | "h4" | "h5" | "h6"
+            );
+            if is_block && (name == "br" || tag.starts_with('/')) && !res.is_empty() {
+                res.push('\n');
+                pending_space = false;
+            }
+        } else if c == '&' {
+            let end = rest.find(';').filter(|i| *i < 10);
+            let decoded = end.and_then(|end| decode_entity(&rest[1..end]));
+            if pending_space && !res.ends_with('\n') {
+                res.push(' ');
+            }
+            pending_space = false;
+            match (end, decoded) {
+                (Some(end), Some(decoded)) => {
+                    res.push(decoded);
+                    rest = &rest[end + 1..];
+                }
+                _ => {
+                    res.push('&');
+                    rest = &rest[1..];
+                }
+            }
+        } else if c.is_whitespace() {
+            pending_space = !res.is_empty();
+            rest = &rest[c.len_utf8()..];
+        } else {
+            if pending_space && !res.ends_with('\n') {
+                res.push(' ');
+            }
+            pending_space = false;
+            res.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    Some(Value::String(res.trim_end().to_owned()))
+}
+
+fn decode_entity(entity: &str) -> Option<char> {
+    match entity {
+        "amp" => Some('&'),
+        "lt" => Some('<'),
+        "gt" => Some('>'),
+        "quot" => Some('"'),
+        "apos" => Some('\''),
+        "nbsp" => Some('\u{a0}'),
+        _ => {
+            let code = entity.strip_prefix('#')?;
+            let code = match code.strip_prefix(['x', 'X']) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                None => code.parse().ok()?,
+            };
+            char::from_u32(code)
+        }
+    }
+}
+
+/// Plain text from RTF. Formatting and destinations that do not contain
+/// document text (font table, pictures, etc.) are ignored.
+pub fn rtf_to_text(value: Value) -> Option<Value> {
+    let rtf = value_to_bytes(value)?;
+    if !rtf.starts_with(b"{\\rtf") {
+        return None;
+    }
+    const SKIPPED_DESTINATIONS: &[&str] = &[
+        "fonttbl",
+        "colortbl",
+        "stylesheet",
+        "info",
+        "pict",
+        "header",
+        "footer",
+        "listtable",
+        "listoverridetable",
+        "generator",
+    ];
+    struct Group {
+        skip: bool,
+        unicode_skip: usize,
+    }
+    let mut groups = vec![Group {
+        skip: false,
+        unicode_skip: 1,
+    }];
+    let mut res = String::new();
+    // Number of fallback characters to skip after \u
+    let mut pending_skip = 0usize;
+    // \u encodes UTF-16 code units
+    let mut high_surrogate = None::<u16>;
+    let mut i = 0;
+    while i < rtf.len() {
+        let c = rtf[i];
+        let skip = groups.last().map(|g| g.skip).unwrap_or(true);
+        match c {
+            b'{' => {
+                let unicode_skip = groups.last().map(|g| g.unicode_skip).unwrap_or(1);
+                let skip = skip || rtf[i + 1..].starts_with(b"\\*");
+                groups.push(Group { skip, unicode_skip });
+                i += 1;
+            }
+            b'}' => {
+                groups.pop();
+                i += 1;
+            }
+            b'\\' => {
+                i += 1;
+                let start = i;
+                while i < rtf.len() && rtf[i].is_ascii_alphabetic() {
+                    i += 1;
+                }
+                if start == i {
+                    // Control symbol
+                    let symbol = rtf.get(i).copied().unwrap_or_default();
+                    i += 1;
+                    let text = match symbol {
+                        b'\'' => {
+                            let hex = rtf.get(i..i + 2).unwrap_or_default();
+                            i += 2;
+                            std::str::from_utf8(hex)
+                                .ok()
+                                .and_then(|h| u8::from_str_radix(h, 16).ok())
+                                // Approximation of Windows-1252
+                                .map(|b| b as char)
+                        }
+                        b'~' => Some('\u{a0}'),
+                        b'\\' | b'{' | b'}' => Some(symbol as char),
+                        _ => None,
+                    };
+                    if let Some(text) = text {
+                        if pending_skip > 0 {
+                            pending_skip -= 1;
+                        } else if !skip {
+                            res.push(text);
+                        }
+                    }
+                    continue;
+                }
+                let word = std::str::from_utf8(&rtf[start..i]).unwrap_or_default();
+                let param_start = i;
+                if i < rtf.len() && rtf[i] == b'-' {
+                    i += 1;
+                }
+                while i < rtf.len() && rtf[i].is_ascii_digit() {
+                    i += 1;
+                }
+                let param = std::str::from_utf8(&rtf[param_start..i])
+                    .ok()
+                    .and_then(|p| p.parse::<i32>().ok());
+                if i < rtf.len() && rtf[i] == b' ' {
+                    i += 1;
+                }
+                if SKIPPED_DESTINATIONS.contains(&word) {
+                    if let Some(group) = groups.last_mut() {
+                        group.skip = true;
+                    }
+                    continue;
+                }
+                if skip {
+                    continue;
+                }
+                match word {
+                    "par" | "line" | "row" => res.push('\n'),
+                    "tab" | "cell" => res.push('\t'),
+                    "uc" => {
+                        if let Some(group) = groups.last_mut() {
+                            group.unicode_skip = param.unwrap_or(1).max(0) as usize;
+                        }
+                    }
+                    "u" => {
+                        if let Some(param) = param {
+                            // Negative values represent code units above 32767
+                            let unit = param as i16 as u16;
+                            match (high_surrogate.take(), unit) {
+                                (_, 0xD800..=0xDBFF) => high_surrogate = Some(unit),
+                                (Some(high), 0xDC00..=0xDFFF) => {
+                                    let units = [high, unit];
+                                    res.extend(
+                                        char::decode_utf16(units)
+                                            .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER)),
+                                    );
+                                }
+                                (_, unit) => res.push(
+                                    char::from_u32(unit as u32)
+                                        .unwrap_or(char::REPLACEMENT_CHARACTER),
+                                ),
+                            }
+                            pending_skip = groups.last().map(|g| g.unicode_skip).unwrap_or(1);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            b'\r' | b'\n' => {
+                i += 1;
+            }
+            _ => {
+                if pending_skip > 0 {
+                    pending_skip -= 1;
+                } else if !skip {
+                    res.push(c as char);
+                }
+                i += 1;
+            }
+        }
+    }
+    Some(Value::String(res))
+}
+
+/// Newline separated file paths from URI list. URIs that are not file URIs
+/// are ignored.
+pub fn uri_list_to_file_paths(value: Value) -> Option<Value> {
+    let list = value_to_string(value)?;
+    let paths: Vec<String> = list
+        .lines()
+        .map(|l| l.trim())
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .filter_map(|l| Url::parse(l).ok()?.to_file_path().ok())
+        .map(|p| p.to_string_lossy().into_owned())
+        .collect();
+    if paths.is_empty() {
+        None
+    } else {
+        Some(Value::String(paths.join("\n")))
+    }
+}
+
+/// URI list from newline separated file paths.
+pub fn file_paths_to_uri_list(value: Value) -> Option<Value> {
+    let paths = value_to_string(value)?;
+    let uris: Vec<String> = paths
+        .lines()
+        .filter(|l| !l.is_empty())
+        .filter_map(|l| Url::from_file_path(Path::new(l)).ok())
+        .map(|u| u.to_string())
+        .collect();
+    if uris.is_empty() {
+        None
+    } else {
+        Some(Value::String(uris.join("\r\n")))
+    }
+}
+
+/// PNG from BMP file.
+pub fn bmp_to_png(value: Value) -> Option<Value> {
+    let data = value_to_bytes(value)?;
+    if !data.starts_with(b"BM") || data.len() < 14 {
+        return None;
+    }
+    let pixel_offset = u32::from_le_bytes(data[10..14].try_into().ok()?) as usize;
+    let png = dib_data_to_png(&data[14..], pixel_offset.checked_sub(14)?)?;
+    Some(Value::U8List(png))
+}
+
+fn dib_data_to_png(dib: &[u8], pixel_offset: usize) -> Option<Vec<u8>> {
+    let u16_at = |offset: usize| -> Option<u16> {
+        Some(u16::from_le_bytes(
+            dib.get(offset..offset + 2)?.try_into().ok()?,
+        ))
+    };
+    let u32_at = |offset: usize| -> Option<u32> {
+        Some(u32::from_le_bytes(
+            dib.get(offset..offset + 4)?.try_into().ok()?,
+        ))
+    };
+    const BI_RGB: u32 = 0;
+    const BI_BITFIELDS: u32 = 3;
+
+    let header_size = u32_at(0)? as usize;
+    let width = u32_at(4)? as i32;
+    let height = u32_at(8)? as i32;
+    let bit_count = u16_at(14)?;
+    let compression = u32_at(16)?;
+    if header_size < 40 || width <= 0 || height == 0 {
+        return None;
+    }
+    if compression != BI_RGB && compression != BI_BITFIELDS {
+        return None;
+    }
+    if bit_count != 24 && bit_count != 32 {
+        return None;
+    }
+    let top_down = height < 0;
+    let (width, height) = (width as usize, height.unsigned_abs() as usize);
+    let bytes_per_pixel = bit_count as usize / 8;
+    // Header values are untrusted.
+    let row_len = width.checked_mul(bytes_per_pixel)?;
+    let stride = row_len.div_ceil(4).checked_mul(4)?;
+    let pixels_end = pixel_offset.checked_add(stride.checked_mul(height)?)?;
+    let pixels = dib.get(pixel_offset..pixels_end)?;
+
+    let mut rgba = Vec::with_capacity(width * height * 4);
+    for y in 0..height {
+        let row = if top_down { y } else { height - 1 - y };
+        let row = &pixels[row * stride..row * stride + row_len];
+        for pixel in row.chunks_exact(bytes_per_pixel) {
+            let alpha = if bytes_per_pixel == 4 { pixel[3] } else { 255 };
+            rgba.extend_from_slice(&[pixel[2], pixel[1], pixel[0], alpha]);
+        }
+    }
+    // 32-bit bitmaps frequently leave alpha channel unused.
+    if bytes_per_pixel == 4 && rgba.chunks_exact(4).all(|p| p[3] == 0) {
+        for pixel in rgba.chunks_exact_mut(4) {
+            pixel[3] = 255;
+        }
+    }
+    encode_png(width as u32, height as u32, &rgba)
+}
+
+fn encode_png(width: u32, height: u32, rgba: &[u8]) -> Option<Vec<u8>> {
+    let mut res = Vec::new();
+    let mut encoder = png::Encoder::new(&mut res, width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header().ok_log()?;
+    writer.write_image_data(rgba).ok_log()?;
+    writer.finish().ok_log()?;
+    Some(res)
+}
+
+#[cfg(test)]
+mod test {
+    use irondash_message_channel::Value;
+
+    use super::{
+        bmp_to_png, html_to_text, rtf_to_text, uri_list_to_file_paths, utf16_to_utf8,
+        utf8_to_utf16, FormatConversion, FormatConversionRegistry,
+    };
+
+    fn string(value: Option<Value>) -> String {
+        match value {
+            Some(Value::String(s)) => s,
+            other => panic!("unexpected value {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_registry() {
+        let registry = FormatConversionRegistry::new(vec![
+            FormatConversion {
+                source: "text/html",
+                target: "text/plain",
+                convert: html_to_text,
+            },
+            FormatConversion {
+                source: "text/rtf",
+                target: "text/plain",
+                convert: rtf_to_text,
+            },
+        ]);
+        let formats = vec!["text/rtf".to_owned()];
+        assert_eq!(registry.synthesized_formats(&formats), vec!["text/plain"]);
+        assert_eq!(
+            registry.find(&formats, "text/plain").unwrap().source,
+            "text/rtf"
+        );
+        let formats = vec!["text/html".to_owned(), "text/plain".to_owned()];
+        assert!(registry.synthesized_formats(&formats).is_empty());
+    }
+
+    #[test]
+    fn test_html_to_text() {
+        let html = "<html><head><style>p { color: red; }</style></head>\
+                    <body><p>Hello&nbsp;<b>world</b> &amp;\n  friends</p><p>Line&#x32;</p></body></html>";
+        assert_eq!(
+            string(html_to_text(Value::String(html.into()))),
+            "Hello\u{a0}world & friends\nLine2"
+        );
+        let windows = "Version:0.9\r\nStartHTML:00000097\r\n<html><body>Text</body></html>";
+        assert_eq!(string(html_to_text(Value::String(windows.into()))), "Text");
+    }
+
+    #[test]
+    fn test_rtf_to_text() {
+        let rtf = b"{\\rtf1\\ansi{\\fonttbl\\f0\\fswiss Helvetica;}{\\*\\expandedcolortbl;;}\
+                    \\f0\\pard Hello \\b world\\b0\\par caf\\'e9 \\u8364?\\u-10179?\\u-8704?\\}}";
+        assert_eq!(
+            string(rtf_to_text(Value::U8List(rtf.to_vec()))),
+            "Hello world\ncafé €😀}"
+        );
+    }
+
+    #[test]
+    fn test_utf16() {
+        let data = utf8_to_utf16(Value::String("Hello €".into())).unwrap();
+        assert_eq!(string(utf16_to_utf8(data)), "Hello €");
+        let data = Value::U8List(vec![0xFE, 0xFF, 0, b'A', 0, 0]);
+        assert_eq!(string(utf16_to_utf8(data)), "A");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_uri_list_to_file_paths() {
+        let list = "# comment\r\nfile:///tmp/a%20b.txt\r\nhttps://example.com\r\nfile:///c";
+        assert_eq!(
+            string(uri_list_to_file_paths(Value::String(list.into()))),
+            "/tmp/a b.txt\n/c"
+        );
+    }
+
+    fn bmp(width: i32, height: i32, pixels: &[u8]) -> Vec<u8> {
+        let mut bmp = Vec::<u8>::new();
+        bmp.extend_from_slice(b"BM");
+        bmp.extend_from_slice(&(54 + pixels.len() as u32).to_le_bytes());
+        bmp.extend_from_slice(&[0, 0, 0, 0]);
+        bmp.extend_from_slice(&54u32.to_le_bytes());
+        bmp.extend_from_slice(&40u32.to_le_bytes());
+        bmp.extend_from_slice(&width.to_le_bytes());
+        bmp.extend_from_slice(&height.to_le_bytes());
+        bmp.extend_from_slice(&1u16.to_le_bytes());
+        bmp.extend_from_slice(&24u16.to_le_bytes());
+        bmp.extend_from_slice(&[0; 24]);
+        bmp.extend_from_slice(pixels);
+        bmp
+    }
+
+    #[test]
+    fn test_bmp_to_png() {
+        // 2x1 24-bit bottom-up bitmap
+        let bmp = bmp(2, 1, &[0, 0, 255, 255, 0, 0, 0, 0]);
+        let png = match bmp_to_png(Value::U8List(bmp)) {
+            Some(Value::U8List(png)) => png,
+            other => panic!("unexpected value {other:?}"),
+        };
+        let mut reader = png::Decoder::new(png.as_slice()).read_info().unwrap();
+        let mut rgba = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut rgba).unwrap();
+        assert_eq!((info.width, info.height), (2, 1));
+        assert_eq!(rgba, [255, 0, 0, 255, 0, 0, 255, 255]);
+    }
+
+    #[test]
+    fn test_bmp_to_png_invalid_size() {
+        let pixels = [0; 8];
+        assert!(bmp_to_png(Value::U8List(bmp(2, i32::MAX, &pixels))).is_none());
+        assert!(bmp_to_png(Value::U8List(bmp(i32::MAX, -i32::MAX, &pixels))).is_none());
+        assert!(bmp_to_png(Value::U8List(bmp(2, 2, &pixels))).is_none());
+    }
+}
@@ -2,23 +2,30 @@ use std::{
     cell::{Cell, RefCell},
     collections::HashMap,
     convert::TryInto,
-    rc::Rc,
+    rc::{Rc, Weak},
+    time::Duration,
 };
 
 use irondash_message_channel::{
     IntoPlatformResult, IntoValue, IsolateId, Late, MethodCall, MethodCallReply, MethodHandler,
     MethodInvoker, PlatformResult, RegisteredMethodHandler, TryFromValue, Value,
 };
+use irondash_run_loop::RunLoop;
+use log::warn;
 
 use crate::{
     context::Context,
     error::{NativeExtensionsError, NativeExtensionsResult},
+    hot_key_sequence::{
+        sequences_conflict, HotKeySequenceCreateRequest, HotKeySequenceTracker, SequenceEvent,
+    },
+    keyboard_layout_manager::{GetKeyboardLayoutDelegate, KeyboardLayout},
     log::OkLog,
     platform_impl::platform::PlatformHotKeyManager,
     util::NextId,
 };
 
-#[derive(TryFromValue, Debug, Clone)]
+#[derive(TryFromValue, IntoValue, Debug, Clone, PartialEq, Eq, Hash)]
 #[irondash(rename_all = "camelCase")]
 pub struct HotKeyCreateRequest {
     pub alt: bool,
@@ -28,6 +35,33 @@ pub struct HotKeyCreateRequest {
     pub platform_code: i64,
 }
 
+/// Hot key specified by logical key instead of platform code. Platform code
+/// is resolved through current keyboard layout and updated when the layout
+/// changes. Shift is added if the layout only produces the logical key with
+/// shift pressed.
+#[derive(TryFromValue, Debug, Clone)]
+#[irondash(rename_all = "camelCase")]
+pub struct LogicalHotKeyCreateRequest {
+    pub alt: bool,
+    pub shift: bool,
+    pub meta: bool,
+    pub control: bool,
+    pub logical_key: i64,
+}
+
+impl LogicalHotKeyCreateRequest {
+    fn resolve(&self, layout: &KeyboardLayout) -> Option<HotKeyCreateRequest> {
+        let (platform_code, shift) = layout.platform_code_for_logical_key(self.logical_key)?;
+        Some(HotKeyCreateRequest {
+            alt: self.alt,
+            shift: self.shift || shift,
+            meta: self.meta,
+            control: self.control,
+            platform_code,
+        })
+    }
+}
+
 #[derive(TryFromValue, Debug)]
 struct HotKeyDestroyRequest {
     pub handle: HotKeyHandle,
@@ -36,11 +70,46 @@ struct HotKeyDestroyRequest {
 #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, IntoValue, TryFromValue)]
 pub struct HotKeyHandle(i64);
 
+impl From<i64> for HotKeyHandle {
+    fn from(v: i64) -> Self {
+        Self(v)
+    }
+}
+
+#[derive(IntoValue, Debug)]
+#[irondash(rename_all = "camelCase")]
+struct HotKeyInfo {
+    handle: HotKeyHandle,
+    alt: bool,
+    shift: bool,
+    meta: bool,
+    control: bool,
+    platform_code: i64,
+    /// Remaining chords of multi-stroke hot key.
+    follow_up_chords: Vec<HotKeyCreateRequest>,
+    logical_key: Option<i64>,
+}
+
+struct HotKey {
+    isolate: IsolateId,
+    /// Single chord for regular hot keys, multiple chords for sequences.
+    chords: Vec<HotKeyCreateRequest>,
+    /// Set for hot keys that need to be re-registered on layout change.
+    logical: Option<LogicalHotKeyCreateRequest>,
+}
+
 pub struct HotKeyManager {
+    weak_self: Late<Weak<Self>>,
     invoker: Late<MethodInvoker>,
-    handle_to_isolate: RefCell<HashMap<HotKeyHandle, IsolateId>>,
+    hot_keys: RefCell<HashMap<HotKeyHandle, HotKey>>,
     next_id: Cell<i64>,
     platform_manager: Late<Rc<PlatformHotKeyManager>>,
+    sequences: RefCell<HotKeySequenceTracker>,
+    /// Chords registered with platform on behalf of sequences.
+    sequence_grabs: RefCell<HashMap<HotKeyCreateRequest, HotKeyHandle>>,
+    /// Grab that completed a sequence and the sequence handle; kept until
+    /// the chord is released.
+    pending_release: Cell<Option<(HotKeyHandle, HotKeyHandle)>>,
 }
 
 pub trait HotKeyManagerDelegate {
@@ -61,10 +130,14 @@ impl GetHotKeyManager for Context {
 impl HotKeyManager {
     pub fn new() -> RegisteredMethodHandler<Self> {
         Self {
+            weak_self: Late::new(),
             invoker: Late::new(),
-            handle_to_isolate: RefCell::new(HashMap::new()),
+            hot_keys: RefCell::new(HashMap::new()),
             next_id: Cell::new(1),
             platform_manager: Late::new(),
+            sequences: RefCell::new(HotKeySequenceTracker::default()),
+            sequence_grabs: RefCell::new(HashMap::new()),
+            pending_release: Cell::new(None),
         }
         .register("HotKeyManager")
     }
@@ -74,21 +147,296 @@ impl HotKeyManager {
         isolate_id: IsolateId,
         request: HotKeyCreateRequest,
     ) -> NativeExtensionsResult<Option<HotKeyHandle>> {
+        self.register_hot_key(isolate_id, request, None)
+    }
+
+    fn create_logical_hot_key(
+        &self,
+        isolate_id: IsolateId,
+        request: LogicalHotKeyCreateRequest,
+    ) -> NativeExtensionsResult<Option<HotKeyHandle>> {
+        let Some(layout) = Context::get().keyboard_map_manager().platform_layout() else {
+            // Keyboard layout is not available on this platform.
+            return Ok(None);
+        };
+        let resolved = request.resolve(&layout).ok_or_else(|| {
+            NativeExtensionsError::OtherError(format!(
+                "no key for logical key {} in current keyboard layout",
+                request.logical_key
+            ))
+        })?;
+        self.register_hot_key(isolate_id, resolved, Some(request))
+    }
+
+    fn register_hot_key(
+        &self,
+        isolate_id: IsolateId,
+        request: HotKeyCreateRequest,
+        logical: Option<LogicalHotKeyCreateRequest>,
+    ) -> NativeExtensionsResult<Option<HotKeyHandle>> {
+        let chords = [request.clone()];
+        if self.is_registered(&chords) {
+            return Err(NativeExtensionsError::HotKeyAlreadyRegistered);
+        }
         let handle = HotKeyHandle(self.next_id.next_id());
         let res = self.platform_manager.create_hot_key(handle, request);
         if let Err(NativeExtensionsError::UnsupportedOperation) = res {
             return Ok(None);
         }
         res?;
-        self.handle_to_isolate
+        self.hot_keys.borrow_mut().insert(
+            handle,
+            HotKey {
+                isolate: isolate_id,
+                chords: chords.into(),
+                logical,
+            },
+        );
+        Ok(Some(handle))
+    }
+
+    fn create_hot_key_sequence(
+        &self,
+        isolate_id: IsolateId,
+        request: HotKeySequenceCreateRequest,
+    ) -> NativeExtensionsResult<Option<HotKeyHandle>> {
+        match request.chords.len() {
+            0 => {
+                return Err(NativeExtensionsError::OtherError(
+                    "hot key sequence must not be empty".into(),
+                ))
+            }
+            1 => return self.create_hot_key(isolate_id, request.chords[0].clone()),
+            _ => {}
+        }
+        let timeout = request.timeout_duration()?;
+        if self.is_registered(&request.chords) {
+            return Err(NativeExtensionsError::HotKeyAlreadyRegistered);
+        }
+        // First chord is grabbed for as long as the sequence exists and may
+        // be shared with other sequences.
+        let first = request.chords[0].clone();
+        if !self.sequence_grabs.borrow().contains_key(&first) {
+            let grab = HotKeyHandle(self.next_id.next_id());
+            let res = self.platform_manager.create_hot_key(grab, first.clone());
+            if let Err(NativeExtensionsError::UnsupportedOperation) = res {
+                return Ok(None);
+            }
+            res?;
+            self.sequence_grabs.borrow_mut().insert(first, grab);
+        }
+        let handle = HotKeyHandle(self.next_id.next_id());
+        self.hot_keys.borrow_mut().insert(
+            handle,
+            HotKey {
+                isolate: isolate_id,
+                chords: request.chords.clone(),
+                logical: None,
+            },
+        );
+        self.sequences
             .borrow_mut()
-            .insert(handle, isolate_id);
+            .add(handle, request.chords, timeout);
+        self.update_grabs();
         Ok(Some(handle))
     }
 
     fn destroy_hot_key(&self, request: HotKeyDestroyRequest) -> NativeExtensionsResult<()> {
-        self.handle_to_isolate.borrow_mut().remove(&request.handle);
-        self.platform_manager.destroy_hot_key(request.handle)
+        self.remove_hot_key(request.handle)
+    }
+
+    fn remove_hot_key(&self, handle: HotKeyHandle) -> NativeExtensionsResult<()> {
+        let hot_key = self.hot_keys.borrow_mut().remove(&handle);
+        match hot_key {
+            Some(hot_key) if hot_key.chords.len() > 1 => {
+                self.sequences.borrow_mut().remove(handle);
+                self.update_grabs();
+                Ok(())
+            }
+            _ => self.platform_manager.destroy_hot_key(handle),
+        }
+    }
+
+    /// Re-registers logical hot keys whose platform code changed with new
+    /// keyboard layout. Handles stay the same. Hot keys that can not be
+    /// re-registered keep previous registration and the owning isolate is
+    /// notified.
+    pub fn keyboard_layout_did_change(&self) {
+        let Some(layout) = Context::get().keyboard_map_manager().platform_layout() else {
+            return;
+        };
+        let changed: Vec<_> = self
+            .hot_keys
+            .borrow()
+            .iter()
+            .filter_map(|(handle, hot_key)| {
+                let logical = hot_key.logical.as_ref()?;
+                let Some(resolved) = logical.resolve(&layout) else {
+                    warn!(
+                        "Logical key {} not found in current keyboard layout",
+                        logical.logical_key
+                    );
+                    return None;
+                };
+                (hot_key.chords[0] != resolved).then_some((*handle, resolved))
+            })
+            .collect();
+        for (handle, chord) in changed {
+            if let Err(error) = self.reregister_hot_key(handle, chord) {
+                warn!("Failed to re-register hot key after keyboard layout change: {error}");
+                self.notify("onHotKeyRegistrationFailed", handle);
+            }
+        }
+    }
+
+    /// Registers existing single chord hot key with new chord. Previous
+    /// chord is restored on failure.
+    fn reregister_hot_key(
+        &self,
+        handle: HotKeyHandle,
+        chord: HotKeyCreateRequest,
+    ) -> NativeExtensionsResult<()> {
+        let chords = [chord.clone()];
+        let conflict = self
+            .hot_keys
+            .borrow()
+            .iter()
+            .any(|(h, hot_key)| *h != handle && sequences_conflict(&hot_key.chords, &chords));
+        if conflict {
+            return Err(NativeExtensionsError::HotKeyAlreadyRegistered);
+        }
+        let previous = match self.hot_keys.borrow().get(&handle) {
+            Some(hot_key) => hot_key.chords[0].clone(),
+            None => return Ok(()),
+        };
+        self.platform_manager.destroy_hot_key(handle)?;
+        if let Err(error) = self.platform_manager.create_hot_key(handle, chord) {
+            self.platform_manager
+                .create_hot_key(handle, previous)
+                .ok_log();
+            return Err(error);
+        }
+        if let Some(hot_key) = self.hot_keys.borrow_mut().get_mut(&handle) {
+            hot_key.chords = chords.into();
+        }
+        Ok(())
+    }
+
+    /// Whether the chords conflict with hot key registered by any isolate.
+    fn is_registered(&self, chords: &[HotKeyCreateRequest]) -> bool {
+        self.hot_keys
+            .borrow()
+            .values()
+            .any(|hot_key| sequences_conflict(&hot_key.chords, chords))
+    }
+
+    /// Single chord hot keys are already registered with platform.
+    fn is_single_chord_hot_key(&self, chord: &HotKeyCreateRequest) -> bool {
+        self.hot_keys
+            .borrow()
+            .values()
+            .any(|hot_key| hot_key.chords.as_slice() == std::slice::from_ref(chord))
+    }
+
+    /// Returns chord for handle reported by platform.
+    fn chord_for_platform_handle(&self, handle: HotKeyHandle) -> Option<HotKeyCreateRequest> {
+        if let Some(hot_key) = self.hot_keys.borrow().get(&handle) {
+            if let [chord] = hot_key.chords.as_slice() {
+                return Some(chord.clone());
+            }
+        }
+        self.sequence_grabs
+            .borrow()
+            .iter()
+            .find(|(_, grab)| **grab == handle)
+            .map(|(chord, _)| chord.clone())
+    }
+
+    /// Registers chords needed by sequence tracker in current state with
+    /// platform and releases the ones no longer needed.
+    fn update_grabs(&self) {
+        let desired = self.sequences.borrow().grabs();
+        let pending = self.pending_release.get().map(|(grab, _)| grab);
+        let mut grabs = self.sequence_grabs.borrow_mut();
+        grabs.retain(|chord, grab| {
+            let keep = desired.contains(chord) || Some(*grab) == pending;
+            if !keep {
+                self.platform_manager.destroy_hot_key(*grab).ok_log();
+            }
+            keep
+        });
+        for chord in desired {
+            if grabs.contains_key(&chord) || self.is_single_chord_hot_key(&chord) {
+                continue;
+            }
+            let grab = HotKeyHandle(self.next_id.next_id());
+            if self
+                .platform_manager
+                .create_hot_key(grab, chord.clone())
+                .ok_log()
+                .is_some()
+            {
+                grabs.insert(chord, grab);
+            }
+        }
+    }
+
+    fn schedule_sequence_timeout(&self, generation: u64, timeout: Duration) {
+        let weak_self = self.weak_self.clone();
+        RunLoop::current()
+            .schedule(timeout, move || {
+                if let Some(this) = weak_self.upgrade() {
+                    if this.sequences.borrow_mut().timer_fired(generation) {
+                        this.update_grabs();
+                    }
+                }
+            })
+            .detach();
+    }
+
+    /// Checks whether the combination can be registered by temporarily
+    /// registering it with the platform. Returns false for combinations
+    /// taken by this or another application and when hot keys are not
+    /// supported.
+    fn can_register(&self, request: HotKeyCreateRequest) -> NativeExtensionsResult<bool> {
+        if self.is_registered(&[request.clone()]) {
+            return Ok(false);
+        }
+        let handle = HotKeyHandle(self.next_id.next_id());
+        match self.platform_manager.create_hot_key(handle, request) {
+            Ok(()) => {
+                self.platform_manager.destroy_hot_key(handle)?;
+                Ok(true)
+            }
+            Err(NativeExtensionsError::UnsupportedOperation)
+            | Err(NativeExtensionsError::HotKeyAlreadyRegistered) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Returns hot keys registered by given isolate.
+    fn list_hot_keys(&self, isolate_id: IsolateId) -> Vec<HotKeyInfo> {
+        let mut hot_keys: Vec<_> = self
+            .hot_keys
+            .borrow()
+            .iter()
+            .filter(|(_, hot_key)| hot_key.isolate == isolate_id)
+            .map(|(handle, hot_key)| {
+                let first = &hot_key.chords[0];
+                HotKeyInfo {
+                    handle: *handle,
+                    alt: first.alt,
+                    shift: first.shift,
+                    meta: first.meta,
+                    control: first.control,
+                    platform_code: first.platform_code,
+                    follow_up_chords: hot_key.chords[1..].to_vec(),
+                    logical_key: hot_key.logical.as_ref().map(|l| l.logical_key),
+                }
+            })
+            .collect();
+        hot_keys.sort_by_key(|info| info.handle.0);
+        hot_keys
     }
 
     fn on_method_call(&self, call: MethodCall) -> PlatformResult {
@@ -96,9 +444,19 @@ impl HotKeyManager {
             "createHotKey" => self
                 .create_hot_key(call.isolate, call.args.try_into()?)
                 .into_platform_result(),
+            "createLogicalHotKey" => self
+                .create_logical_hot_key(call.isolate, call.args.try_into()?)
+                .into_platform_result(),
+            "createHotKeySequence" => self
+                .create_hot_key_sequence(call.isolate, call.args.try_into()?)
+                .into_platform_result(),
             "destroyHotKey" => self
                 .destroy_hot_key(call.args.try_into()?)
                 .into_platform_result(),
+            "canRegister" => self
+                .can_register(call.args.try_into()?)
+                .into_platform_result(),
+            "listHotKeys" => Ok(self.list_hot_keys(call.isolate).into()),
             _ => Ok(Value::Null),
         }
     }
@@ -113,7 +471,8 @@ impl MethodHandler for HotKeyManager {
         self.invoker.set(invoker);
     }
 
-    fn assign_weak_self(&self, weak_self: std::rc::Weak<Self>) {
+    fn assign_weak_self(&self, weak_self: Weak<Self>) {
+        self.weak_self.set(weak_self.clone());
         let platform_manager = Rc::new(PlatformHotKeyManager::new(weak_self));
         platform_manager.assign_weak_self(Rc::downgrade(&platform_manager));
         self.platform_manager.set(platform_manager);
@@ -121,37 +480,69 @@ impl MethodHandler for HotKeyManager {
 
     fn on_isolate_destroyed(&self, isolate: IsolateId) {
         let handles = self
-            .handle_to_isolate
+            .hot_keys
             .borrow()
             .iter()
-            .filter_map(|(handle, id)| if *id == isolate { Some(*handle) } else { None })
+            .filter_map(|(handle, hot_key)| {
+                if hot_key.isolate == isolate {
+                    Some(*handle)
+                } else {
+                    None
+                }
+            })
             .collect::<Vec<_>>();
         for handle in handles {
-            self.handle_to_isolate.borrow_mut().remove(&handle);
-            self.platform_manager.destroy_hot_key(handle).ok_log();
+            self.remove_hot_key(handle).ok_log();
         }
     }
 }
 
-impl HotKeyManagerDelegate for HotKeyManager {
-    fn on_hot_key_pressed(&self, handle: HotKeyHandle) {
-        let handle_to_isolate = self.handle_to_isolate.borrow();
-        let isolate = handle_to_isolate.get(&handle);
-        if let Some(isolate) = isolate {
+impl HotKeyManager {
+    fn notify(&self, method: &str, handle: HotKeyHandle) {
+        let hot_keys = self.hot_keys.borrow();
+        if let Some(hot_key) = hot_keys.get(&handle) {
             self.invoker
-                .call_method(*isolate, "onHotKeyPressed", handle, |r| {
+                .call_method(hot_key.isolate, method, handle, |r| {
                     r.ok_log();
                 });
         }
     }
+}
+
+impl HotKeyManagerDelegate for HotKeyManager {
+    fn on_hot_key_pressed(&self, handle: HotKeyHandle) {
+        if let Some(chord) = self.chord_for_platform_handle(handle) {
+            let event = self.sequences.borrow_mut().key_pressed(&chord);
+            match event {
+                SequenceEvent::Completed(sequence) => {
+                    self.pending_release.set(Some((handle, sequence)));
+                    self.update_grabs();
+                    self.notify("onHotKeyPressed", sequence);
+                    return;
+                }
+                SequenceEvent::InProgress {
+                    generation,
+                    timeout,
+                } => {
+                    self.schedule_sequence_timeout(generation, timeout);
+                    self.update_grabs();
+                    return;
+                }
+                SequenceEvent::NoMatch => self.update_grabs(),
+            }
+        }
+        self.notify("onHotKeyPressed", handle);
+    }
+
     fn on_hot_key_released(&self, handle: HotKeyHandle) {
-        let handle_to_isolate = self.handle_to_isolate.borrow();
-        let isolate = handle_to_isolate.get(&handle);
-        if let Some(isolate) = isolate {
-            self.invoker
-                .call_method(*isolate, "onHotKeyReleased", handle, |r| {
-                    r.ok_log();
-                });
+        if let Some((grab, sequence)) = self.pending_release.get() {
+            if grab == handle {
+                self.pending_release.set(None);
+                self.update_grabs();
+                self.notify("onHotKeyReleased", sequence);
+                return;
+            }
         }
+        self.notify("onHotKeyReleased", handle);
     }
 }
@@ -0,0 +1,277 @@
+use std::{
+    collections::{HashMap, HashSet},
+    time::Duration,
+};
+
+use irondash_message_channel::TryFromValue;
+
+use crate::{
+    error::{NativeExtensionsError, NativeExtensionsResult},
+    hot_key_manager::{HotKeyCreateRequest, HotKeyHandle},
+};
+
+#[derive(TryFromValue, Debug, Clone)]
+#[irondash(rename_all = "camelCase")]
+pub struct HotKeySequenceCreateRequest {
+    pub chords: Vec<HotKeyCreateRequest>,
+    /// Maximum time in seconds between two consecutive chords.
+    pub timeout: f64,
+}
+
+impl HotKeySequenceCreateRequest {
+    /// Returns timeout as duration. Negative timeout is treated as zero.
+    pub fn timeout_duration(&self) -> NativeExtensionsResult<Duration> {
+        Duration::try_from_secs_f64(self.timeout.max(0.0))
+            .map_err(|_| NativeExtensionsError::InvalidData)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum SequenceEvent {
+    /// Chord completed sequence with given handle.
+    Completed(HotKeyHandle),
+    /// Chord is part of at least one sequence. Timer for `generation` should
+    /// be scheduled to cancel the sequence after `timeout`.
+    InProgress { generation: u64, timeout: Duration },
+    /// Chord is not part of any sequence.
+    NoMatch,
+}
+
+struct Sequence {
+    chords: Vec<HotKeyCreateRequest>,
+    timeout: Duration,
+}
+
+/// Returns whether two chord sequences can not be registered together
+/// because one is a prefix of the other.
+pub fn sequences_conflict(a: &[HotKeyCreateRequest], b: &[HotKeyCreateRequest]) -> bool {
+    a.starts_with(b) || b.starts_with(a)
+}
+
+/// Tracks progress of multi-stroke hot keys. Platform only reports single
+/// chords; the tracker decides which chords need to be grabbed in current
+/// state and when a sequence is completed. Timers are scheduled by the
+/// caller.
+#[derive(Default)]
+pub struct HotKeySequenceTracker {
+    sequences: HashMap<HotKeyHandle, Sequence>,
+    progress: Vec<HotKeyCreateRequest>,
+    generation: u64,
+}
+
+impl HotKeySequenceTracker {
+    pub fn add(
+        &mut self,
+        handle: HotKeyHandle,
+        chords: Vec<HotKeyCreateRequest>,
+        timeout: Duration,
+    ) {
+        self.sequences.insert(handle, Sequence { chords, timeout });
+        self.reset();
+    }
+
+    pub fn remove(&mut self, handle: HotKeyHandle) {
+        if self.sequences.remove(&handle).is_some() {
+            self.reset();
+        }
+    }
+
+    /// Called when grabbed chord is pressed.
+    pub fn key_pressed(&mut self, chord: &HotKeyCreateRequest) -> SequenceEvent {
+        let mut progress = std::mem::take(&mut self.progress);
+        let restart = !progress.is_empty();
+        progress.push(chord.clone());
+        match self.advance(progress) {
+            // Chord that breaks current sequence may start a new one.
+            SequenceEvent::NoMatch if restart => self.advance(vec![chord.clone()]),
+            event => event,
+        }
+    }
+
+    fn advance(&mut self, progress: Vec<HotKeyCreateRequest>) -> SequenceEvent {
+        self.generation += 1;
+        let completed = self
+            .sequences
+            .iter()
+            .find(|(_, s)| s.chords == progress)
+            .map(|(handle, _)| *handle);
+        if let Some(handle) = completed {
+            return SequenceEvent::Completed(handle);
+        }
+        let timeout = self
+            .sequences
+            .values()
+            .filter(|s| s.chords.starts_with(&progress))
+            .map(|s| s.timeout)
+            .max();
+        match timeout {
+            Some(timeout) => {
+                self.progress = progress;
+                SequenceEvent::InProgress {
+                    generation: self.generation,
+                    timeout,
+                }
+            }
+            None => SequenceEvent::NoMatch,
+        }
+    }
+
+    /// Called when timer scheduled for `generation` fires. Returns true if
+    /// the sequence in progress was cancelled.
+    pub fn timer_fired(&mut self, generation: u64) -> bool {
+        if generation != self.generation || self.progress.is_empty() {
+            return false;
+        }
+        self.reset();
+        true
+    }
+
+    pub fn reset(&mut self) {
+        self.progress.clear();
+        self.generation += 1;
+    }
+
+    /// Chords that need to be grabbed in current state. These are first
+    /// chords of all sequences and, while a sequence is in progress, the
+    /// chords that can follow.
+    pub fn grabs(&self) -> HashSet<HotKeyCreateRequest> {
+        let mut grabs: HashSet<_> = self
+            .sequences
+            .values()
+            .filter_map(|s| s.chords.first().cloned())
+            .collect();
+        if !self.progress.is_empty() {
+            grabs.extend(self.sequences.values().filter_map(|s| {
+                if s.chords.starts_with(&self.progress) {
+                    s.chords.get(self.progress.len()).cloned()
+                } else {
+                    None
+                }
+            }));
+        }
+        grabs
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use crate::hot_key_manager::{HotKeyCreateRequest, HotKeyHandle};
+
+    use super::{
+        sequences_conflict, HotKeySequenceCreateRequest, HotKeySequenceTracker, SequenceEvent,
+    };
+
+    fn ctrl(platform_code: i64) -> HotKeyCreateRequest {
+        HotKeyCreateRequest {
+            alt: false,
+            shift: false,
+            meta: false,
+            control: true,
+            platform_code,
+        }
+    }
+
+    const K: i64 = 40;
+    const S: i64 = 1;
+    const D: i64 = 2;
+
+    fn handle(id: i64) -> HotKeyHandle {
+        id.into()
+    }
+
+    fn tracker() -> HotKeySequenceTracker {
+        let mut tracker = HotKeySequenceTracker::default();
+        tracker.add(handle(1), vec![ctrl(K), ctrl(S)], Duration::from_secs(1));
+        tracker.add(handle(2), vec![ctrl(K), ctrl(D)], Duration::from_secs(2));
+        tracker
+    }
+
+    #[test]
+    fn test_completed() {
+        let mut tracker = tracker();
+        assert_eq!(tracker.grabs(), [ctrl(K)].into());
+        let event = tracker.key_pressed(&ctrl(K));
+        assert!(matches!(
+            event,
+            SequenceEvent::InProgress { timeout, .. } if timeout == Duration::from_secs(2)
+        ));
+        assert_eq!(tracker.grabs(), [ctrl(K), ctrl(S), ctrl(D)].into());
+        assert_eq!(
+            tracker.key_pressed(&ctrl(D)),
+            SequenceEvent::Completed(handle(2))
+        );
+        assert_eq!(tracker.grabs(), [ctrl(K)].into());
+    }
+
+    #[test]
+    fn test_no_match() {
+        let mut tracker = tracker();
+        assert_eq!(tracker.key_pressed(&ctrl(S)), SequenceEvent::NoMatch);
+        tracker.key_pressed(&ctrl(K));
+        assert_eq!(tracker.key_pressed(&ctrl(3)), SequenceEvent::NoMatch);
+        assert_eq!(tracker.grabs(), [ctrl(K)].into());
+        assert_eq!(tracker.key_pressed(&ctrl(S)), SequenceEvent::NoMatch);
+    }
+
+    #[test]
+    fn test_restart() {
+        let mut tracker = tracker();
+        tracker.key_pressed(&ctrl(K));
+        assert!(matches!(
+            tracker.key_pressed(&ctrl(K)),
+            SequenceEvent::InProgress { .. }
+        ));
+        assert_eq!(
+            tracker.key_pressed(&ctrl(S)),
+            SequenceEvent::Completed(handle(1))
+        );
+    }
+
+    #[test]
+    fn test_timeout() {
+        let mut tracker = tracker();
+        let generation = match tracker.key_pressed(&ctrl(K)) {
+            SequenceEvent::InProgress { generation, .. } => generation,
+            event => panic!("unexpected event {event:?}"),
+        };
+        assert!(tracker.timer_fired(generation));
+        assert!(!tracker.timer_fired(generation));
+        assert_eq!(tracker.key_pressed(&ctrl(S)), SequenceEvent::NoMatch);
+
+        // Timer from previous chord is ignored.
+        let generation = match tracker.key_pressed(&ctrl(K)) {
+            SequenceEvent::InProgress { generation, .. } => generation,
+            event => panic!("unexpected event {event:?}"),
+        };
+        tracker.key_pressed(&ctrl(K));
+        assert!(!tracker.timer_fired(generation));
+    }
+
+    #[test]
+    fn test_conflict() {
+        assert!(sequences_conflict(&[ctrl(K)], &[ctrl(K), ctrl(S)]));
+        assert!(sequences_conflict(&[ctrl(K), ctrl(S)], &[ctrl(K), ctrl(S)]));
+        assert!(!sequences_conflict(
+            &[ctrl(K), ctrl(S)],
+            &[ctrl(K), ctrl(D)]
+        ));
+        assert!(!sequences_conflict(&[ctrl(S)], &[ctrl(K), ctrl(S)]));
+    }
+
+    #[test]
+    fn test_timeout_duration() {
+        let request = |timeout| HotKeySequenceCreateRequest {
+            chords: vec![ctrl(K), ctrl(S)],
+            timeout,
+        };
+        assert_eq!(
+            request(1.5).timeout_duration().unwrap(),
+            Duration::from_millis(1500)
+        );
+        assert_eq!(request(-1.0).timeout_duration().unwrap(), Duration::ZERO);
+        assert!(request(f64::INFINITY).timeout_duration().is_err());
+        assert!(request(1e300).timeout_duration().is_err());
+    }
+}
@@ -0,0 +1,427 @@
+use crate::{
+    api_model::ImageData,
+    error::{NativeExtensionsError, NativeExtensionsResult},
+    log::OkLog,
+};
+
+use log::warn;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChannelOrder {
+    Rgba,
+    Bgra,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AlphaMode {
+    Straight,
+    Premultiplied,
+}
+
+/// 32-bit pixel format with 8 bits per channel.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PixelFormat {
+    pub order: ChannelOrder,
+    pub alpha: AlphaMode,
+}
+
+impl PixelFormat {
+    /// Format of [`ImageData`] received from Dart.
+    pub const RGBA_STRAIGHT: Self = Self {
+        order: ChannelOrder::Rgba,
+        alpha: AlphaMode::Straight,
+    };
+    pub const RGBA_PREMULTIPLIED: Self = Self {
+        order: ChannelOrder::Rgba,
+        alpha: AlphaMode::Premultiplied,
+    };
+    pub const BGRA_STRAIGHT: Self = Self {
+        order: ChannelOrder::Bgra,
+        alpha: AlphaMode::Straight,
+    };
+    /// Cairo ARGB32 on little endian, Direct2D premultiplied BGRA.
+    pub const BGRA_PREMULTIPLIED: Self = Self {
+        order: ChannelOrder::Bgra,
+        alpha: AlphaMode::Premultiplied,
+    };
+
+    fn read(&self, pixel: &[u8]) -> [u8; 4] {
+        let [r, g, b, a] = match self.order {
+            ChannelOrder::Rgba => [pixel[0], pixel[1], pixel[2], pixel[3]],
+            ChannelOrder::Bgra => [pixel[2], pixel[1], pixel[0], pixel[3]],
+        };
+        match self.alpha {
+            AlphaMode::Straight => [r, g, b, a],
+            AlphaMode::Premultiplied => [
+                unpremultiply(r, a),
+                unpremultiply(g, a),
+                unpremultiply(b, a),
+                a,
+            ],
+        }
+    }
+
+    fn write(&self, [r, g, b, a]: [u8; 4], pixel: &mut [u8]) {
+        let [r, g, b] = match self.alpha {
+            AlphaMode::Straight => [r, g, b],
+            AlphaMode::Premultiplied => [premultiply(r, a), premultiply(g, a), premultiply(b, a)],
+        };
+        let channels = match self.order {
+            ChannelOrder::Rgba => [r, g, b, a],
+            ChannelOrder::Bgra => [b, g, r, a],
+        };
+        pixel[..4].copy_from_slice(&channels);
+    }
+}
+
+fn premultiply(c: u8, a: u8) -> u8 {
+    ((c as u32 * a as u32 + 127) / 255) as u8
+}
+
+fn unpremultiply(c: u8, a: u8) -> u8 {
+    if a == 0 {
+        0
+    } else {
+        ((c as u32 * 255 + a as u32 / 2) / a as u32).min(255) as u8
+    }
+}
+
+/// Memory layout of pixel buffer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PixelLayout {
+    pub format: PixelFormat,
+    pub bytes_per_row: usize,
+    /// Rows are stored from bottom to top (i.e. Windows DIB).
+    pub bottom_up: bool,
+}
+
+impl PixelLayout {
+    pub fn packed(format: PixelFormat, width: usize) -> Self {
+        Self {
+            format,
+            // Overflowing width fails validation.
+            bytes_per_row: width.saturating_mul(4),
+            bottom_up: false,
+        }
+    }
+
+    /// Minimum buffer length for image of given size. Returns `None` if the
+    /// length overflows.
+    pub fn buffer_len(&self, width: usize, height: usize) -> Option<usize> {
+        if height == 0 {
+            Some(0)
+        } else {
+            self.bytes_per_row
+                .checked_mul(height - 1)?
+                .checked_add(width.checked_mul(4)?)
+        }
+    }
+
+    fn validate(&self, width: usize, height: usize, len: usize) -> NativeExtensionsResult<()> {
+        let (Some(line_length), Some(required)) =
+            (width.checked_mul(4), self.buffer_len(width, height))
+        else {
+            return Err(invalid_image(format!(
+                "size {width}x{height} ({} bytes per row) overflows",
+                self.bytes_per_row
+            )));
+        };
+        if self.bytes_per_row < line_length {
+            return Err(invalid_image(format!(
+                "{} bytes per row is not enough for width {}",
+                self.bytes_per_row, width
+            )));
+        }
+        if len < required {
+            return Err(invalid_image(format!(
+                "buffer has {len} bytes, {required} bytes required"
+            )));
+        }
+        Ok(())
+    }
+
+    fn row_offset(&self, y: usize, height: usize) -> usize {
+        let row = if self.bottom_up { height - 1 - y } else { y };
+        row * self.bytes_per_row
+    }
+}
+
+fn invalid_image(message: String) -> NativeExtensionsError {
+    warn!("Invalid image data: {message}");
+    NativeExtensionsError::InvalidData
+}
+
+/// Converts pixels between formats and layouts. Both buffers are validated
+/// against image size.
+pub fn convert_pixels(
+    width: usize,
+    height: usize,
+    src: &[u8],
+    src_layout: PixelLayout,
+    dst: &mut [u8],
+    dst_layout: PixelLayout,
+) -> NativeExtensionsResult<()> {
+    src_layout.validate(width, height, src.len())?;
+    dst_layout.validate(width, height, dst.len())?;
+    let line_length = width * 4;
+    for y in 0..height {
+        let src_start = src_layout.row_offset(y, height);
+        let dst_start = dst_layout.row_offset(y, height);
+        let src_line = &src[src_start..src_start + line_length];
+        let dst_line = &mut dst[dst_start..dst_start + line_length];
+        if src_layout.format == dst_layout.format {
+            dst_line.copy_from_slice(src_line);
+            continue;
+        }
+        for (src_pixel, dst_pixel) in src_line.chunks_exact(4).zip(dst_line.chunks_exact_mut(4)) {
+            dst_layout
+                .format
+                .write(src_layout.format.read(src_pixel), dst_pixel);
+        }
+    }
+    Ok(())
+}
+
+impl ImageData {
+    /// Creates image data from straight alpha RGBA pixels, verifying that
+    /// the buffer matches the dimensions.
+    pub fn new(
+        width: i32,
+        height: i32,
+        bytes_per_row: i32,
+        data: Vec<u8>,
+        device_pixel_ratio: Option<f64>,
+    ) -> NativeExtensionsResult<Self> {
+        let res = Self {
+            width,
+            height,
+            bytes_per_row,
+            data,
+            device_pixel_ratio,
+        };
+        res.validate()?;
+        Ok(res)
+    }
+
+    /// Transparent image of given size. Returns empty image if the size is
+    /// too large to allocate.
+    pub fn blank(width: i32, height: i32, device_pixel_ratio: Option<f64>) -> Self {
+        let width = width.max(0);
+        let height = height.max(0);
+        let len = (width as usize)
+            .checked_mul(height as usize)
+            .and_then(|pixels| pixels.checked_mul(4))
+            // Vec can not hold more than isize::MAX bytes.
+            .filter(|len| *len <= isize::MAX as usize);
+        let (width, height, len) = match (width.checked_mul(4), len) {
+            (Some(_), Some(len)) => (width, height, len),
+            _ => (0, 0, 0),
+        };
+        Self {
+            width,
+            height,
+            bytes_per_row: width * 4,
+            data: vec![0; len],
+            device_pixel_ratio,
+        }
+    }
+
+    /// Returns the image if valid. Otherwise logs the error and returns blank
+    /// image of same size, for call sites that can not fail.
+    pub fn validated(self) -> Self {
+        match self.validate().ok_log() {
+            Some(()) => self,
+            None => Self::blank(self.width, self.height, self.device_pixel_ratio),
+        }
+    }
+
+    /// Layout of the pixel data.
+    pub fn layout(&self) -> PixelLayout {
+        PixelLayout {
+            format: PixelFormat::RGBA_STRAIGHT,
+            bytes_per_row: self.bytes_per_row.max(0) as usize,
+            bottom_up: false,
+        }
+    }
+
+    /// Verifies that the data buffer is large enough for image dimensions and
+    /// stride. Image data coming from Dart should be validated before
+    /// accessing pixels directly.
+    pub fn validate(&self) -> NativeExtensionsResult<()> {
+        if self.width < 0 || self.height < 0 || self.bytes_per_row < 0 {
+            return Err(invalid_image(format!(
+                "negative dimensions {}x{} ({} bytes per row)",
+                self.width, self.height, self.bytes_per_row
+            )));
+        }
+        self.layout()
+            .validate(self.width as usize, self.height as usize, self.data.len())
+    }
+
+    /// Converts pixels to given layout. Returned buffer has
+    /// `bytes_per_row * height` bytes.
+    pub fn to_layout(&self, layout: PixelLayout) -> NativeExtensionsResult<Vec<u8>> {
+        self.validate()?;
+        let (width, height) = (self.width as usize, self.height as usize);
+        let len = layout
+            .bytes_per_row
+            .checked_mul(height)
+            .ok_or_else(|| invalid_image(format!("size {width}x{height} overflows")))?;
+        let mut res = vec![0; len];
+        convert_pixels(width, height, &self.data, self.layout(), &mut res, layout)?;
+        Ok(res)
+    }
+
+    /// Converts pixels to given format without padding between rows.
+    pub fn to_packed(&self, format: PixelFormat) -> NativeExtensionsResult<Vec<u8>> {
+        self.to_layout(PixelLayout::packed(format, self.width.max(0) as usize))
+    }
+
+    /// Returns copy of the image without padding between rows.
+    pub fn packed(&self) -> NativeExtensionsResult<ImageData> {
+        let bytes_per_row = self
+            .width
+            .checked_mul(4)
+            .ok_or_else(|| invalid_image(format!("width {} overflows", self.width)))?;
+        Ok(Self {
+            width: self.width,
+            height: self.height,
+            bytes_per_row,
+            data: self.to_packed(PixelFormat::RGBA_STRAIGHT)?,
+            device_pixel_ratio: self.device_pixel_ratio,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{api_model::ImageData, error::NativeExtensionsError};
+
+    use super::{convert_pixels, PixelFormat, PixelLayout};
+
+    // 2x2 image with 4 bytes of padding per row.
+    fn image() -> ImageData {
+        ImageData::new(
+            2,
+            2,
+            12,
+            vec![
+                255, 0, 0, 255, /**/ 0, 255, 0, 128, /**/ 9, 9, 9, 9, //
+                0, 0, 255, 0, /**/ 10, 20, 30, 64, /**/ 9, 9, 9, 9,
+            ],
+            None,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(ImageData::new(2, 2, 8, vec![0; 16], None).is_ok());
+        // Last row does not need padding.
+        assert!(ImageData::new(2, 2, 12, vec![0; 20], None).is_ok());
+        assert!(matches!(
+            ImageData::new(2, 2, 8, vec![0; 15], None),
+            Err(NativeExtensionsError::InvalidData)
+        ));
+        assert!(ImageData::new(2, 2, 4, vec![0; 16], None).is_err());
+        assert!(ImageData::new(-1, 2, 8, vec![0; 16], None).is_err());
+        assert!(ImageData::new(0, 0, 0, vec![], None).is_ok());
+
+        let mut image = image();
+        image.data.truncate(10);
+        assert!(image.to_packed(PixelFormat::BGRA_PREMULTIPLIED).is_err());
+        assert!(image.packed().is_err());
+    }
+
+    #[test]
+    fn test_validate_overflow() {
+        assert!(matches!(
+            ImageData::new(i32::MAX, 2, i32::MAX, vec![0; 16], None),
+            Err(NativeExtensionsError::InvalidData)
+        ));
+        assert!(matches!(
+            ImageData::new(1, i32::MAX, i32::MAX, vec![0; 16], None),
+            Err(NativeExtensionsError::InvalidData)
+        ));
+        let layout = PixelLayout {
+            format: PixelFormat::RGBA_STRAIGHT,
+            bytes_per_row: usize::MAX,
+            bottom_up: false,
+        };
+        assert_eq!(layout.buffer_len(1, 2), None);
+        assert_eq!(layout.buffer_len(usize::MAX, 1), None);
+        assert_eq!(layout.buffer_len(usize::MAX, 0), Some(0));
+        let mut dst = [0; 16];
+        assert!(convert_pixels(1, 2, &[0; 16], layout, &mut dst, layout).is_err());
+    }
+
+    #[test]
+    fn test_blank() {
+        let image = ImageData::blank(3, 2, Some(2.0));
+        assert_eq!((image.width, image.height, image.bytes_per_row), (3, 2, 12));
+        assert_eq!(image.data, vec![0; 24]);
+        assert!(image.validate().is_ok());
+
+        let image = ImageData::blank(-3, 2, None);
+        assert_eq!((image.width, image.height), (0, 2));
+        assert!(image.data.is_empty());
+
+        let image = ImageData::blank(i32::MAX, i32::MAX, None);
+        assert_eq!((image.width, image.height, image.bytes_per_row), (0, 0, 0));
+        assert!(image.data.is_empty());
+    }
+
+    #[test]
+    fn test_packed() {
+        let image = image().packed().unwrap();
+        assert_eq!(image.bytes_per_row, 8);
+        assert_eq!(
+            image.data,
+            [255, 0, 0, 255, 0, 255, 0, 128, 0, 0, 255, 0, 10, 20, 30, 64]
+        );
+    }
+
+    #[test]
+    fn test_bgra_premultiplied() {
+        let data = image().to_packed(PixelFormat::BGRA_PREMULTIPLIED).unwrap();
+        assert_eq!(
+            data,
+            [0, 0, 255, 255, 0, 128, 0, 128, 0, 0, 0, 0, 8, 5, 3, 64]
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let image = image();
+        let premultiplied = image.to_packed(PixelFormat::RGBA_PREMULTIPLIED).unwrap();
+        let mut straight = vec![0; 16];
+        convert_pixels(
+            2,
+            2,
+            &premultiplied,
+            PixelLayout::packed(PixelFormat::RGBA_PREMULTIPLIED, 2),
+            &mut straight,
+            PixelLayout::packed(PixelFormat::RGBA_STRAIGHT, 2),
+        )
+        .unwrap();
+        // Fully transparent pixel loses color; others survive within rounding.
+        assert_eq!(
+            straight,
+            [255, 0, 0, 255, 0, 255, 0, 128, 0, 0, 0, 0, 12, 20, 32, 64]
+        );
+    }
+
+    #[test]
+    fn test_bottom_up() {
+        let layout = PixelLayout {
+            format: PixelFormat::BGRA_STRAIGHT,
+            bytes_per_row: 8,
+            bottom_up: true,
+        };
+        let data = image().to_layout(layout).unwrap();
+        assert_eq!(
+            data,
+            [255, 0, 0, 0, 30, 20, 10, 64, 0, 0, 255, 255, 0, 255, 0, 128]
+        );
+    }
+}
@@ -1,17 +1,20 @@
 use std::{
     cell::RefCell,
-    collections::HashSet,
+    collections::{HashMap, HashSet},
     rc::{Rc, Weak},
 };
 
 use irondash_message_channel::{
     IntoValue, IsolateId, Late, MethodCall, MethodCallReply, MethodHandler, MethodInvoker,
-    RegisteredMethodHandler, Value,
+    PlatformResult, RegisteredMethodHandler, TryFromValue, Value,
 };
 
-use crate::{context::Context, log::OkLog, platform_impl::platform::PlatformKeyboardLayout};
+use crate::{
+    context::Context, hot_key_manager::GetHotKeyManager, log::OkLog,
+    platform_impl::platform::PlatformKeyboardLayout,
+};
 
-#[derive(IntoValue, Clone)]
+#[derive(IntoValue, TryFromValue, Clone, Debug, PartialEq)]
 #[irondash(rename_all = "camelCase")]
 pub struct Key {
     pub platform: i64,
@@ -21,18 +24,47 @@ pub struct Key {
     pub logical_alt: Option<i64>,
     pub logical_alt_shift: Option<i64>,
     pub logical_meta: Option<i64>,
+    /// Whether the key is a dead key at any level.
+    pub is_dead_key: bool,
+    /// Combining characters (i.e. U+0301 for dead acute) for levels at which
+    /// the key is a dead key.
+    pub dead_key: Option<i64>,
+    pub dead_key_shift: Option<i64>,
+    pub dead_key_alt: Option<i64>,
+    pub dead_key_alt_shift: Option<i64>,
 }
 
-#[derive(IntoValue, Clone)]
+#[derive(IntoValue, TryFromValue, Clone, Debug, PartialEq)]
 #[irondash(rename_all = "camelCase")]
 pub struct KeyboardLayout {
     pub keys: Vec<Key>,
 }
 
+impl KeyboardLayout {
+    /// Returns platform code of the key producing given logical key and
+    /// whether shift needs to be pressed to produce it. Keys producing the
+    /// logical key without modifiers are preferred.
+    pub fn platform_code_for_logical_key(&self, logical_key: i64) -> Option<(i64, bool)> {
+        let find = |f: fn(&Key) -> Option<i64>| {
+            self.keys
+                .iter()
+                .find(|k| f(k) == Some(logical_key))
+                .map(|k| k.platform)
+        };
+        find(|k| k.logical)
+            .map(|code| (code, false))
+            .or_else(|| find(|k| k.logical_shift).map(|code| (code, true)))
+    }
+}
+
 pub struct KeyboardLayoutManager {
     pub(crate) platform_layout: Late<Rc<PlatformKeyboardLayout>>,
     invoker: Late<MethodInvoker>,
     isolates: RefCell<HashSet<IsolateId>>,
+    /// Layouts loaded from snapshot that replace platform layout for given
+    /// isolate until cleared. Overrides only affect layout reported to the
+    /// isolate; hot keys are always resolved using platform layout.
+    layout_overrides: RefCell<HashMap<IsolateId, KeyboardLayout>>,
 }
 
 pub trait KeyboardLayoutDelegate {
@@ -55,23 +87,64 @@ impl KeyboardLayoutManager {
             platform_layout: Late::new(),
             invoker: Late::new(),
             isolates: RefCell::new(HashSet::new()),
+            layout_overrides: RefCell::new(HashMap::new()),
         }
         .register("KeyboardLayoutManager")
     }
-}
 
-impl MethodHandler for KeyboardLayoutManager {
-    fn on_method_call(&self, call: MethodCall, reply: MethodCallReply) {
-        #[allow(clippy::single_match)]
+    /// Returns current platform layout, ignoring layout overrides.
+    pub fn platform_layout(&self) -> Option<KeyboardLayout> {
+        self.platform_layout.get_current_layout()
+    }
+
+    /// Returns layout override for given isolate or platform layout if not
+    /// overridden.
+    fn layout_for_isolate(&self, isolate: IsolateId) -> Option<KeyboardLayout> {
+        match self.layout_overrides.borrow().get(&isolate) {
+            Some(layout) => Some(layout.clone()),
+            None => self.platform_layout(),
+        }
+    }
+
+    fn set_layout_override(&self, isolate: IsolateId, layout: Option<KeyboardLayout>) {
+        match layout {
+            Some(layout) => self.layout_overrides.borrow_mut().insert(isolate, layout),
+            None => self.layout_overrides.borrow_mut().remove(&isolate),
+        };
+        self.notify_layout_changed(isolate);
+    }
+
+    fn notify_layout_changed(&self, isolate: IsolateId) {
+        let layout: Value = self.layout_for_isolate(isolate).into();
+        self.invoker
+            .call_method(isolate, "onLayoutChanged", layout, |r| {
+                r.ok_log();
+            });
+    }
+
+    fn on_method_call(&self, call: MethodCall) -> PlatformResult {
         match call.method.as_str() {
             "getKeyboardLayout" => {
                 self.isolates.borrow_mut().insert(call.isolate);
-                let layout = self.platform_layout.get_current_layout();
-                reply.send_ok(layout);
+                Ok(self.layout_for_isolate(call.isolate).into())
+            }
+            "setLayoutOverride" => {
+                self.set_layout_override(call.isolate, Some(call.args.try_into()?));
+                Ok(Value::Null)
             }
-            _ => {}
+            "clearLayoutOverride" => {
+                self.set_layout_override(call.isolate, None);
+                Ok(Value::Null)
+            }
+            _ => Ok(Value::Null),
         }
     }
+}
+
+impl MethodHandler for KeyboardLayoutManager {
+    fn on_method_call(&self, call: MethodCall, reply: MethodCallReply) {
+        reply.send(self.on_method_call(call))
+    }
 
     fn assign_weak_self(&self, weak_self: Weak<Self>) {
         let delegate: Weak<dyn KeyboardLayoutDelegate> = weak_self;
@@ -88,19 +161,87 @@ impl MethodHandler for KeyboardLayoutManager {
     /// Called when isolate is about to be destroyed.
     fn on_isolate_destroyed(&self, isolate: IsolateId) {
         self.isolates.borrow_mut().remove(&isolate);
+        self.layout_overrides.borrow_mut().remove(&isolate);
     }
 }
 
 impl KeyboardLayoutDelegate for KeyboardLayoutManager {
     fn keyboard_map_did_change(&self) {
-        let layout = self.platform_layout.get_current_layout();
-        let layout: Value = layout.into();
-        let isolates = self.isolates.borrow();
-        for isolate in isolates.iter() {
-            self.invoker
-                .call_method(*isolate, "onLayoutChanged", layout.clone(), |r| {
-                    r.ok_log();
-                });
+        Context::get()
+            .hot_key_manager()
+            .keyboard_layout_did_change();
+        // Platform changes are not visible to isolates with overridden layout.
+        let isolates: Vec<_> = self
+            .isolates
+            .borrow()
+            .iter()
+            .filter(|isolate| !self.layout_overrides.borrow().contains_key(isolate))
+            .cloned()
+            .collect();
+        for isolate in isolates {
+            self.notify_layout_changed(isolate);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use irondash_message_channel::Value;
+
+    use super::{Key, KeyboardLayout};
+
+    fn key(platform: i64, logical: char, logical_shift: char) -> Key {
+        Key {
+            platform,
+            physical: 0,
+            logical: Some(logical as i64),
+            logical_shift: Some(logical_shift as i64),
+            logical_alt: None,
+            logical_alt_shift: None,
+            logical_meta: None,
+            is_dead_key: false,
+            dead_key: None,
+            dead_key_shift: None,
+            dead_key_alt: None,
+            dead_key_alt_shift: None,
         }
     }
+
+    #[test]
+    fn test_platform_code_for_logical_key() {
+        // German layout has Z and Y swapped.
+        let layout = KeyboardLayout {
+            keys: vec![key(6, 'y', 'Y'), key(16, 'z', 'Z'), key(26, '7', '/')],
+        };
+        assert_eq!(
+            layout.platform_code_for_logical_key('z' as i64),
+            Some((16, false))
+        );
+        assert_eq!(
+            layout.platform_code_for_logical_key('y' as i64),
+            Some((6, false))
+        );
+        // Slash is on Shift+7.
+        assert_eq!(
+            layout.platform_code_for_logical_key('/' as i64),
+            Some((26, true))
+        );
+        assert_eq!(layout.platform_code_for_logical_key('q' as i64), None);
+    }
+
+    #[test]
+    fn test_layout_value_round_trip() {
+        let dead_key = Key {
+            logical_alt: Some('@' as i64),
+            is_dead_key: true,
+            dead_key_shift: Some(0x0301),
+            ..key(21, '\'', '`')
+        };
+        let layout = KeyboardLayout {
+            keys: vec![key(6, 'y', 'Y'), dead_key],
+        };
+        let value: Value = layout.clone().into();
+        let decoded: KeyboardLayout = value.try_into().unwrap();
+        assert_eq!(decoded, layout);
+    }
 }
@@ -0,0 +1,345 @@
+use std::{collections::HashMap, mem::size_of};
+
+use irondash_message_channel::{IsolateId, Value};
+
+use crate::{
+    api_model::DataProviderValueId,
+    segmented_queue::{FileSegment, Segment},
+};
+
+pub struct LazyDataCacheConfiguration {
+    /// Maximum amount of memory used by cached values. Zero disables the
+    /// cache.
+    pub max_memory_usage: usize,
+
+    /// Maximum size of values spilled to temporary files when evicted from
+    /// memory. If None evicted values are discarded.
+    pub max_disk_usage: Option<usize>,
+}
+
+/// Caching is disabled by default so that providers are asked for every
+/// request.
+impl Default for LazyDataCacheConfiguration {
+    fn default() -> Self {
+        Self {
+            max_memory_usage: 0,
+            max_disk_usage: None,
+        }
+    }
+}
+
+enum Storage {
+    Memory(Value),
+    /// Values are only spilled when they are a string or byte array.
+    File {
+        segment: FileSegment,
+        is_string: bool,
+    },
+}
+
+/// Value ids are only unique within an isolate.
+type CacheKey = (IsolateId, DataProviderValueId);
+
+struct CacheEntry {
+    storage: Storage,
+    /// Approximate memory size for values in memory, data length for values
+    /// in file.
+    size: usize,
+    last_used: u64,
+}
+
+/// Least recently used cache for resolved lazy data values.
+pub struct LazyDataCache {
+    configuration: LazyDataCacheConfiguration,
+    entries: HashMap<CacheKey, CacheEntry>,
+    memory_used: usize,
+    disk_used: usize,
+    counter: u64,
+}
+
+impl LazyDataCache {
+    pub fn new(configuration: LazyDataCacheConfiguration) -> Self {
+        Self {
+            configuration,
+            entries: HashMap::new(),
+            memory_used: 0,
+            disk_used: 0,
+            counter: 0,
+        }
+    }
+
+    pub fn set_configuration(&mut self, configuration: LazyDataCacheConfiguration) {
+        self.configuration = configuration;
+        self.trim();
+    }
+
+    pub fn get(&mut self, isolate_id: IsolateId, id: DataProviderValueId) -> Option<Value> {
+        let entry = self.entries.remove(&(isolate_id, id))?;
+        let value = match entry.storage {
+            Storage::Memory(value) => {
+                self.memory_used -= entry.size;
+                value
+            }
+            Storage::File { segment, is_string } => {
+                self.disk_used -= entry.size;
+                // Reading completed segment removes the file.
+                let mut data = Vec::with_capacity(entry.size);
+                loop {
+                    let chunk = segment.read(entry.size.max(1));
+                    if chunk.is_empty() {
+                        break;
+                    }
+                    data.extend_from_slice(&chunk);
+                }
+                if data.len() != entry.size {
+                    // Failed or incomplete read.
+                    return None;
+                }
+                if is_string {
+                    Value::String(String::from_utf8(data).ok()?)
+                } else {
+                    Value::U8List(data)
+                }
+            }
+        };
+        self.insert(isolate_id, id, value.clone());
+        Some(value)
+    }
+
+    pub fn insert(&mut self, isolate_id: IsolateId, id: DataProviderValueId, value: Value) {
+        self.remove(isolate_id, id);
+        let size = value_size(&value);
+        if size > self.configuration.max_memory_usage {
+            // Do not evict everything else for single value.
+            return;
+        }
+        self.counter += 1;
+        self.memory_used += size;
+        self.entries.insert(
+            (isolate_id, id),
+            CacheEntry {
+                storage: Storage::Memory(value),
+                size,
+                last_used: self.counter,
+            },
+        );
+        self.trim();
+    }
+
+    pub fn remove(&mut self, isolate_id: IsolateId, id: DataProviderValueId) {
+        self.remove_key(&(isolate_id, id));
+    }
+
+    /// Removes all values provided by given isolate.
+    pub fn remove_isolate(&mut self, isolate_id: IsolateId) {
+        let keys: Vec<_> = self
+            .entries
+            .keys()
+            .filter(|key| key.0 == isolate_id)
+            .cloned()
+            .collect();
+        for key in keys {
+            self.remove_key(&key);
+        }
+    }
+
+    fn remove_key(&mut self, key: &CacheKey) {
+        if let Some(entry) = self.entries.remove(key) {
+            match entry.storage {
+                Storage::Memory(_) => self.memory_used -= entry.size,
+                Storage::File { .. } => self.disk_used -= entry.size,
+            }
+        }
+    }
+
+    pub fn memory_used(&self) -> usize {
+        self.memory_used
+    }
+
+    pub fn disk_used(&self) -> usize {
+        self.disk_used
+    }
+
+    fn least_recently_used(&self, in_memory: bool) -> Option<CacheKey> {
+        self.entries
+            .iter()
+            .filter(|(_, e)| matches!(e.storage, Storage::Memory(_)) == in_memory)
+            .min_by_key(|(_, e)| e.last_used)
+            .map(|(key, _)| *key)
+    }
+
+    fn trim(&mut self) {
+        while self.memory_used > self.configuration.max_memory_usage {
+            match self.least_recently_used(true) {
+                Some(key) => self.spill(key),
+                None => break,
+            }
+        }
+        let max_disk_usage = self.configuration.max_disk_usage.unwrap_or(0);
+        while self.disk_used > max_disk_usage {
+            match self.least_recently_used(false) {
+                Some(key) => self.remove_key(&key),
+                None => break,
+            }
+        }
+    }
+
+    /// Moves value to a file segment if possible, otherwise drops it.
+    fn spill(&mut self, key: CacheKey) {
+        let entry = match self.entries.remove(&key) {
+            Some(entry) => entry,
+            None => return,
+        };
+        self.memory_used -= entry.size;
+        if self.configuration.max_disk_usage.is_none() {
+            return;
+        }
+        let (data, is_string) = match &entry.storage {
+            Storage::Memory(Value::String(s)) => (s.as_bytes(), true),
+            Storage::Memory(Value::U8List(data)) => (data.as_slice(), false),
+            _ => return,
+        };
+        if data.is_empty() {
+            return;
+        }
+        let segment = FileSegment::new(data.len() as u64);
+        if segment.write(data).is_err() {
+            return;
+        }
+        segment.complete();
+        let size = data.len();
+        self.disk_used += size;
+        self.entries.insert(
+            key,
+            CacheEntry {
+                storage: Storage::File { segment, is_string },
+                size,
+                last_used: entry.last_used,
+            },
+        );
+    }
+}
+
+/// Approximate amount of memory occupied by the value.
+fn value_size(value: &Value) -> usize {
+    fn list_size<T>(list: &[T]) -> usize {
+        size_of::<Value>() + std::mem::size_of_val(list)
+    }
+    match value {
+        Value::String(s) => size_of::<Value>() + s.len(),
+        Value::I8List(data) => list_size(data),
+        Value::U8List(data) => list_size(data),
+        Value::I16List(data) => list_size(data),
+        Value::U16List(data) => list_size(data),
+        Value::I32List(data) => list_size(data),
+        Value::U32List(data) => list_size(data),
+        Value::I64List(data) => list_size(data),
+        Value::F32List(data) => list_size(data),
+        Value::F64List(data) => list_size(data),
+        Value::List(list) => size_of::<Value>() + list.iter().map(value_size).sum::<usize>(),
+        _ => size_of::<Value>(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use irondash_message_channel::{IsolateId, Value};
+
+    use crate::api_model::DataProviderValueId;
+
+    use super::{LazyDataCache, LazyDataCacheConfiguration};
+
+    const ISOLATE: IsolateId = IsolateId(1);
+
+    fn id(id: i64) -> DataProviderValueId {
+        id.into()
+    }
+
+    fn cache() -> LazyDataCache {
+        LazyDataCache::new(LazyDataCacheConfiguration {
+            max_memory_usage: 1024,
+            max_disk_usage: None,
+        })
+    }
+
+    #[test]
+    fn test_disabled_by_default() {
+        let mut cache = LazyDataCache::new(LazyDataCacheConfiguration::default());
+        cache.insert(ISOLATE, id(1), Value::U8List(vec![1; 100]));
+        assert!(cache.get(ISOLATE, id(1)).is_none());
+        assert_eq!(cache.memory_used(), 0);
+    }
+
+    #[test]
+    fn test_evicts_least_recently_used() {
+        let mut cache = LazyDataCache::new(LazyDataCacheConfiguration {
+            max_memory_usage: 300,
+            max_disk_usage: None,
+        });
+        cache.insert(ISOLATE, id(1), Value::U8List(vec![1; 100]));
+        cache.insert(ISOLATE, id(2), Value::U8List(vec![2; 100]));
+        assert!(cache.get(ISOLATE, id(1)).is_some());
+        cache.insert(ISOLATE, id(3), Value::U8List(vec![3; 100]));
+        assert!(cache.get(ISOLATE, id(2)).is_none());
+        assert_eq!(cache.get(ISOLATE, id(1)), Some(Value::U8List(vec![1; 100])));
+        assert_eq!(cache.get(ISOLATE, id(3)), Some(Value::U8List(vec![3; 100])));
+        assert!(cache.memory_used() <= 300);
+    }
+
+    #[test]
+    fn test_spills_to_disk() {
+        let mut cache = LazyDataCache::new(LazyDataCacheConfiguration {
+            max_memory_usage: 300,
+            max_disk_usage: Some(1000),
+        });
+        cache.insert(ISOLATE, id(1), Value::String("a".repeat(100)));
+        cache.insert(ISOLATE, id(2), Value::U8List(vec![2; 100]));
+        cache.insert(ISOLATE, id(3), Value::U8List(vec![3; 100]));
+        assert!(cache.disk_used() > 0);
+        assert_eq!(
+            cache.get(ISOLATE, id(1)),
+            Some(Value::String("a".repeat(100)))
+        );
+        assert_eq!(cache.get(ISOLATE, id(2)), Some(Value::U8List(vec![2; 100])));
+        assert_eq!(cache.get(ISOLATE, id(3)), Some(Value::U8List(vec![3; 100])));
+    }
+
+    #[test]
+    fn test_short_file_read_is_miss() {
+        let mut cache = LazyDataCache::new(LazyDataCacheConfiguration {
+            max_memory_usage: 150,
+            max_disk_usage: Some(1000),
+        });
+        cache.insert(ISOLATE, id(1), Value::U8List(vec![1; 100]));
+        cache.insert(ISOLATE, id(2), Value::U8List(vec![2; 100]));
+        assert_eq!(cache.disk_used(), 100);
+        // Pretend the file is shorter than the value.
+        cache.entries.get_mut(&(ISOLATE, id(1))).unwrap().size += 1;
+        cache.disk_used += 1;
+        assert!(cache.get(ISOLATE, id(1)).is_none());
+        assert_eq!(cache.disk_used(), 0);
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut cache = cache();
+        cache.insert(ISOLATE, id(1), Value::U8List(vec![1; 100]));
+        cache.remove(ISOLATE, id(1));
+        assert!(cache.get(ISOLATE, id(1)).is_none());
+        assert_eq!(cache.memory_used(), 0);
+    }
+
+    #[test]
+    fn test_isolates_do_not_share_values() {
+        let mut cache = cache();
+        let other = IsolateId(2);
+        cache.insert(ISOLATE, id(1), Value::U8List(vec![1; 100]));
+        assert!(cache.get(other, id(1)).is_none());
+        cache.insert(other, id(1), Value::U8List(vec![2; 100]));
+        assert_eq!(cache.get(ISOLATE, id(1)), Some(Value::U8List(vec![1; 100])));
+        assert_eq!(cache.get(other, id(1)), Some(Value::U8List(vec![2; 100])));
+        cache.remove_isolate(other);
+        assert!(cache.get(other, id(1)).is_none());
+        assert_eq!(cache.get(ISOLATE, id(1)), Some(Value::U8List(vec![1; 100])));
+    }
+}
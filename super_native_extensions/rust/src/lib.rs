@@ -30,6 +30,7 @@ use irondash_message_channel::{irondash_init_message_channel_context, FunctionRe
 use reader_manager::GetDataReaderManager;
 
 mod api_model;
+mod auto_scroll;
 mod blur;
 mod clipboard_events_manager;
 mod clipboard_reader;
@@ -39,15 +40,21 @@ mod data_provider_manager;
 mod drag_manager;
 mod drop_manager;
 mod error;
+mod format_conversion;
 mod hot_key_manager;
+mod hot_key_sequence;
+mod image_data;
 mod keyboard_layout_manager;
+mod lazy_data_cache;
 mod log;
 mod menu_manager;
 mod reader_manager;
 mod shadow;
+mod spring_loading;
 mod util;
 mod value_coerce;
 mod value_promise;
+mod xkb_keymap;
 
 #[allow(dead_code)]
 mod segmented_queue;
@@ -1,17 +1,20 @@
 use std::ffi::{CStr, CString};
 
 use gdk::{
-    cairo::{Format, ImageSurface},
+    cairo::{self, Format, ImageSurface},
     glib::translate::{FromGlibPtrNone, ToGlibPtr, ToGlibPtrMut},
-    Atom, Event, EventType,
+    Atom, DragContext, Event, EventType,
 };
-use gdk_sys::{gdk_atom_intern, gdk_atom_name, GdkAtom};
+use gdk_sys::{gdk_atom_intern, gdk_atom_name, gdk_drag_get_selection, GdkAtom};
 use glib_sys::GFALSE;
 use gtk::{TargetEntry, TargetList};
 use gtk_sys::{gtk_target_table_new_from_list, gtk_targets_include_text};
 
-use crate::api_model::ImageData;
+use crate::api_model::{ImageData, Point, Rect, ShadowStyle, TargettedImage};
 use crate::error::{NativeExtensionsError::OtherError, NativeExtensionsResult};
+use crate::image_data::{PixelFormat, PixelLayout};
+use crate::log::OkLog;
+use crate::shadow::WithShadow;
 
 // Use gtk function to set/retrieve text (there are multiple possible format,
 // we don't want to mess with that)
@@ -21,6 +24,26 @@ pub const TYPE_TEXT: &str = "text/plain";
 // URI list, when reading URI list is split into multiple items.
 pub const TYPE_URI: &str = "text/uri-list";
 
+pub const SELECTION_CLIPBOARD: &str = "CLIPBOARD";
+
+// Formats of items other than the first one are advertised under targets
+// with this prefix followed by item index and format, i.e.
+// "application/x-super-native-extensions-item;index=1;format=image/png".
+// Other applications only see formats of the first item.
+const ITEM_TARGET_PREFIX: &str = "application/x-super-native-extensions-item;index=";
+const ITEM_TARGET_FORMAT: &str = ";format=";
+
+pub fn item_target(index: usize, format: &str) -> String {
+    format!("{ITEM_TARGET_PREFIX}{index}{ITEM_TARGET_FORMAT}{format}")
+}
+
+/// Returns item index and format for targets created by [`item_target`].
+pub fn parse_item_target(target: &str) -> Option<(usize, &str)> {
+    let rest = target.strip_prefix(ITEM_TARGET_PREFIX)?;
+    let (index, format) = rest.split_once(ITEM_TARGET_FORMAT)?;
+    Some((index.parse().ok()?, format))
+}
+
 pub trait AtomExt {
     fn from_string(s: &str) -> GdkAtom;
     fn to_string(&self) -> String;
@@ -40,6 +63,11 @@ impl AtomExt for GdkAtom {
     }
 }
 
+/// Name of the selection used to transfer data of given drag.
+pub fn drag_selection(context: &DragContext) -> String {
+    unsafe { gdk_drag_get_selection(context.to_glib_none().0) }.to_string()
+}
+
 pub fn target_includes_text(target: &Atom) -> bool {
     let res = unsafe { gtk_targets_include_text(&mut target.to_glib_none().0, 1) };
     res != GFALSE
@@ -62,43 +90,210 @@ impl TargetListExt for TargetList {
     }
 }
 
-pub fn surface_from_image_data(image: ImageData, opacity: f64) -> ImageSurface {
-    let factor: i32 = (opacity * 255.0) as i32;
-
-    let mut data = image.data;
-    for offset in (0..data.len()).step_by(4) {
-        let (r, g, b, a) = (
-            data[offset],
-            data[offset + 1],
-            data[offset + 2],
-            data[offset + 3],
-        );
-
-        let a = if factor == 255 {
-            a
-        } else {
-            (a as i32 * factor / 255) as u8
-        };
-
-        // Premultiply alpha
-        data[offset] = (b as i32 * a as i32 / 255) as u8;
-        data[offset + 1] = (g as i32 * a as i32 / 255) as u8;
-        data[offset + 2] = (r as i32 * a as i32 / 255) as u8;
-        data[offset + 3] = a;
+pub fn surface_from_image_data(
+    image: ImageData,
+    opacity: f64,
+) -> NativeExtensionsResult<ImageSurface> {
+    let image = image.validated();
+    let stride = Format::ARgb32
+        .stride_for_width(image.width as u32)
+        .unwrap_or(image.width * 4);
+    let layout = PixelLayout {
+        format: PixelFormat::BGRA_PREMULTIPLIED,
+        bytes_per_row: stride as usize,
+        bottom_up: false,
+    };
+    let mut data = image.to_layout(layout)?;
+    if opacity < 1.0 {
+        // Premultiplied pixels are faded by scaling all channels.
+        let factor = (opacity.max(0.0) * 255.0) as u32;
+        for c in data.iter_mut() {
+            *c = (*c as u32 * factor / 255) as u8;
+        }
     }
-    let surface = ImageSurface::create_for_data(
-        data,
-        Format::ARgb32,
-        image.width,
-        image.height,
-        image.bytes_per_row,
-    );
-    let res = surface.unwrap();
+    let res =
+        ImageSurface::create_for_data(data, Format::ARgb32, image.width, image.height, stride)
+            .map_err(|e| OtherError(format!("failed to create image surface: {e}")))?;
     res.set_device_scale(
         image.device_pixel_ratio.unwrap_or(1.0),
         image.device_pixel_ratio.unwrap_or(1.0),
     );
-    res
+    Ok(res)
+}
+
+/// Maximum number of items stacked behind the first item.
+const MAX_STACKED_ITEMS: usize = 3;
+
+/// Offset between stacked items in logical pixels.
+const STACK_OFFSET: f64 = 6.0;
+
+/// Rotation between fanned out stacked items in radians.
+const FAN_ANGLE: f64 = 0.06;
+
+/// Describes how drag item images are composited into drag icon.
+pub struct DragImageLayout {
+    /// Items before this index are drawn at their original location.
+    /// Remaining items (added while dragging) are fanned out behind the
+    /// first item.
+    pub stacked_from: usize,
+    /// Items from this index are moving from their original location
+    /// to the stack.
+    pub animating_from: usize,
+    /// Progress of the stacking animation, from 0.0 to 1.0.
+    pub animation_progress: f64,
+    /// Opacity of the whole drag image.
+    pub opacity: f64,
+}
+
+/// Returns rect and rotation of stacked item at given depth (starting at 1),
+/// interpolated from the original item rect by `progress`.
+fn stacked_item_geometry(rect: &Rect, center: &Point, depth: usize, progress: f64) -> (Rect, f64) {
+    let offset = depth as f64 * STACK_OFFSET;
+    // Alternate fanning direction.
+    let direction = if depth % 2 == 1 { 1.0 } else { -1.0 };
+    let angle = direction * depth.div_ceil(2) as f64 * FAN_ANGLE;
+    let lerp = |from: f64, to: f64| from + (to - from) * progress;
+    let rect = Rect::xywh(
+        lerp(rect.x, center.x - rect.width / 2.0 + offset),
+        lerp(rect.y, center.y - rect.height / 2.0 + offset),
+        rect.width,
+        rect.height,
+    );
+    (rect, angle * progress)
+}
+
+/// Bounding box of rect rotated around its center.
+fn rotated_bounds(rect: &Rect, angle: f64) -> Rect {
+    let (sin, cos) = angle.sin_cos();
+    let (sin, cos) = (sin.abs(), cos.abs());
+    let width = rect.width * cos + rect.height * sin;
+    let height = rect.width * sin + rect.height * cos;
+    let center = rect.center();
+    Rect::xywh(
+        center.x - width / 2.0,
+        center.y - height / 2.0,
+        width,
+        height,
+    )
+}
+
+/// Drag item image (with shadow) placed in drag icon along with its rotation.
+type PlacedImage = (TargettedImage, Rect, f64);
+
+/// Returns placement of each item in drag icon or `None` for items that are
+/// not visible.
+fn place_drag_images(
+    images: &[(&TargettedImage, ShadowStyle)],
+    layout: &DragImageLayout,
+) -> Vec<Option<PlacedImage>> {
+    let images: Vec<_> = images
+        .iter()
+        .map(|(image, shadow)| image.with_shadow(shadow))
+        .collect();
+    let Some(center) = images.first().map(|i| i.rect.center()) else {
+        return Vec::new();
+    };
+    images
+        .into_iter()
+        .enumerate()
+        .map(|(index, image)| {
+            if index < layout.stacked_from {
+                let rect = image.rect.clone();
+                return Some((image, rect, 0.0));
+            }
+            let depth = index - layout.stacked_from + 1;
+            if depth > MAX_STACKED_ITEMS {
+                return None;
+            }
+            let progress = if index < layout.animating_from {
+                1.0
+            } else {
+                layout.animation_progress.clamp(0.0, 1.0)
+            };
+            let (rect, angle) = stacked_item_geometry(&image.rect, &center, depth, progress);
+            Some((image, rect, angle))
+        })
+        .collect()
+}
+
+/// Draws placed images into single surface, first image on top. Returns the
+/// surface and its rect in view coordinates.
+fn render_drag_images(placed: Vec<PlacedImage>, opacity: f64) -> Option<(ImageSurface, Rect)> {
+    let scale = placed
+        .iter()
+        .map(|(image, _, _)| image.image_data.device_pixel_ratio.unwrap_or(1.0))
+        .fold(1.0, f64::max);
+    let bounds: Vec<_> = placed
+        .iter()
+        .map(|(_, rect, angle)| rotated_bounds(rect, *angle))
+        .collect();
+    let left = bounds.iter().map(|b| b.x).fold(f64::INFINITY, f64::min);
+    let top = bounds.iter().map(|b| b.y).fold(f64::INFINITY, f64::min);
+    let right = bounds
+        .iter()
+        .map(|b| b.x + b.width)
+        .fold(f64::NEG_INFINITY, f64::max);
+    let bottom = bounds
+        .iter()
+        .map(|b| b.y + b.height)
+        .fold(f64::NEG_INFINITY, f64::max);
+    if left >= right || top >= bottom {
+        return None;
+    }
+    let bounds = Rect::xywh(left, top, right - left, bottom - top);
+
+    let surface = ImageSurface::create(
+        Format::ARgb32,
+        (bounds.width * scale).ceil() as i32,
+        (bounds.height * scale).ceil() as i32,
+    )
+    .ok()?;
+    surface.set_device_scale(scale, scale);
+    let context = cairo::Context::new(&surface).ok()?;
+    context.push_group();
+    for (image, rect, angle) in placed.into_iter().rev() {
+        let Some(image) = surface_from_image_data(image.image_data, 1.0).ok_log() else {
+            continue;
+        };
+        let center = rect.center();
+        context.save().ok()?;
+        context.translate(center.x - bounds.x, center.y - bounds.y);
+        context.rotate(angle);
+        context
+            .set_source_surface(&image, -rect.width / 2.0, -rect.height / 2.0)
+            .ok()?;
+        context.paint().ok()?;
+        context.restore().ok()?;
+    }
+    context.pop_group_to_source().ok()?;
+    context.paint_with_alpha(opacity.clamp(0.0, 1.0)).ok()?;
+    drop(context);
+    Some((surface, bounds))
+}
+
+/// Composites drag item images into single drag icon, drawing each image
+/// with its shadow. Returns the surface and its rect in view coordinates.
+pub fn composite_drag_image(
+    images: &[(&TargettedImage, ShadowStyle)],
+    layout: &DragImageLayout,
+) -> Option<(ImageSurface, Rect)> {
+    let placed = place_drag_images(images, layout)
+        .into_iter()
+        .flatten()
+        .collect();
+    render_drag_images(placed, layout.opacity)
+}
+
+/// Renders image of each item as it appears in composited drag icon.
+/// Returns `None` for items that are not visible.
+pub fn drag_item_images(
+    images: &[(&TargettedImage, ShadowStyle)],
+    layout: &DragImageLayout,
+) -> Vec<Option<(ImageSurface, Rect)>> {
+    place_drag_images(images, layout)
+        .into_iter()
+        .map(|placed| render_drag_images(vec![placed?], layout.opacity))
+        .collect()
 }
 
 pub(super) fn synthesize_button_up(event: &Event) -> NativeExtensionsResult<Event> {
@@ -117,3 +312,68 @@ pub(super) fn synthesize_button_up(event: &Event) -> NativeExtensionsResult<Even
     e.type_ = gdk_sys::GDK_BUTTON_RELEASE;
     Ok(event)
 }
+
+#[cfg(test)]
+mod test {
+    use crate::api_model::{Point, Rect};
+
+    use super::{item_target, parse_item_target, rotated_bounds, stacked_item_geometry};
+
+    #[test]
+    fn test_item_target_round_trip() {
+        let cases = [
+            (0, "image/png"),
+            (1, "text/plain"),
+            (123, "application/octet-stream"),
+            (2, "text/plain;charset=utf-8"),
+            (3, "application/x-custom;format=1"),
+            (4, ""),
+        ];
+        for (index, format) in cases {
+            let target = item_target(index, format);
+            assert_eq!(parse_item_target(&target), Some((index, format)));
+        }
+    }
+
+    #[test]
+    fn test_parse_malformed_item_target() {
+        let cases = [
+            "",
+            "text/plain",
+            "application/x-super-native-extensions-item;index=",
+            "application/x-super-native-extensions-item;index=1",
+            "application/x-super-native-extensions-item;index=;format=text/plain",
+            "application/x-super-native-extensions-item;index=-1;format=text/plain",
+            "application/x-super-native-extensions-item;index=a;format=text/plain",
+            "application/x-super-native-extensions-item;format=text/plain",
+            "x-application/x-super-native-extensions-item;index=1;format=text/plain",
+        ];
+        for target in cases {
+            assert_eq!(parse_item_target(target), None, "{target}");
+        }
+    }
+
+    #[test]
+    fn test_stacked_item_geometry() {
+        let rect = Rect::xywh(100.0, 100.0, 20.0, 10.0);
+        let center = Point { x: 10.0, y: 10.0 };
+        let (start, angle) = stacked_item_geometry(&rect, &center, 1, 0.0);
+        assert_eq!(start, rect);
+        assert_eq!(angle, 0.0);
+        let (end, angle) = stacked_item_geometry(&rect, &center, 1, 1.0);
+        assert_eq!(end, Rect::xywh(6.0, 11.0, 20.0, 10.0));
+        assert!(angle > 0.0);
+        let (_, angle2) = stacked_item_geometry(&rect, &center, 2, 1.0);
+        assert_eq!(angle2, -angle);
+    }
+
+    #[test]
+    fn test_rotated_bounds() {
+        let rect = Rect::xywh(0.0, 0.0, 20.0, 10.0);
+        assert_eq!(rotated_bounds(&rect, 0.0), rect);
+        let rotated = rotated_bounds(&rect, std::f64::consts::FRAC_PI_2);
+        assert!((rotated.width - 10.0).abs() < 1e-9);
+        assert!((rotated.height - 20.0).abs() < 1e-9);
+        assert!((rotated.x - 5.0).abs() < 1e-9);
+    }
+}
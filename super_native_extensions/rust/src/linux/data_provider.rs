@@ -1,6 +1,5 @@
 use std::{
     cell::RefCell,
-    collections::HashMap,
     rc::{Rc, Weak},
     sync::Arc,
 };
@@ -9,17 +8,22 @@ use gdk::{Atom, Display};
 
 use gtk::{Clipboard, SelectionData, TargetList};
 use irondash_message_channel::{IsolateId, Late};
-use irondash_run_loop::RunLoop;
 
 use crate::{
-    api_model::{DataProvider, DataProviderValueId, DataRepresentation},
+    api_model::{DataProvider, DataRepresentation},
     data_provider_manager::{DataProviderHandle, PlatformDataProviderDelegate},
     error::{NativeExtensionsError, NativeExtensionsResult},
     log::OkLog,
     value_coerce::{CoerceToData, StringFormat},
 };
 
-use super::common::{target_includes_text, TargetListExt, TYPE_TEXT, TYPE_URI};
+use super::{
+    common::{
+        item_target, parse_item_target, target_includes_text, TargetListExt, SELECTION_CLIPBOARD,
+        TYPE_TEXT, TYPE_URI,
+    },
+    lazy_data::{resolved_lazy_data, set_selection_owner, LazyValue},
+};
 
 pub fn platform_stream_write(_handle: i32, _data: &[u8]) -> i32 {
     0
@@ -60,28 +64,59 @@ impl PlatformDataProvider {
     }
 }
 
+/// Where data for a selection target comes from.
+enum TargetSource {
+    /// Target created by [`item_target`] for items other than the first one.
+    Item {
+        index: usize,
+        format: String,
+    },
+    /// URIs of all items, separated by CRLF.
+    MergedUris,
+    FirstItem {
+        format: String,
+    },
+}
+
 struct ProviderEntry {
     provider: Rc<PlatformDataProvider>,
     _handle: Arc<DataProviderHandle>,
 }
 
 pub struct DataObject {
-    providers: Vec<ProviderEntry>,
-    cache: RefCell<HashMap<DataProviderValueId, Option<Vec<u8>>>>,
+    providers: RefCell<Vec<ProviderEntry>>,
 }
 
 impl DataObject {
     pub fn new(providers: Vec<(Rc<PlatformDataProvider>, Arc<DataProviderHandle>)>) -> Rc<Self> {
-        Rc::new(Self {
-            providers: providers
-                .into_iter()
-                .map(|p| ProviderEntry {
-                    provider: p.0,
-                    _handle: p.1,
-                })
-                .collect(),
-            cache: RefCell::new(HashMap::new()),
-        })
+        let res = Rc::new(Self {
+            providers: RefCell::new(Vec::new()),
+        });
+        res.add_providers(providers);
+        res
+    }
+
+    /// Adds providers for items added during dragging. Targets advertised
+    /// when drag started can not be changed, so data of these items is only
+    /// available through merged URI list.
+    pub fn add_providers(
+        &self,
+        providers: Vec<(Rc<PlatformDataProvider>, Arc<DataProviderHandle>)>,
+    ) {
+        self.providers
+            .borrow_mut()
+            .extend(providers.into_iter().map(|p| ProviderEntry {
+                provider: p.0,
+                _handle: p.1,
+            }));
+    }
+
+    fn item_providers(&self) -> Vec<Rc<PlatformDataProvider>> {
+        self.providers
+            .borrow()
+            .iter()
+            .map(|p| p.provider.clone())
+            .collect()
     }
 
     fn set_data_(selection_data: &SelectionData, data: &[u8]) -> NativeExtensionsResult<()> {
@@ -107,27 +142,8 @@ impl DataObject {
                 }
                 DataRepresentation::Lazy { format, id } => {
                     if format == ty {
-                        if let Some(cached) = self.cache.borrow().get(id).cloned() {
-                            return cached;
-                        }
-                        if let Some(delegate) = item.delegate.upgrade() {
-                            let promise = delegate.get_lazy_data(item.isolate_id, *id, None);
-                            loop {
-                                if let Some(result) = promise.try_take() {
-                                    match result {
-                                        crate::value_promise::ValuePromiseResult::Ok { value } => {
-                                            let data = value.coerce_to_data(StringFormat::Utf8);
-                                            self.cache.borrow_mut().insert(*id, data.clone());
-                                            return data;
-                                        }
-                                        crate::value_promise::ValuePromiseResult::Cancelled => {
-                                            return None;
-                                        }
-                                    }
-                                }
-                                RunLoop::current().platform_run_loop.poll_once();
-                            }
-                        }
+                        // Lazy data has been resolved before GTK asked for it.
+                        return resolved_lazy_data(item.isolate_id, *id);
                     }
                 }
                 _ => {}
@@ -136,30 +152,110 @@ impl DataObject {
         None
     }
 
-    pub fn get_data(&self, selection_data: &SelectionData) -> NativeExtensionsResult<()> {
-        let target = selection_data.target();
-        let is_text = target_includes_text(&target);
-
-        let target = if is_text {
-            TYPE_TEXT.to_owned()
+    fn source_for_target(target: &Atom) -> TargetSource {
+        let name = target.name();
+        if let Some((index, format)) = parse_item_target(name.as_str()) {
+            return TargetSource::Item {
+                index,
+                format: format.to_owned(),
+            };
+        }
+        if target_includes_text(target) {
+            TargetSource::FirstItem {
+                format: TYPE_TEXT.to_owned(),
+            }
+        } else if name.as_str() == TYPE_URI {
+            TargetSource::MergedUris
         } else {
-            target.name().as_str().to_owned()
+            TargetSource::FirstItem {
+                format: name.as_str().to_owned(),
+            }
+        }
+    }
+
+    /// Returns all lazy values of all items.
+    pub fn lazy_values(&self) -> Vec<LazyValue> {
+        self.item_providers()
+            .iter()
+            .flat_map(|item| {
+                item.data.representations.iter().filter_map(|r| match r {
+                    DataRepresentation::Lazy { id, .. } => Some(LazyValue {
+                        delegate: item.delegate.upgrade()?,
+                        isolate_id: item.isolate_id,
+                        id: *id,
+                    }),
+                    _ => None,
+                })
+            })
+            .collect()
+    }
+
+    /// Returns lazy values that need to be resolved before selection request
+    /// for given target can be answered.
+    pub fn lazy_values_for_target(&self, target: &Atom) -> Vec<LazyValue> {
+        let providers = self.item_providers();
+        let items: Vec<_> = match Self::source_for_target(target) {
+            TargetSource::Item { index, format } => providers
+                .get(index)
+                .map(|item| (item, format))
+                .into_iter()
+                .collect(),
+            TargetSource::MergedUris => providers
+                .iter()
+                .map(|item| (item, TYPE_URI.to_owned()))
+                .collect(),
+            TargetSource::FirstItem { format } => providers
+                .first()
+                .map(|item| (item, format))
+                .into_iter()
+                .collect(),
         };
-        if target == TYPE_URI {
-            // merge URIs from all items
-            let mut data = Vec::<u8>::new();
-            for item in &self.providers {
-                if let Some(item_data) = self.get_data_for_item(&item.provider, &target) {
-                    data.extend_from_slice(&item_data);
-                    data.push(b'\r');
-                    data.push(b'\n');
+        items
+            .into_iter()
+            .filter_map(|(item, ty)| {
+                let id = item.data.representations.iter().find_map(|r| match r {
+                    DataRepresentation::Lazy { format, id } if *format == ty => Some(*id),
+                    _ => None,
+                })?;
+                Some(LazyValue {
+                    delegate: item.delegate.upgrade()?,
+                    isolate_id: item.isolate_id,
+                    id,
+                })
+            })
+            .collect()
+    }
+
+    pub fn get_data(&self, selection_data: &SelectionData) -> NativeExtensionsResult<()> {
+        let target = selection_data.target();
+        match Self::source_for_target(&target) {
+            TargetSource::Item { index, format } => {
+                let data = self
+                    .item_providers()
+                    .get(index)
+                    .and_then(|item| self.get_data_for_item(item, &format));
+                if let Some(data) = data {
+                    selection_data.set(&target, 8, &data);
                 }
             }
-            Self::set_data_(selection_data, &data)?;
-        } else if let Some(item) = self.providers.first() {
-            if let Some(data) = self.get_data_for_item(&item.provider, &target) {
+            TargetSource::MergedUris => {
+                let mut data = Vec::<u8>::new();
+                for item in self.item_providers() {
+                    if let Some(item_data) = self.get_data_for_item(&item, TYPE_URI) {
+                        data.extend_from_slice(&item_data);
+                        data.push(b'\r');
+                        data.push(b'\n');
+                    }
+                }
                 Self::set_data_(selection_data, &data)?;
             }
+            TargetSource::FirstItem { format } => {
+                if let Some(item) = self.item_providers().first() {
+                    if let Some(data) = self.get_data_for_item(item, &format) {
+                        Self::set_data_(selection_data, &data)?;
+                    }
+                }
+            }
         }
         Ok(())
     }
@@ -176,6 +272,7 @@ impl DataObject {
         clipboard.set_with_data(&targets, move |_, selection_data, _| {
             self_clone.get_data(selection_data).ok_log();
         });
+        set_selection_owner(SELECTION_CLIPBOARD, self);
         Ok(())
     }
 
@@ -188,16 +285,19 @@ impl DataObject {
                 list.add(&Atom::intern(ty), 0, 0);
             }
         }
-        if let Some(item) = self.providers.first() {
+        for (index, item) in self.providers.borrow().iter().enumerate() {
             for repr in &item.provider.data.representations {
-                match repr {
-                    DataRepresentation::Simple { format, data: _ } => {
-                        add(&list, format);
-                    }
-                    DataRepresentation::Lazy { format, id: _ } => {
-                        add(&list, format);
-                    }
-                    _ => {}
+                let format = match repr {
+                    DataRepresentation::Simple { format, data: _ } => format,
+                    DataRepresentation::Lazy { format, id: _ } => format,
+                    _ => continue,
+                };
+                if index == 0 {
+                    add(&list, format);
+                } else {
+                    // Only our own reader understands these; other applications
+                    // still get the first item and merged URI list.
+                    add(&list, &item_target(index, format));
                 }
             }
         }
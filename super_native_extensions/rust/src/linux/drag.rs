@@ -3,14 +3,16 @@ use std::{
     collections::HashMap,
     os::raw::c_ulong,
     rc::{Rc, Weak},
-    time::Duration,
+    sync::Arc,
+    time::{Duration, Instant},
 };
 
 use gdk::{
+    cairo::ImageSurface,
     glib::{translate::from_glib_none, WeakRef},
     prelude::StaticType,
     traits::{DeviceExt, SeatExt},
-    Display, DragAction, DragCancelReason, DragContext, Event,
+    Atom, Device, Display, DragAction, DragCancelReason, DragContext, Event,
 };
 
 use gtk::{prelude::DragContextExtManual, traits::WidgetExt, SelectionData, Widget};
@@ -20,20 +22,31 @@ use irondash_message_channel::{Late, Value};
 use irondash_run_loop::RunLoop;
 
 use crate::{
-    api_model::{DataProviderId, DragConfiguration, DragRequest, DropOperation, Point},
+    api_model::{
+        DataProviderId, DragConfiguration, DragItem, DragRequest, DragStartMode, DropOperation,
+        Point, Rect, ShadowStyle, TargettedImage,
+    },
+    data_provider_manager::DataProviderHandle,
     drag_manager::{
-        DataProviderEntry, DragSessionId, PlatformDragContextDelegate, PlatformDragContextId,
+        DataProviderEntry, DragSessionId, DropDestination, DropDestinationKind,
+        GetAdditionalItemsResult, LocalDragSession, NativeDragId, PlatformDragContextDelegate,
+        PlatformDragContextId,
     },
     error::{NativeExtensionsError, NativeExtensionsResult},
+    format_conversion::uri_list_to_file_paths,
     log::OkLog,
-    platform_impl::platform::drag_common::DropOperationExt,
-    shadow::WithShadow,
+    platform_impl::platform::drag_common::{native_drag_id, DropOperationExt},
+    value_promise::{Promise, PromiseResult},
 };
 
 use super::{
-    common::{surface_from_image_data, synthesize_button_up},
+    common::{
+        composite_drag_image, drag_item_images, drag_selection, synthesize_button_up,
+        DragImageLayout, TYPE_TEXT, TYPE_URI,
+    },
+    lazy_data::set_selection_owner,
     signal::Signal,
-    DataObject,
+    DataObject, PlatformDataProvider,
 };
 
 pub struct PlatformDragContext {
@@ -46,15 +59,43 @@ pub struct PlatformDragContext {
     sessions: RefCell<HashMap<DragContext, Rc<Session>>>,
 }
 
+// Requested by file manager when items are dropped on trash.
+const TYPE_GNOME_COPIED_FILES: &str = "x-special/gnome-copied-files";
+
+// Direct save protocol (XDS).
+const TYPE_DIRECT_SAVE: &str = "XdndDirectSave0";
+
+// Maximum length of XDS property in 32-bit units.
+const PROPERTY_MAX_LENGTH: c_ulong = 1024;
+
+// Duration of animation moving added items to the stack.
+const STACK_ANIMATION_DURATION: Duration = Duration::from_millis(250);
+
 struct Session {
     id: DragSessionId,
     context_id: PlatformDragContextId,
     context_delegate: Weak<dyn PlatformDragContextDelegate>,
+    drag_context: DragContext,
+    native_id: NativeDragId,
     data_object: Rc<DataObject>,
-    configuration: DragConfiguration,
+    configuration: RefCell<DragConfiguration>,
+    /// Drag start position in view coordinates.
+    start_position: Point,
+    /// Number of items the drag started with. Items added later are stacked
+    /// behind the first item in drag image.
+    initial_item_count: usize,
+    /// Index of first item being animated to the stack and animation start.
+    stack_animation: Cell<Option<(usize, Instant)>>,
+    /// Replaces item images when set through `update_drag_image`.
+    image_override: RefCell<Option<TargettedImage>>,
     weak_self: Late<Weak<Self>>,
     last_position: RefCell<Point>,
     last_operation: Cell<DropOperation>,
+    /// Destination outside of the application, based on targets requested
+    /// by drop site.
+    destination: RefCell<Option<DropDestination>>,
+    pending_additional_items:
+        RefCell<Option<Arc<Promise<PromiseResult<GetAdditionalItemsResult>>>>>,
 }
 
 impl Session {
@@ -62,24 +103,217 @@ impl Session {
         id: DragSessionId,
         context_id: PlatformDragContextId,
         context_delegate: Weak<dyn PlatformDragContextDelegate>,
+        drag_context: DragContext,
         data_object: Rc<DataObject>,
         configuration: DragConfiguration,
+        start_position: Point,
     ) -> Rc<Self> {
         let res = Rc::new(Self {
             id,
             context_id,
             context_delegate,
+            native_id: native_drag_id(&drag_context),
+            drag_context,
             data_object,
-            configuration,
+            initial_item_count: configuration.items.len(),
+            stack_animation: Cell::new(None),
+            configuration: RefCell::new(configuration),
+            start_position,
+            image_override: RefCell::new(None),
             weak_self: Late::new(),
             last_position: RefCell::new(Point::default()),
             last_operation: Cell::new(DropOperation::None),
+            destination: RefCell::new(None),
+            pending_additional_items: RefCell::new(None),
         });
         res.weak_self.set(Rc::downgrade(&res));
+        res.register_local_session();
+        res.update_drag_icon();
         res.schedule_update_position();
         res
     }
 
+    /// Makes session and its local data available to drop contexts of all
+    /// engines.
+    fn register_local_session(&self) {
+        if let Some(delegate) = self.context_delegate.upgrade() {
+            delegate.register_local_drag_session(
+                self.native_id,
+                LocalDragSession {
+                    session_id: self.id,
+                    source_isolate: self.context_id,
+                    local_data: self.configuration.borrow().get_local_data(),
+                },
+            );
+        }
+    }
+
+    fn with_drag_images<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&[(&TargettedImage, ShadowStyle)], &DragImageLayout) -> R,
+    {
+        let image_override = self.image_override.borrow();
+        let configuration = self.configuration.borrow();
+        let images: Vec<_> = match image_override.as_ref() {
+            Some(image) => vec![(image, configuration.shadow_for_item(0))],
+            None => configuration
+                .items
+                .iter()
+                .enumerate()
+                .map(|(index, item)| (&item.image, configuration.shadow_for_item(index)))
+                .collect(),
+        };
+        let (animating_from, animation_progress) = match self.stack_animation.get() {
+            Some((index, start)) => {
+                let t = start.elapsed().as_secs_f64() / STACK_ANIMATION_DURATION.as_secs_f64();
+                // Ease out
+                (index, 1.0 - (1.0 - t.min(1.0)).powi(3))
+            }
+            None => (usize::MAX, 1.0),
+        };
+        let layout = DragImageLayout {
+            stacked_from: self.initial_item_count,
+            animating_from,
+            animation_progress,
+            opacity: configuration.drag_image_opacity,
+        };
+        f(&images, &layout)
+    }
+
+    /// Returns drag image and its rect in view coordinates.
+    fn drag_image(&self) -> Option<(ImageSurface, Rect)> {
+        self.with_drag_images(composite_drag_image)
+    }
+
+    /// Redraws drag icon while added items are moving to the stack.
+    fn update_stack_animation(&self) {
+        if let Some((_, start)) = self.stack_animation.get() {
+            self.update_drag_icon();
+            if start.elapsed() >= STACK_ANIMATION_DURATION {
+                self.stack_animation.set(None);
+            }
+        }
+    }
+
+    fn update_drag_icon(&self) {
+        if let Some((surface, rect)) = self.drag_image() {
+            let (scale, _) = surface.device_scale();
+            surface.set_device_offset(
+                (rect.x - self.start_position.x) * scale,
+                (rect.y - self.start_position.y) * scale,
+            );
+            self.drag_context.drag_set_icon_surface(&surface);
+        }
+    }
+
+    /// Records destination kind from target requested by drop site after
+    /// the data has been provided.
+    fn record_destination(&self, data: &SelectionData) {
+        if self.is_local_destination() {
+            return;
+        }
+        let target = data.target().name();
+        let destination = match target.as_str() {
+            TYPE_URI => DropDestination {
+                kind: DropDestinationKind::UriList,
+                file_paths: file_paths_from_uri_list(data.data()),
+            },
+            // First line contains the action ("copy" or "cut") which is not
+            // a URI and gets ignored.
+            TYPE_GNOME_COPIED_FILES => DropDestination {
+                kind: DropDestinationKind::Trash,
+                file_paths: file_paths_from_uri_list(data.data()),
+            },
+            TYPE_DIRECT_SAVE => DropDestination {
+                kind: DropDestinationKind::DirectSave,
+                file_paths: self.direct_save_path().into_iter().collect(),
+            },
+            _ => return,
+        };
+        let mut current = self.destination.borrow_mut();
+        // Trash and XDS targets may request URI list as well; keep the more
+        // specific kind.
+        let keep_current = destination.kind == DropDestinationKind::UriList
+            && current
+                .as_ref()
+                .map(|d| d.kind != DropDestinationKind::UriList)
+                .unwrap_or(false);
+        if !keep_current {
+            current.replace(destination);
+        }
+    }
+
+    /// Whether drop site is a window of this process.
+    fn is_local_destination(&self) -> bool {
+        let dest_window = match self.drag_context.dest_window() {
+            Some(window) => window.toplevel(),
+            None => return false,
+        };
+        gtk::Window::list_toplevels()
+            .iter()
+            .any(|w| w.window().as_ref() == Some(&dest_window))
+    }
+
+    /// XDS target stores destination file URI in property of the source
+    /// window before requesting the selection.
+    fn direct_save_path(&self) -> Option<String> {
+        let window = self.drag_context.source_window()?;
+        let (_, _, data) = gdk::property_get(
+            &window,
+            &Atom::intern(TYPE_DIRECT_SAVE),
+            &Atom::intern(TYPE_TEXT),
+            0,
+            PROPERTY_MAX_LENGTH,
+            0,
+        )?;
+        file_paths_from_uri_list(data).into_iter().next()
+    }
+
+    fn request_additional_items(&self, location: Point) {
+        if self.pending_additional_items.borrow().is_some() {
+            return;
+        }
+        if let Some(delegate) = self.context_delegate.upgrade() {
+            let promise =
+                delegate.get_additional_items_for_location(self.context_id, self.id, location);
+            self.pending_additional_items.replace(Some(promise));
+        }
+    }
+
+    fn check_additional_items(&self) {
+        let result = self
+            .pending_additional_items
+            .borrow()
+            .as_ref()
+            .and_then(|p| p.try_take());
+        if let Some(result) = result {
+            self.pending_additional_items.replace(None);
+            if let PromiseResult::Ok { value } = result {
+                self.add_items(value);
+            }
+        }
+    }
+
+    fn add_items(&self, mut result: GetAdditionalItemsResult) {
+        if result.items.is_empty() {
+            return;
+        }
+        let Some(providers) = take_providers(&result.items, &mut result.providers).ok_log() else {
+            return;
+        };
+        self.data_object.add_providers(providers);
+        let first_added = {
+            let mut configuration = self.configuration.borrow_mut();
+            let first_added = configuration.items.len();
+            configuration.items.append(&mut result.items);
+            first_added
+        };
+        self.register_local_session();
+        self.stack_animation
+            .set(Some((first_added, Instant::now())));
+        self.update_drag_icon();
+    }
+
     fn schedule_update_position(&self) {
         let weak_self = self.weak_self.clone();
         RunLoop::current()
@@ -92,6 +326,8 @@ impl Session {
     }
 
     fn update_position(&self) {
+        self.check_additional_items();
+        self.update_stack_animation();
         if let Some(display) = Display::default() {
             if let Some(seat) = display.default_seat() {
                 if let Some(pointer) = seat.pointer() {
@@ -121,10 +357,17 @@ impl Session {
 impl Drop for Session {
     fn drop(&mut self) {
         if let Some(delegate) = self.context_delegate.upgrade() {
+            delegate.unregister_local_drag_session(self.native_id, self.id);
+            let operation = self.last_operation.get();
+            let destination = match operation {
+                DropOperation::None | DropOperation::UserCancelled => None,
+                _ => self.destination.take(),
+            };
             delegate.drag_session_did_end_with_operation(
                 self.context_id,
                 self.id,
-                self.last_operation.get(),
+                operation,
+                destination,
             );
         }
     }
@@ -160,6 +403,7 @@ impl PlatformDragContext {
             let hook = signal.add_emission_hook(move |_, values| {
                 if let Some(this) = weak_self.clone().upgrade() {
                     if let Some(event) = values[1].get::<Event>().ok_log() {
+                        this.on_button_press(&event);
                         this.last_button_press_event.replace(Some(event));
                     }
                 }
@@ -177,13 +421,62 @@ impl PlatformDragContext {
         }
     }
 
+    /// Clicking while dragging adds items at pointer location to the session
+    /// driven by the clicking pointer (if Dart provides any).
+    fn on_button_press(&self, event: &Event) {
+        let Some(device) = event.device() else {
+            return;
+        };
+        let session = self
+            .sessions
+            .borrow()
+            .values()
+            .find(|s| s.drag_context.device().as_ref() == Some(&device))
+            .cloned();
+        if let Some(session) = session {
+            if let Some(location) = self.pointer_location(&device) {
+                session.request_additional_items(location);
+            }
+        }
+    }
+
+    fn pointer_location(&self, pointer: &Device) -> Option<Point> {
+        let window = self.view.upgrade()?.window()?;
+        let (_, x, y, _) = window.device_position_double(pointer);
+        Some(Point { x, y })
+    }
+
+    /// Moves pointer to given location in view coordinates.
+    fn warp_pointer(&self, location: &Point) -> NativeExtensionsResult<()> {
+        let window = self
+            .view()?
+            .window()
+            .ok_or_else(|| NativeExtensionsError::OtherError("View has no window".into()))?;
+        let pointer = Display::default()
+            .and_then(|d| d.default_seat())
+            .and_then(|s| s.pointer())
+            .ok_or_else(|| NativeExtensionsError::OtherError("Missing pointer device".into()))?;
+        let (_, x, y) = window.origin();
+        pointer.warp(
+            &window.screen(),
+            x + location.x.round() as i32,
+            y + location.y.round() as i32,
+        );
+        Ok(())
+    }
+
     pub fn get_data(&self, context: &DragContext, data: &SelectionData) {
         if let Some(session) = self.sessions.borrow().get(context).cloned() {
             session.data_object.get_data(data).ok_log();
+            session.record_destination(data);
         }
     }
 
     pub fn needs_combined_drag_image() -> bool {
+        false
+    }
+
+    pub fn supports_keyboard_drag() -> bool {
         true
     }
 
@@ -199,60 +492,65 @@ impl PlatformDragContext {
         mut providers: HashMap<DataProviderId, DataProviderEntry>,
         session_id: DragSessionId,
     ) -> NativeExtensionsResult<()> {
-        let providers: Vec<_> = request
-            .configuration
-            .items
-            .iter()
-            .map(|item| {
-                let entry = providers
-                    .remove(&item.data_provider_id)
-                    .expect("Missing data provider entry");
-                (entry.provider, entry.handle)
-            })
-            .collect();
+        let providers = take_providers(&request.configuration.items, &mut providers)?;
         let object = DataObject::new(providers);
         let target_list = object.create_target_list();
-        let event = self
-            .last_button_press_event
-            .borrow()
-            .as_ref()
-            .cloned()
-            .ok_or_else(|| NativeExtensionsError::OtherError("Missing mouse event".into()))?;
-
-        // release event will get eaten
-        let mut release = synthesize_button_up(&event)?;
-        gtk::main_do_event(&mut release);
-
-        let view = self.view()?;
         let mut actions = DragAction::empty();
         for operation in &request.configuration.allowed_operations {
             actions |= operation.to_platform();
         }
-        let context = view.drag_begin_with_coordinates(
-            &target_list,
-            actions,
-            event.button().unwrap_or(0) as i32,
-            Some(&event),
-            request.position.x as i32,
-            request.position.y as i32,
-        );
-        if let Some(context) = context {
-            if let Some(image) = request.combined_drag_image {
-                let image = image.with_shadow(10);
-                let scale = image.image_data.device_pixel_ratio.unwrap_or(1.0);
-                let surface = surface_from_image_data(image.image_data, 0.8);
-                surface.set_device_offset(
-                    (image.rect.x - request.position.x) * scale,
-                    (image.rect.y - request.position.y) * scale,
-                );
-                context.drag_set_icon_surface(&surface)
+        let context = match request.start_mode {
+            DragStartMode::Pointer => {
+                let event = self
+                    .last_button_press_event
+                    .borrow()
+                    .as_ref()
+                    .cloned()
+                    .ok_or_else(|| {
+                        NativeExtensionsError::OtherError("Missing mouse event".into())
+                    })?;
+
+                // release event will get eaten
+                let mut release = synthesize_button_up(&event)?;
+                gtk::main_do_event(&mut release);
+
+                self.view()?.drag_begin_with_coordinates(
+                    &target_list,
+                    actions,
+                    event.button().unwrap_or(0) as i32,
+                    Some(&event),
+                    request.position.x as i32,
+                    request.position.y as i32,
+                )
+            }
+            DragStartMode::Keyboard => {
+                // Drag image follows the pointer so move it to drag location.
+                self.warp_pointer(&request.position)?;
+                // Without triggering event GTK drives the drag from keyboard:
+                // arrow keys move the pointer (in bigger steps with Alt),
+                // Return or space drops at pointer location and Escape
+                // cancels. Drop is then delivered through drop context as
+                // usual.
+                self.view()?.drag_begin_with_coordinates(
+                    &target_list,
+                    actions,
+                    0,
+                    None,
+                    request.position.x as i32,
+                    request.position.y as i32,
+                )
             }
+        };
+        if let Some(context) = context {
+            set_selection_owner(&drag_selection(&context), &object);
             let session = Session::new(
                 session_id,
                 self.id,
                 self.delegate.clone(),
+                context.clone(),
                 object,
                 request.configuration,
+                request.position,
             );
             self.sessions.borrow_mut().insert(context.clone(), session);
             let weak_self = self.weak_self.clone();
@@ -283,13 +581,83 @@ impl PlatformDragContext {
         Ok(())
     }
 
-    pub fn get_local_data(&self) -> Option<Vec<Value>> {
+    /// Returns native id of drag in progress that given destination drag
+    /// context belongs to. GTK creates separate context for destination side
+    /// of a local drag; both share the source window, which is private to
+    /// single drag at a time.
+    pub fn native_drag_id_for_destination(&self, context: &DragContext) -> Option<NativeDragId> {
+        let source_window = context.source_window()?;
         self.sessions
+            .borrow()
+            .values()
+            .find(|s| s.drag_context.source_window().as_ref() == Some(&source_window))
+            .map(|s| s.native_id)
+    }
+
+    /// Returns image of each item in drag icon of given native drag, in root
+    /// window coordinates. Items that are not visible in drag icon have no
+    /// image. Used by drop context to animate local drops.
+    pub fn current_drag_item_images(
+        &self,
+        native_id: NativeDragId,
+    ) -> Option<Vec<Option<(ImageSurface, Rect)>>> {
+        let session = self
+            .sessions
+            .borrow()
+            .values()
+            .find(|s| s.native_id == native_id)
+            .cloned()?;
+        let images = session.with_drag_images(drag_item_images);
+        let pointer = Display::default()?.default_seat()?.pointer()?;
+        let (_, x, y) = pointer.position_double();
+        let (dx, dy) = (x - session.start_position.x, y - session.start_position.y);
+        Some(
+            images
+                .into_iter()
+                .map(|image| image.map(|(surface, rect)| (surface, rect.translated(dx, dy))))
+                .collect(),
+        )
+    }
+
+    pub fn get_active_sessions(&self) -> Vec<DragSessionId> {
+        self.sessions.borrow().values().map(|s| s.id).collect()
+    }
+
+    /// Cancelled session reports `DropOperation::None`, `UserCancelled` is
+    /// only used when user presses escape.
+    pub fn cancel_drag(&self, session_id: DragSessionId) -> NativeExtensionsResult<()> {
+        let context = self
+            .sessions
             .borrow()
             .iter()
-            .next()
-            .map(|a| a.1.clone())
-            .map(|s| s.configuration.get_local_data())
+            .find(|(_, s)| s.id == session_id)
+            .map(|(context, _)| context.clone())
+            .ok_or(NativeExtensionsError::DragSessionNotFound)?;
+        // Remove the session before cancelling; the cancel signal may not be
+        // emitted (or may be emitted synchronously).
+        let session = self.sessions.borrow_mut().remove(&context);
+        if let Some(session) = &session {
+            session.last_operation.replace(DropOperation::None);
+        }
+        context.drag_cancel();
+        Ok(())
+    }
+
+    pub fn update_drag_image(
+        &self,
+        session_id: DragSessionId,
+        image: TargettedImage,
+    ) -> NativeExtensionsResult<()> {
+        let session = self
+            .sessions
+            .borrow()
+            .values()
+            .find(|s| s.id == session_id)
+            .cloned()
+            .ok_or(NativeExtensionsError::DragSessionNotFound)?;
+        session.image_override.replace(Some(image));
+        session.update_drag_icon();
+        Ok(())
     }
 
     pub fn get_local_data_for_session_id(
@@ -307,10 +675,34 @@ impl PlatformDragContext {
                 }
             })
             .ok_or(NativeExtensionsError::DragSessionNotFound)?;
-        Ok(session.configuration.get_local_data())
+        Ok(session.configuration.borrow().get_local_data())
     }
 }
 
+fn file_paths_from_uri_list(data: Vec<u8>) -> Vec<String> {
+    uri_list_to_file_paths(Value::U8List(data))
+        .and_then(|paths| match paths {
+            Value::String(paths) => Some(paths.lines().map(|p| p.to_owned()).collect()),
+            _ => None,
+        })
+        .unwrap_or_default()
+}
+
+fn take_providers(
+    items: &[DragItem],
+    providers: &mut HashMap<DataProviderId, DataProviderEntry>,
+) -> NativeExtensionsResult<Vec<(Rc<PlatformDataProvider>, Arc<DataProviderHandle>)>> {
+    items
+        .iter()
+        .map(|item| {
+            let entry = providers
+                .remove(&item.data_provider_id)
+                .ok_or(NativeExtensionsError::DataSourceNotFound)?;
+            Ok((entry.provider, entry.handle))
+        })
+        .collect()
+}
+
 impl Drop for PlatformDragContext {
     fn drop(&mut self) {
         if let Some(signal) = Signal::lookup("button-press-event", Widget::static_type()) {
@@ -1,6 +1,14 @@
-use gdk::DragAction;
+use gdk::{glib::translate::ToGlibPtr, DragAction, DragContext};
 
-use crate::api_model::DropOperation;
+use crate::{api_model::DropOperation, drag_manager::NativeDragId};
+
+/// Identifies native drag by its source drag context. Destination side of
+/// a drag has its own context, see
+/// `PlatformDragContext::native_drag_id_for_destination`.
+pub fn native_drag_id(context: &DragContext) -> NativeDragId {
+    let context: *mut gdk_sys::GdkDragContext = context.to_glib_none().0;
+    context as NativeDragId
+}
 
 pub trait DropOperationExt {
     fn to_platform(&self) -> DragAction;
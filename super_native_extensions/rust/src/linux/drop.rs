@@ -1,11 +1,15 @@
 use std::{
     cell::{Cell, RefCell},
+    os::raw::c_ulong,
     rc::{Rc, Weak},
 };
 
 use gdk::{
     glib::{translate::from_glib_none, WeakRef},
-    Atom, DragAction, DragContext,
+    keys::constants as keys,
+    prelude::StaticType,
+    traits::{DeviceExt, SeatExt},
+    Atom, Display, DragAction, DragContext, Event, ModifierType,
 };
 
 use gtk::{
@@ -19,10 +23,11 @@ use irondash_message_channel::{Late, Value};
 use irondash_run_loop::RunLoop;
 
 use crate::{
-    api_model::{DropOperation, Point},
+    api_model::{DropOperation, Point, Size},
+    drag_manager::NativeDragId,
     drop_manager::{
-        BaseDropEvent, DropEvent, DropItem, DropSessionId, PlatformDropContextDelegate,
-        PlatformDropContextId,
+        BaseDropEvent, DropEvent, DropItem, DropModifiers, DropSessionId, ItemPreviewRequest,
+        PlatformDropContextDelegate, PlatformDropContextId,
     },
     error::{NativeExtensionsError, NativeExtensionsResult},
     log::OkLog,
@@ -33,6 +38,8 @@ use crate::{
 use super::{
     common::{TargetListExt, TYPE_TEXT, TYPE_URI},
     drag_common::DropOperationExt,
+    drop_preview::{DropPreview, DEFAULT_FADE_OUT_DELAY, DEFAULT_FADE_OUT_DURATION},
+    signal::Signal,
     PlatformDataReader, WidgetReader,
 };
 
@@ -41,10 +48,17 @@ pub struct PlatformDropContext {
     delegate: Weak<dyn PlatformDropContextDelegate>,
     weak_self: Late<Weak<Self>>,
     view: WeakRef<Widget>,
+    key_press_hook: Late<c_ulong>,
     next_session_id: Cell<i64>,
     current_session: RefCell<Option<Rc<Session>>>,
 }
 
+struct PointerState {
+    modifiers: DropModifiers,
+    button: Option<i64>,
+    location_on_screen: Point,
+}
+
 struct Session {
     id: DropSessionId,
     widget_reader: Rc<WidgetReader>,
@@ -72,13 +86,28 @@ impl PlatformDropContext {
             delegate,
             weak_self: Late::new(),
             view: weak,
+            key_press_hook: Late::new(),
             next_session_id: Cell::new(0),
             current_session: RefCell::new(None),
         })
     }
 
     pub fn assign_weak_self(&self, weak_self: Weak<Self>) {
-        self.weak_self.set(weak_self);
+        self.weak_self.set(weak_self.clone());
+
+        // During local drags keyboard is grabbed by GTK so key press events
+        // are not delivered to the view.
+        if let Some(signal) = Signal::lookup("key-press-event", Widget::static_type()) {
+            let hook = signal.add_emission_hook(move |_, values| {
+                if let Some(this) = weak_self.upgrade() {
+                    if let Some(event) = values[1].get::<Event>().ok_log() {
+                        this.on_key_press(&event);
+                    }
+                }
+                true
+            });
+            self.key_press_hook.set(hook);
+        }
 
         if let Some(view) = self.view.upgrade() {
             let weak_self = self.weak_self.clone();
@@ -113,6 +142,21 @@ impl PlatformDropContext {
         }
     }
 
+    /// Pressing Tab while dragging over the view spring-loads current
+    /// location. GTK uses space, Return, Escape and arrow keys to control
+    /// the drag itself.
+    fn on_key_press(&self, event: &Event) {
+        if event.keyval() != Some(keys::Tab) {
+            return;
+        }
+        let session_id = self.current_session.borrow().as_ref().map(|s| s.id);
+        if let Some(session_id) = session_id {
+            if let Some(delegate) = self.delegate().ok_log() {
+                delegate.force_spring_load(self.id, session_id);
+            }
+        }
+    }
+
     fn view(&self) -> NativeExtensionsResult<Widget> {
         self.view
             .upgrade()
@@ -151,17 +195,17 @@ impl PlatformDropContext {
     ) -> Option<DropEvent> {
         let reader_info = session.platform_reader.reader_info()?;
 
-        let local_data = self
+        let local_session = self
             .delegate()
             .ok()?
-            .get_platform_drag_contexts()
-            .iter()
-            .map(|c| c.get_local_data())
-            .find(|c| c.is_some())
-            .flatten()
+            .get_local_drag_session(self.native_drag_id(context));
+        let local_data = local_session
+            .as_ref()
+            .map(|s| s.local_data.clone())
             .unwrap_or_default();
 
         let number_of_items = local_data.len().max(reader_info.number_of_items);
+        let pointer_state = self.pointer_state();
         Some(DropEvent {
             session_id: session.id,
             location_in_view: Point {
@@ -183,6 +227,39 @@ impl PlatformDropContext {
                 })
                 .collect(),
             reader: Some(session.registered_reader.clone()),
+            auto_scroll_velocity: None,
+            modifiers: pointer_state.as_ref().map(|s| s.modifiers),
+            pointer_button: pointer_state.as_ref().and_then(|s| s.button),
+            location_on_screen: pointer_state.map(|s| s.location_on_screen),
+            source_drag_session_id: local_session.map(|s| s.session_id),
+        })
+    }
+
+    /// GTK drag events do not carry modifier or button state so it is
+    /// queried from the pointer device instead.
+    fn pointer_state(&self) -> Option<PointerState> {
+        let pointer = Display::default()?.default_seat()?.pointer()?;
+        let (_, x, y) = pointer.position_double();
+        let (_, _, _, mask) = self.view().ok()?.window()?.device_position_double(&pointer);
+        let button = [
+            ModifierType::BUTTON1_MASK,
+            ModifierType::BUTTON2_MASK,
+            ModifierType::BUTTON3_MASK,
+            ModifierType::BUTTON4_MASK,
+            ModifierType::BUTTON5_MASK,
+        ]
+        .iter()
+        .position(|b| mask.contains(*b))
+        .map(|index| index as i64 + 1);
+        Some(PointerState {
+            modifiers: DropModifiers {
+                shift: mask.contains(ModifierType::SHIFT_MASK),
+                alt: mask.contains(ModifierType::MOD1_MASK),
+                control: mask.contains(ModifierType::CONTROL_MASK),
+                meta: mask.intersects(ModifierType::SUPER_MASK | ModifierType::META_MASK),
+            },
+            button,
+            location_on_screen: Point { x, y },
         })
     }
 
@@ -231,31 +308,28 @@ impl PlatformDropContext {
             if let Some(event) =
                 self.create_drop_event(&session, context, x, y, Some(session.last_operation.get()))
             {
-                let done = Rc::new(Cell::new(Option::<bool>::None));
-                let done_clone = done.clone();
+                // Request preview before performing drop, Dart side discards
+                // the session once the drop ends.
+                self.start_drop_preview(&session, context, &event.items)
+                    .ok_log();
+
+                // Returning from the handler before drop is performed is fine,
+                // drag_finish can be called at later point. Waiting for the
+                // result here would require pumping the run loop reentrantly.
+                let weak_self = self.weak_self.clone();
+                let context = context.clone();
                 self.delegate()?.send_perform_drop(
                     self.id,
                     event,
                     Box::new(move |r| {
                         let ok = r.ok_log().is_some();
-                        done_clone.set(Some(ok));
+                        if let Some(this) = weak_self.upgrade() {
+                            this.finish_drop(&session, &context, ok, time).ok_log();
+                        } else {
+                            context.drag_finish(false, false, time);
+                        }
                     }),
                 );
-                while done.get().is_none() {
-                    RunLoop::current().platform_run_loop.poll_once();
-                }
-                let context = context.clone();
-                let deleting = session.last_operation.get() == DropOperation::Move;
-                let ok = done.get().unwrap_or(false);
-                session.widget_reader.on_all_requests_resolved(move || {
-                    context.drag_finish(ok, deleting, time);
-                });
-                self.delegate()?.send_drop_ended(
-                    self.id,
-                    BaseDropEvent {
-                        session_id: session.id,
-                    },
-                );
             } else {
                 context.drag_finish(false, false, time);
             }
@@ -265,6 +339,86 @@ impl PlatformDropContext {
         Ok(true)
     }
 
+    /// Returns native id of local drag that destination context belongs to.
+    fn native_drag_id(&self, context: &DragContext) -> Option<NativeDragId> {
+        self.delegate()
+            .ok()?
+            .get_platform_drag_contexts()
+            .iter()
+            .find_map(|c| c.native_drag_id_for_destination(context))
+    }
+
+    /// Animates image of each dropped item into destination rect provided by
+    /// delegate. Only possible for local drags, image of drags from other
+    /// applications is not accessible.
+    fn start_drop_preview(
+        &self,
+        session: &Session,
+        context: &DragContext,
+        items: &[DropItem],
+    ) -> NativeExtensionsResult<()> {
+        let Some(native_id) = self.native_drag_id(context) else {
+            return Ok(());
+        };
+        let delegate = self.delegate()?;
+        let Some(images) = delegate
+            .get_platform_drag_contexts()
+            .iter()
+            .find_map(|c| c.current_drag_item_images(native_id))
+        else {
+            return Ok(());
+        };
+        let window = self
+            .view()?
+            .window()
+            .ok_or_else(|| NativeExtensionsError::OtherError("View has no window".into()))?;
+        let (_, x, y) = window.origin();
+        let view_origin = Point {
+            x: x as f64,
+            y: y as f64,
+        };
+        for (item, image) in items.iter().zip(images) {
+            let Some((surface, rect)) = image else {
+                continue;
+            };
+            let request = ItemPreviewRequest {
+                session_id: session.id,
+                item_id: item.item_id,
+                size: Size {
+                    width: rect.width,
+                    height: rect.height,
+                },
+                fade_out_delay: DEFAULT_FADE_OUT_DELAY,
+                fade_out_duration: DEFAULT_FADE_OUT_DURATION,
+            };
+            let fade_out = (request.fade_out_delay, request.fade_out_duration);
+            let promise = delegate.get_preview_for_item(self.id, request);
+            DropPreview::start(surface, rect, view_origin.clone(), fade_out, promise);
+        }
+        Ok(())
+    }
+
+    fn finish_drop(
+        &self,
+        session: &Rc<Session>,
+        context: &DragContext,
+        ok: bool,
+        time: u32,
+    ) -> NativeExtensionsResult<()> {
+        let context = context.clone();
+        let deleting = session.last_operation.get() == DropOperation::Move;
+        session.widget_reader.on_all_requests_resolved(move || {
+            context.drag_finish(ok, deleting, time);
+        });
+        self.delegate()?.send_drop_ended(
+            self.id,
+            BaseDropEvent {
+                session_id: session.id,
+            },
+        );
+        Ok(())
+    }
+
     fn drag_leave(&self, _context: &DragContext, _time: u32) -> NativeExtensionsResult<()> {
         if let Some(session) = self.current_session.take() {
             self.delegate()?.send_drop_leave(
@@ -304,3 +458,11 @@ impl PlatformDropContext {
         Ok(())
     }
 }
+
+impl Drop for PlatformDropContext {
+    fn drop(&mut self) {
+        if let Some(signal) = Signal::lookup("key-press-event", Widget::static_type()) {
+            signal.remove_emission_hook(*self.key_press_hook);
+        }
+    }
+}
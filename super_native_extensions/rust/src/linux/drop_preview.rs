@@ -0,0 +1,204 @@
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use gdk::{
+    cairo::{self, ImageSurface},
+    WindowTypeHint,
+};
+use gtk::{
+    traits::{GtkWindowExt, WidgetExt},
+    Inhibit, WindowType,
+};
+use irondash_run_loop::RunLoop;
+
+use crate::{
+    api_model::{Point, Rect},
+    drop_manager::ItemPreviewResponse,
+    log::OkLog,
+    value_promise::{Promise, PromiseResult},
+};
+
+use super::common::surface_from_image_data;
+
+/// Duration of the slide from drop location to destination rect.
+const SLIDE_DURATION: f64 = 0.25;
+
+/// How long to wait for the delegate to provide preview.
+const PREVIEW_TIMEOUT: Duration = Duration::from_secs(1);
+
+pub const DEFAULT_FADE_OUT_DELAY: f64 = 0.8;
+pub const DEFAULT_FADE_OUT_DURATION: f64 = 0.3;
+
+struct Animation {
+    start: Instant,
+    from: Rect,
+    to: Rect,
+    fade_out_delay: f64,
+    fade_out_duration: f64,
+}
+
+/// Shows dropped image in a popup window and animates it into destination
+/// rect provided by drop delegate. GTK hides the drag icon as soon as the
+/// drop happens so the animation is done entirely on our side.
+pub struct DropPreview {
+    window: gtk::Window,
+    surface: RefCell<ImageSurface>,
+    rect: RefCell<Rect>,
+    alpha: Cell<f64>,
+    view_origin: Point,
+    /// Fade out parameters used when preview does not specify them.
+    fade_out: (f64, f64),
+    started: Instant,
+    promise: Arc<Promise<PromiseResult<ItemPreviewResponse>>>,
+    animation: RefCell<Option<Animation>>,
+}
+
+impl DropPreview {
+    /// Shows `surface` at `rect` (root window coordinates) and starts the
+    /// animation once `promise` resolves. `view_origin` is used to convert
+    /// destination rect from view coordinates. `fade_out` is the delay and
+    /// duration of fade out requested from the delegate.
+    pub fn start(
+        surface: ImageSurface,
+        rect: Rect,
+        view_origin: Point,
+        fade_out: (f64, f64),
+        promise: Arc<Promise<PromiseResult<ItemPreviewResponse>>>,
+    ) {
+        let window = gtk::Window::new(WindowType::Popup);
+        window.set_type_hint(WindowTypeHint::Dnd);
+        window.set_accept_focus(false);
+        window.set_app_paintable(true);
+        if let Some(visual) = window.screen().and_then(|s| s.rgba_visual()) {
+            window.set_visual(Some(&visual));
+        }
+        let preview = Rc::new(Self {
+            window,
+            surface: RefCell::new(surface),
+            rect: RefCell::new(rect),
+            alpha: Cell::new(1.0),
+            view_origin,
+            fade_out,
+            started: Instant::now(),
+            promise,
+            animation: RefCell::new(None),
+        });
+        let weak = Rc::downgrade(&preview);
+        preview.window.connect_draw(move |_, context| {
+            if let Some(this) = weak.upgrade() {
+                this.draw(context);
+            }
+            Inhibit(true)
+        });
+        preview.update_window();
+        preview.window.show();
+        preview.schedule_next_frame();
+    }
+
+    fn draw(&self, context: &cairo::Context) {
+        context.set_operator(cairo::Operator::Clear);
+        context.paint().ok();
+        context.set_operator(cairo::Operator::Over);
+
+        let surface = self.surface.borrow();
+        let rect = self.rect.borrow();
+        let (scale_x, scale_y) = surface.device_scale();
+        let width = surface.width() as f64 / scale_x;
+        let height = surface.height() as f64 / scale_y;
+        if width <= 0.0 || height <= 0.0 {
+            return;
+        }
+        context.scale(rect.width / width, rect.height / height);
+        if context.set_source_surface(&*surface, 0.0, 0.0).is_ok() {
+            context.paint_with_alpha(self.alpha.get()).ok();
+        }
+    }
+
+    fn update_window(&self) {
+        let rect = self.rect.borrow();
+        self.window
+            .move_(rect.x.round() as i32, rect.y.round() as i32);
+        self.window.resize(
+            (rect.width.round() as i32).max(1),
+            (rect.height.round() as i32).max(1),
+        );
+        self.window.queue_draw();
+    }
+
+    fn schedule_next_frame(self: &Rc<Self>) {
+        let this = self.clone();
+        RunLoop::current()
+            .schedule(Duration::from_secs_f64(1.0 / 60.0), move || {
+                if this.next_frame() {
+                    this.schedule_next_frame();
+                } else {
+                    this.window.close();
+                }
+            })
+            .detach();
+    }
+
+    /// Returns false when the animation is finished.
+    fn next_frame(&self) -> bool {
+        if self.animation.borrow().is_none() {
+            match self.promise.try_take() {
+                Some(PromiseResult::Ok { value }) => match value.preview {
+                    Some(preview) => {
+                        let surface = preview
+                            .destination_image
+                            .and_then(|image| surface_from_image_data(image, 1.0).ok_log());
+                        if let Some(surface) = surface {
+                            self.surface.replace(surface);
+                        }
+                        self.animation.replace(Some(Animation {
+                            start: Instant::now(),
+                            from: self.rect.borrow().clone(),
+                            to: preview
+                                .destination_rect
+                                .translated(self.view_origin.x, self.view_origin.y),
+                            fade_out_delay: preview.fade_out_delay.unwrap_or(self.fade_out.0),
+                            fade_out_duration: preview.fade_out_duration.unwrap_or(self.fade_out.1),
+                        }));
+                    }
+                    None => return false,
+                },
+                Some(PromiseResult::Cancelled) => return false,
+                None => return self.started.elapsed() < PREVIEW_TIMEOUT,
+            }
+        }
+        let animation = self.animation.borrow();
+        let animation = match animation.as_ref() {
+            Some(animation) => animation,
+            None => return false,
+        };
+        let elapsed = animation.start.elapsed().as_secs_f64();
+
+        // Ease out cubic
+        let progress = (elapsed / SLIDE_DURATION).min(1.0);
+        let progress = 1.0 - (1.0 - progress).powi(3);
+        let lerp = |from: f64, to: f64| from + (to - from) * progress;
+        self.rect.replace(Rect::xywh(
+            lerp(animation.from.x, animation.to.x),
+            lerp(animation.from.y, animation.to.y),
+            lerp(animation.from.width, animation.to.width),
+            lerp(animation.from.height, animation.to.height),
+        ));
+
+        let fade_elapsed = elapsed - animation.fade_out_delay;
+        if fade_elapsed >= animation.fade_out_duration {
+            return false;
+        }
+        let alpha = if fade_elapsed > 0.0 {
+            1.0 - fade_elapsed / animation.fade_out_duration
+        } else {
+            1.0
+        };
+        self.alpha.set(alpha);
+        self.update_window();
+        true
+    }
+}
@@ -5,8 +5,12 @@ use std::{
 };
 
 use gdk::{
-    glib::translate::from_glib_none, prelude::StaticType, Display, Event, EventKey, Keymap,
-    KeymapKey,
+    glib::{
+        translate::{from_glib_none, IntoGlib},
+        SignalHandlerId,
+    },
+    prelude::{ObjectExt, StaticType},
+    Display, Event, EventKey, Keymap, KeymapKey,
 };
 use gtk::Widget;
 use irondash_message_channel::Late;
@@ -14,6 +18,7 @@ use irondash_message_channel::Late;
 use crate::{
     keyboard_layout_manager::{Key, KeyboardLayout, KeyboardLayoutDelegate},
     log::OkLog,
+    xkb_keymap::{dead_key_combining_char, XkbKeymap},
 };
 
 use super::signal::Signal;
@@ -23,6 +28,7 @@ pub struct PlatformKeyboardLayout {
     current_group: Cell<u8>,
     delegate: Weak<dyn KeyboardLayoutDelegate>,
     key_press_hook: Late<c_ulong>,
+    keymap_handlers: RefCell<Option<(Keymap, Vec<SignalHandlerId>)>>,
 }
 
 include!(concat!(env!("OUT_DIR"), "/generated_keyboard_map.rs"));
@@ -40,6 +46,52 @@ fn lookup_key(keymap: &Keymap, key: &KeymapKey) -> Option<i64> {
     Some(res)
 }
 
+// Shift levels of XKB key types. Level 2 and 3 are reached through
+// ISO_Level3_Shift (AltGr), which corresponds to Option key on macOS.
+const LEVEL_BASE: i32 = 0;
+const LEVEL_SHIFT: i32 = 1;
+const LEVEL_ALT: i32 = 2;
+const LEVEL_ALT_SHIFT: i32 = 3;
+
+/// Character lookup for key code at given group and shift level.
+pub(super) trait KeymapLookup {
+    fn lookup(&self, keycode: u32, group: u8, level: i32) -> Option<i64>;
+
+    /// Returns combining character if the key is a dead key at given level.
+    fn lookup_dead_key(&self, _keycode: u32, _group: u8, _level: i32) -> Option<i64> {
+        None
+    }
+}
+
+fn keymap_key(keycode: u32, group: u8, level: i32) -> KeymapKey {
+    PlatformKeyboardLayout::create_key(gdk::ffi::GdkKeymapKey {
+        keycode,
+        group: group as _,
+        level,
+    })
+}
+
+impl KeymapLookup for Keymap {
+    fn lookup(&self, keycode: u32, group: u8, level: i32) -> Option<i64> {
+        lookup_key(self, &keymap_key(keycode, group, level))
+    }
+
+    fn lookup_dead_key(&self, keycode: u32, group: u8, level: i32) -> Option<i64> {
+        let key = self.lookup_key(&keymap_key(keycode, group, level))?;
+        dead_key_combining_char(key.into_glib()).map(|c| c as i64)
+    }
+}
+
+impl KeymapLookup for XkbKeymap {
+    fn lookup(&self, keycode: u32, group: u8, level: i32) -> Option<i64> {
+        self.character(keycode, group, level).map(|c| c as i64)
+    }
+
+    fn lookup_dead_key(&self, keycode: u32, group: u8, level: i32) -> Option<i64> {
+        self.dead_key(keycode, group, level).map(|c| c as i64)
+    }
+}
+
 impl PlatformKeyboardLayout {
     pub fn new(delegate: Weak<dyn KeyboardLayoutDelegate>) -> Self {
         unsafe { gtk::set_initialized() };
@@ -48,6 +100,7 @@ impl PlatformKeyboardLayout {
             current_layout: RefCell::new(None),
             delegate,
             key_press_hook: Late::new(),
+            keymap_handlers: RefCell::new(None),
         }
     }
 
@@ -64,19 +117,34 @@ impl PlatformKeyboardLayout {
         let key_map = get_key_map();
         if let Some(display) = Display::default() {
             if let Some(keymap) = Keymap::for_display(&display) {
-                let group = self.get_group(&keymap);
-                let keys: Vec<Key> = key_map
-                    .iter()
-                    .map(|a| self.key_from_entry(a, &keymap, group))
-                    .collect();
-                return KeyboardLayout { keys };
+                return self.layout_for_keymap(&key_map, &keymap);
+            }
+        }
+
+        // Without display use configured layout from XKB data files.
+        if let Some(keymap) = XkbKeymap::from_configured_layout() {
+            if keymap.group_count() > 0 {
+                return self.layout_for_keymap(&key_map, &keymap);
             }
         }
 
         Self::fallback_map(&key_map)
     }
 
-    fn get_group(&self, keymap: &Keymap) -> u8 {
+    fn layout_for_keymap(
+        &self,
+        key_map: &[KeyMapEntry],
+        keymap: &dyn KeymapLookup,
+    ) -> KeyboardLayout {
+        let group = self.get_group(keymap);
+        let keys: Vec<Key> = key_map
+            .iter()
+            .map(|a| Self::key_from_entry(a, keymap, group))
+            .collect();
+        KeyboardLayout { keys }
+    }
+
+    fn get_group(&self, keymap: &dyn KeymapLookup) -> u8 {
         // If current layout is ascii capable but with numbers having diacritics, accept that
         if self.is_ascii_capable(keymap, false, self.current_group.get()) {
             return self.current_group.get();
@@ -98,15 +166,8 @@ impl PlatformKeyboardLayout {
         self.current_group.get()
     }
 
-    fn is_ascii(&self, keymap: &Keymap, group: u8, code: u32) -> bool {
-        let key = lookup_key(
-            keymap,
-            &Self::create_key(gdk::ffi::GdkKeymapKey {
-                keycode: code,
-                group: group as _,
-                level: 0,
-            }),
-        );
+    fn is_ascii(&self, keymap: &dyn KeymapLookup, group: u8, code: u32) -> bool {
+        let key = keymap.lookup(code, group, LEVEL_BASE);
         if let Some(key) = key {
             if key < 256 {
                 let char = key as u8 as char;
@@ -116,7 +177,12 @@ impl PlatformKeyboardLayout {
         false
     }
 
-    fn is_ascii_capable(&self, keymap: &Keymap, including_numbers: bool, group: u8) -> bool {
+    fn is_ascii_capable(
+        &self,
+        keymap: &dyn KeymapLookup,
+        including_numbers: bool,
+        group: u8,
+    ) -> bool {
         // Q - P
         for key in 24..33 {
             if !self.is_ascii(keymap, group, key) {
@@ -152,37 +218,35 @@ impl PlatformKeyboardLayout {
         unsafe { from_glib_none(&key as *const _) }
     }
 
-    fn key_from_entry(&self, entry: &KeyMapEntry, keymap: &Keymap, group: u8) -> Key {
-        let key = lookup_key(
-            keymap,
-            &Self::create_key(gdk::ffi::GdkKeymapKey {
-                keycode: entry.platform as u32,
-                group: group as _,
-                level: 0,
-            }),
-        );
+    fn key_from_entry(entry: &KeyMapEntry, keymap: &dyn KeymapLookup, group: u8) -> Key {
+        let lookup = |level| keymap.lookup(entry.platform as u32, group, level);
+        let lookup_dead_key = |level| keymap.lookup_dead_key(entry.platform as u32, group, level);
+        let key = lookup(LEVEL_BASE);
+        let dead_key = lookup_dead_key(LEVEL_BASE);
 
-        let key_shift = if let Some(_key) = key {
-            lookup_key(
-                keymap,
-                &Self::create_key(gdk::ffi::GdkKeymapKey {
-                    keycode: entry.platform as u32,
-                    group: group as _,
-                    level: 1,
-                }),
-            )
+        // Keys with two-level types have nothing at AltGr levels.
+        let levels = [LEVEL_SHIFT, LEVEL_ALT, LEVEL_ALT_SHIFT];
+        let (keys, dead_keys) = if key.is_some() || dead_key.is_some() {
+            (levels.map(lookup), levels.map(lookup_dead_key))
         } else {
-            None
+            ([None; 3], [None; 3])
         };
+        let [key_shift, key_alt, key_alt_shift] = keys;
+        let [dead_key_shift, dead_key_alt, dead_key_alt_shift] = dead_keys;
 
         Key {
             platform: entry.platform,
             physical: entry.physical,
             logical: key.or(entry.logical),
             logical_shift: key_shift,
-            logical_alt: None,
-            logical_alt_shift: None,
+            logical_alt: key_alt,
+            logical_alt_shift: key_alt_shift,
             logical_meta: None,
+            is_dead_key: dead_key.is_some() || dead_keys.iter().any(|k| k.is_some()),
+            dead_key,
+            dead_key_shift,
+            dead_key_alt,
+            dead_key_alt_shift,
         }
     }
 
@@ -201,6 +265,11 @@ impl PlatformKeyboardLayout {
             logical_alt: None,
             logical_alt_shift: None,
             logical_meta: None,
+            is_dead_key: false,
+            dead_key: None,
+            dead_key_shift: None,
+            dead_key_alt: None,
+            dead_key_alt_shift: None,
         }
     }
 
@@ -248,6 +317,7 @@ impl PlatformKeyboardLayout {
     }
 
     pub fn assign_weak_self(&self, weak: Weak<PlatformKeyboardLayout>) {
+        self.connect_keymap_signals(weak.clone());
         if let Some(signal) = Signal::lookup("key-press-event", Widget::static_type()) {
             let hook = signal.add_emission_hook(move |_, values| {
                 if let Some(this) = weak.clone().upgrade() {
@@ -261,13 +331,56 @@ impl PlatformKeyboardLayout {
         }
     }
 
+    fn connect_keymap_signals(&self, weak: Weak<PlatformKeyboardLayout>) {
+        let keymap = match Display::default().and_then(|d| Keymap::for_display(&d)) {
+            Some(keymap) => keymap,
+            None => return,
+        };
+        // Emitted when keymap is replaced, which is how desktop environments
+        // usually switch layouts.
+        let weak_self = weak.clone();
+        let keys_changed = keymap.connect_keys_changed(move |_| {
+            if let Some(this) = weak_self.upgrade() {
+                this.on_layout_changed();
+            }
+        });
+        // GDK emits this for XKB state notify events.
+        let weak_self = weak;
+        let state_changed = keymap.connect_state_changed(move |_| {
+            if let Some(this) = weak_self.upgrade() {
+                if let Some(group) = Self::current_x11_group() {
+                    this.set_group(group);
+                }
+            }
+        });
+        self.keymap_handlers
+            .replace(Some((keymap, vec![keys_changed, state_changed])));
+    }
+
+    /// Returns active XKB group on X11, where pointer state includes group
+    /// bits. Other backends don't expose the group outside of key events.
+    fn current_x11_group() -> Option<u8> {
+        let display = Display::default()?;
+        if display.type_().name() != "GdkX11Display" {
+            return None;
+        }
+        let pointer = display.default_seat()?.pointer()?;
+        let root = display.default_screen().root_window()?;
+        let (_, _, _, state) = root.device_position(&pointer);
+        // XkbGroupForCoreState
+        Some(((state.bits() >> 13) & 0x3) as u8)
+    }
+
     pub(crate) fn on_key_event(&self, event: &Event) {
         if let Some(event) = event.downcast_ref::<EventKey>() {
-            let group = event.group();
-            if group != self.current_group.get() {
-                self.current_group.set(group);
-                self.on_layout_changed();
-            }
+            self.set_group(event.group());
+        }
+    }
+
+    fn set_group(&self, group: u8) {
+        if group != self.current_group.get() {
+            self.current_group.set(group);
+            self.on_layout_changed();
         }
     }
 
@@ -284,5 +397,173 @@ impl Drop for PlatformKeyboardLayout {
         if let Some(signal) = Signal::lookup("key-press-event", Widget::static_type()) {
             signal.remove_emission_hook(*self.key_press_hook);
         }
+        if let Some((keymap, handlers)) = self.keymap_handlers.take() {
+            for handler in handlers {
+                keymap.disconnect(handler);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use super::{get_key_map, KeyMapEntry, KeymapLookup, PlatformKeyboardLayout, XkbKeymap};
+
+    /// Keymap with fixed (keycode, group, level) -> character table.
+    struct FixedKeymap(HashMap<(u32, u8, i32), char>);
+
+    impl FixedKeymap {
+        fn new(keys: &[(u32, u8, [Option<char>; 4])]) -> Self {
+            let mut map = HashMap::new();
+            for (keycode, group, levels) in keys {
+                for (level, c) in levels.iter().enumerate() {
+                    if let Some(c) = c {
+                        map.insert((*keycode, *group, level as i32), *c);
+                    }
+                }
+            }
+            Self(map)
+        }
+    }
+
+    impl KeymapLookup for FixedKeymap {
+        fn lookup(&self, keycode: u32, group: u8, level: i32) -> Option<i64> {
+            self.0.get(&(keycode, group, level)).map(|c| *c as i64)
+        }
+    }
+
+    fn entry(platform: i64, fallback: char) -> KeyMapEntry {
+        KeyMapEntry {
+            platform,
+            physical: 0,
+            logical: None,
+            fallback: Some(fallback as i64),
+        }
+    }
+
+    fn chars(key: &super::Key) -> [Option<char>; 4] {
+        let c = |v: Option<i64>| v.and_then(|v| char::from_u32(v as u32));
+        [
+            c(key.logical),
+            c(key.logical_shift),
+            c(key.logical_alt),
+            c(key.logical_alt_shift),
+        ]
+    }
+
+    // US layout in group 0, German layout in group 1.
+    fn keymap() -> FixedKeymap {
+        FixedKeymap::new(&[
+            (24, 0, [Some('q'), Some('Q'), None, None]),
+            (24, 1, [Some('q'), Some('Q'), Some('@'), Some('Ω')]),
+            (11, 0, [Some('2'), Some('@'), None, None]),
+            (11, 1, [Some('2'), Some('"'), Some('²'), Some('⅛')]),
+            (26, 1, [Some('e'), Some('E'), Some('€'), Some('€')]),
+            (65, 1, [Some(' '), Some(' '), None, None]),
+        ])
+    }
+
+    #[test]
+    fn test_alt_gr_levels() {
+        let keymap = keymap();
+        let key = PlatformKeyboardLayout::key_from_entry(&entry(24, 'q'), &keymap, 1);
+        assert_eq!(chars(&key), [Some('q'), Some('Q'), Some('@'), Some('Ω')]);
+        assert_eq!(key.logical_meta, None);
+
+        let key = PlatformKeyboardLayout::key_from_entry(&entry(11, '2'), &keymap, 1);
+        assert_eq!(chars(&key), [Some('2'), Some('"'), Some('²'), Some('⅛')]);
+
+        let key = PlatformKeyboardLayout::key_from_entry(&entry(26, 'e'), &keymap, 1);
+        assert_eq!(chars(&key), [Some('e'), Some('E'), Some('€'), Some('€')]);
+    }
+
+    #[test]
+    fn test_two_level_key() {
+        let keymap = keymap();
+        let key = PlatformKeyboardLayout::key_from_entry(&entry(65, ' '), &keymap, 1);
+        assert_eq!(chars(&key), [Some(' '), Some(' '), None, None]);
+    }
+
+    #[test]
+    fn test_group() {
+        let keymap = keymap();
+        let key = PlatformKeyboardLayout::key_from_entry(&entry(11, '2'), &keymap, 0);
+        assert_eq!(chars(&key), [Some('2'), Some('@'), None, None]);
+    }
+
+    #[test]
+    fn test_xkb_keymap() {
+        let keymap = XkbKeymap::parse_with_resolver(
+            r#"xkb_keymap {
+                xkb_keycodes { <AD01> = 24; <AC01> = 38; };
+                xkb_symbols {
+                    key <AD01> { [ a, A, ae, AE ] };
+                    key <AC01> { [ q, Q, at, Greek_OMEGA ] };
+                };
+            };"#,
+            &|_, _| None,
+        )
+        .unwrap();
+        let key_map = get_key_map();
+        let keys: Vec<_> = key_map
+            .iter()
+            .map(|e| PlatformKeyboardLayout::key_from_entry(e, &keymap, 0))
+            .collect();
+        let key = keys.iter().find(|k| k.platform == 24).unwrap();
+        assert_eq!(chars(key), [Some('a'), Some('A'), Some('æ'), Some('Æ')]);
+        let key = keys.iter().find(|k| k.platform == 38).unwrap();
+        assert_eq!(chars(key), [Some('q'), Some('Q'), Some('@'), Some('Ω')]);
+    }
+
+    #[test]
+    fn test_dead_keys() {
+        let keymap = XkbKeymap::parse_with_resolver(
+            r#"xkb_keymap {
+                xkb_keycodes { <TLDE> = 49; <AE12> = 21; <AD01> = 24; };
+                xkb_symbols {
+                    key <TLDE> { [ dead_circumflex, degree, U2032, U2033 ] };
+                    key <AE12> { [ dead_acute, dead_grave, dead_cedilla, dead_ogonek ] };
+                    key <AD01> { [ q, Q, at, Greek_OMEGA ] };
+                };
+            };"#,
+            &|_, _| None,
+        )
+        .unwrap();
+
+        let key = PlatformKeyboardLayout::key_from_entry(&entry(49, '`'), &keymap, 0);
+        assert!(key.is_dead_key);
+        assert_eq!(chars(&key), [None, Some('°'), Some('′'), Some('″')]);
+        assert_eq!(key.dead_key, Some(0x302));
+        assert_eq!(key.dead_key_shift, None);
+
+        let key = PlatformKeyboardLayout::key_from_entry(&entry(21, '='), &keymap, 0);
+        assert!(key.is_dead_key);
+        assert_eq!(
+            [
+                key.dead_key,
+                key.dead_key_shift,
+                key.dead_key_alt,
+                key.dead_key_alt_shift
+            ],
+            [Some(0x301), Some(0x300), Some(0x327), Some(0x328)]
+        );
+
+        let key = PlatformKeyboardLayout::key_from_entry(&entry(24, 'q'), &keymap, 0);
+        assert!(!key.is_dead_key);
+        assert_eq!(key.dead_key, None);
+    }
+
+    #[test]
+    fn test_missing_key() {
+        let keymap = keymap();
+        let mut entry = entry(30, 'u');
+        entry.logical = Some(0x100000001);
+        let key = PlatformKeyboardLayout::key_from_entry(&entry, &keymap, 1);
+        assert_eq!(key.logical, Some(0x100000001));
+        assert_eq!(key.logical_shift, None);
+        assert_eq!(key.logical_alt, None);
+        assert_eq!(key.logical_alt_shift, None);
     }
 }
@@ -0,0 +1,263 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    ptr::null_mut,
+    rc::{Rc, Weak},
+};
+
+use gdk::{
+    glib::{
+        translate::{from_glib_none, ToGlibPtr},
+        ObjectExt,
+    },
+    Atom, EventSelection,
+};
+use gdk_sys::gdk_window_get_user_data;
+use glib_sys::gpointer;
+use gtk::{traits::WidgetExt, Inhibit, Widget};
+use irondash_message_channel::IsolateId;
+use irondash_run_loop::spawn;
+use log::warn;
+
+use crate::{
+    api_model::DataProviderValueId,
+    data_provider_manager::PlatformDataProviderDelegate,
+    error::{NativeExtensionsError, NativeExtensionsResult},
+    value_coerce::{CoerceToData, StringFormat},
+    value_promise::ValuePromiseResult,
+};
+
+use super::data_provider::DataObject;
+
+/// Lazy value backing a selection target.
+pub struct LazyValue {
+    pub delegate: Rc<dyn PlatformDataProviderDelegate>,
+    pub isolate_id: IsolateId,
+    pub id: DataProviderValueId,
+}
+
+type LazyValueKey = (IsolateId, DataProviderValueId);
+
+struct ResolvedValue {
+    data: Option<Vec<u8>>,
+    /// Number of [`ResolvedLazyData`] instances referencing the value.
+    retain_count: usize,
+}
+
+thread_local! {
+    /// Data objects owning selections, keyed by selection name.
+    static SELECTION_OWNERS: RefCell<HashMap<String, Weak<DataObject>>> =
+        RefCell::new(HashMap::new());
+    /// Values available to synchronous selection handlers.
+    static RESOLVED: RefCell<HashMap<LazyValueKey, ResolvedValue>> =
+        RefCell::new(HashMap::new());
+}
+
+/// Key of widget data marking widgets with selection request handler.
+const REQUEST_HANDLER_KEY: &str = "super_native_extensions_selection_request";
+
+/// Records data object providing contents of given selection. Must be called
+/// after GTK took the selection ownership.
+///
+/// GTK requires selection data to be provided synchronously from within the
+/// selection handler. To avoid waiting for lazy data there, selection
+/// requests received by the widget owning the selection are held back in
+/// its `selection-request-event` handler. If the requested target (or any
+/// target for `MULTIPLE` requests) is backed by lazy data, the request is
+/// kept pending and delivered to the widget again once the data is resolved,
+/// at which point GTK answers it through the selection handler. Requests
+/// from within this process are not delivered as events; GTK invokes the
+/// selection handler synchronously, so these need to be preceded by
+/// [`resolve_lazy_data_for_target`].
+pub fn set_selection_owner(selection: &str, data_object: &Rc<DataObject>) {
+    SELECTION_OWNERS.with(|owners| {
+        owners
+            .borrow_mut()
+            .insert(selection.to_owned(), Rc::downgrade(data_object))
+    });
+    match selection_owner_widget(&Atom::intern(selection)) {
+        Some(widget) => install_request_handler(&widget),
+        None => warn!("No widget owns selection {selection}"),
+    }
+}
+
+/// Resolves lazy data backing given target of selection owned by this
+/// process. Resolved values are available to selection handler while the
+/// returned object is alive.
+pub async fn resolve_lazy_data_for_target(selection: &str, target: &str) -> ResolvedLazyData {
+    let owner = SELECTION_OWNERS.with(|owners| {
+        owners
+            .borrow()
+            .get(selection)
+            .and_then(|owner| owner.upgrade())
+    });
+    let values = match owner {
+        Some(owner) => owner.lazy_values_for_target(&Atom::intern(target)),
+        None => Vec::new(),
+    };
+    resolve_lazy_values(values).await
+}
+
+/// Returns data for lazy value previously resolved for pending selection
+/// request. Selection handlers must not wait for lazy data, so values that
+/// have not been resolved (i.e. synchronous requests from within this
+/// process not preceded by [`resolve_lazy_data_for_target`]) are refused.
+pub fn resolved_lazy_data(isolate_id: IsolateId, id: DataProviderValueId) -> Option<Vec<u8>> {
+    let res = RESOLVED.with(|resolved| {
+        resolved
+            .borrow()
+            .get(&(isolate_id, id))
+            .map(|value| value.data.clone())
+    });
+    match res {
+        Some(data) => data,
+        None => {
+            warn!("Lazy data {id:?} was requested before being resolved");
+            None
+        }
+    }
+}
+
+/// Keeps resolved lazy values available to selection handler.
+pub struct ResolvedLazyData {
+    keys: Vec<LazyValueKey>,
+    timed_out: bool,
+}
+
+impl ResolvedLazyData {
+    /// Returns error if any of the values timed out. Timed out values are
+    /// answered with empty data.
+    pub fn check(&self) -> NativeExtensionsResult<()> {
+        if self.timed_out {
+            Err(NativeExtensionsError::DataProviderTimedOut)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl Drop for ResolvedLazyData {
+    fn drop(&mut self) {
+        RESOLVED.with(|resolved| {
+            let mut resolved = resolved.borrow_mut();
+            for key in &self.keys {
+                if let Some(value) = resolved.get_mut(key) {
+                    value.retain_count -= 1;
+                    if value.retain_count == 0 {
+                        resolved.remove(key);
+                    }
+                }
+            }
+        });
+    }
+}
+
+async fn resolve_lazy_values(values: Vec<LazyValue>) -> ResolvedLazyData {
+    let mut res = ResolvedLazyData {
+        keys: Vec::new(),
+        timed_out: false,
+    };
+    for value in values {
+        let result = value
+            .delegate
+            .get_lazy_data_async(value.isolate_id, value.id)
+            .await;
+        res.timed_out |= matches!(result, ValuePromiseResult::TimedOut);
+        let data = data_from_result(result, value.id);
+        let key = (value.isolate_id, value.id);
+        RESOLVED.with(|resolved| {
+            let mut resolved = resolved.borrow_mut();
+            let entry = resolved.entry(key).or_insert(ResolvedValue {
+                data: None,
+                retain_count: 0,
+            });
+            entry.data = data;
+            entry.retain_count += 1;
+        });
+        res.keys.push(key);
+    }
+    res
+}
+
+fn data_from_result(result: ValuePromiseResult, id: DataProviderValueId) -> Option<Vec<u8>> {
+    match result {
+        ValuePromiseResult::Ok { value } => value.coerce_to_data(StringFormat::Utf8),
+        ValuePromiseResult::Cancelled => None,
+        ValuePromiseResult::TimedOut => {
+            warn!(
+                "{} (value {id:?})",
+                NativeExtensionsError::DataProviderTimedOut
+            );
+            None
+        }
+    }
+}
+
+/// Returns widget owning given selection within this process.
+fn selection_owner_widget(selection: &Atom) -> Option<Widget> {
+    let window = gdk::selection_owner_get(selection)?;
+    let mut widget: gpointer = null_mut();
+    unsafe { gdk_window_get_user_data(window.to_glib_none().0, &mut widget) };
+    if widget.is_null() {
+        None
+    } else {
+        Some(unsafe { from_glib_none(widget as *mut gtk_sys::GtkWidget) })
+    }
+}
+
+/// Widgets owning selections (GtkClipboard widget, drag and drop widget)
+/// are shared and reused, so the handler is only installed once per widget.
+/// It only affects requests for selections recorded by
+/// [`set_selection_owner`].
+fn install_request_handler(widget: &Widget) {
+    if unsafe { widget.data::<bool>(REQUEST_HANDLER_KEY) }.is_some() {
+        return;
+    }
+    widget.connect_selection_request_event(|widget, event| {
+        Inhibit(defer_selection_request(widget, event))
+    });
+    unsafe { widget.set_data(REQUEST_HANDLER_KEY, true) };
+}
+
+/// Returns true if the request was deferred until lazy data is resolved.
+fn defer_selection_request(widget: &Widget, event: &EventSelection) -> bool {
+    let selection = event.selection();
+    let target = event.target();
+    let owner = SELECTION_OWNERS.with(|owners| {
+        owners
+            .borrow()
+            .get(selection.name().as_str())
+            .and_then(|owner| owner.upgrade())
+    });
+    let Some(owner) = owner else {
+        return false;
+    };
+    // Targets of MULTIPLE request are stored in requestor property that is
+    // only read by GTK, so resolve everything that might be requested.
+    let values = if target.name() == "MULTIPLE" {
+        owner.lazy_values()
+    } else {
+        owner.lazy_values_for_target(&target)
+    };
+    let values: Vec<_> = values
+        .into_iter()
+        .filter(|value| {
+            !RESOLVED.with(|resolved| {
+                resolved
+                    .borrow()
+                    .contains_key(&(value.isolate_id, value.id))
+            })
+        })
+        .collect();
+    if values.is_empty() {
+        return false;
+    }
+    let widget = widget.clone();
+    let event = (**event).clone();
+    spawn(async move {
+        let _resolved = resolve_lazy_values(values).await;
+        // Handler lets the request through now and GTK answers it.
+        widget.event(&event);
+    });
+    true
+}
@@ -199,8 +199,13 @@ impl PlatformMenu {
                     }
                 };
 
-                let label = if let Some(MenuImage::Image { data }) = &action.image {
-                    let surface = surface_from_image_data(data.clone(), 1.0);
+                let surface = match &action.image {
+                    Some(MenuImage::Image { data }) => {
+                        surface_from_image_data(data.clone(), 1.0).ok_log()
+                    }
+                    _ => None,
+                };
+                let label = if let Some(surface) = surface {
                     let image = gtk::Image::from_surface(Some(&surface));
                     let item_box = gtk::Box::new(gtk::Orientation::Horizontal, 6);
                     item_box.add(&image);
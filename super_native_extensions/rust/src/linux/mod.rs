@@ -5,8 +5,10 @@ mod data_provider;
 mod drag;
 mod drag_common;
 mod drop;
+mod drop_preview;
 mod hot_key;
 mod keyboard_layout;
+mod lazy_data;
 mod menu;
 mod reader;
 mod signal;
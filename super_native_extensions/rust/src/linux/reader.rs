@@ -16,12 +16,17 @@ use url::Url;
 
 use crate::{
     error::{NativeExtensionsError, NativeExtensionsResult},
+    format_conversion::{self, FormatConversion},
     reader_manager::{ReadProgress, VirtualFileReader},
 };
 
 use super::{
     clipboard_async::ClipboardAsync,
-    common::{target_includes_text, TYPE_TEXT, TYPE_URI},
+    common::{
+        drag_selection, item_target, parse_item_target, target_includes_text, SELECTION_CLIPBOARD,
+        TYPE_TEXT, TYPE_URI,
+    },
+    lazy_data::{resolve_lazy_data_for_target, ResolvedLazyData},
 };
 
 pub struct PlatformDataReader {
@@ -33,6 +38,9 @@ pub struct PlatformDataReader {
 struct Inner {
     targets: Vec<String>,
     uris: Vec<String>,
+    /// Formats of additional items written by our own data provider,
+    /// indexed by item index minus one.
+    items: Vec<Vec<String>>,
 }
 
 enum Reader {
@@ -55,14 +63,14 @@ impl Reader {
         }
     }
 
-    async fn get_text(&self) -> Option<String> {
+    async fn get_text(&self) -> NativeExtensionsResult<Option<String>> {
         match self {
             Reader::Clipboard(clipboard) => clipboard.get_text().await,
             Reader::Widget(widget) => widget.get_text().await,
         }
     }
 
-    async fn get_data(&self, ty: &str) -> Option<Vec<u8>> {
+    async fn get_data(&self, ty: &str) -> NativeExtensionsResult<Option<Vec<u8>>> {
         match self {
             Reader::Clipboard(clipboard) => clipboard.get_data(ty).await,
             Reader::Widget(widget) => widget.get_data(ty).await,
@@ -80,6 +88,17 @@ impl PlatformDataReader {
         if !self.inner.is_set() && !self.initializing.get() {
             self.initializing.set(true);
             let mut targets = self.reader.get_targets().await;
+            let mut items = Vec::<Vec<String>>::new();
+            targets.retain(|t| match parse_item_target(t) {
+                Some((index, format)) if index > 0 => {
+                    if items.len() < index {
+                        items.resize(index, Vec::new());
+                    }
+                    items[index - 1].push(format.to_owned());
+                    false
+                }
+                _ => true,
+            });
             let has_text = targets
                 .iter()
                 .any(|t| target_includes_text(&Atom::intern(t)));
@@ -98,7 +117,11 @@ impl PlatformDataReader {
             };
             // double check - we might have been preempted
             if !self.inner.is_set() {
-                self.inner.set(Inner { targets, uris })
+                self.inner.set(Inner {
+                    targets,
+                    uris,
+                    items,
+                })
             }
         }
     }
@@ -106,7 +129,7 @@ impl PlatformDataReader {
     pub fn reader_info(self: &Rc<Self>) -> Option<ReaderInfo> {
         if self.inner.is_set() {
             Some(ReaderInfo {
-                number_of_items: 1.max(self.inner.uris.len()),
+                number_of_items: self.inner.number_of_items(),
                 targets: self.inner.targets.clone(),
             })
         } else {
@@ -120,8 +143,7 @@ impl PlatformDataReader {
 
     pub async fn get_items(&self) -> NativeExtensionsResult<Vec<i64>> {
         self.init().await;
-        // uris from urilist are represented as separate items
-        let num_items = 1.max(self.inner.uris.len());
+        let num_items = self.inner.number_of_items();
         Ok((0..num_items as i64).collect())
     }
 
@@ -129,6 +151,8 @@ impl PlatformDataReader {
         self.init().await;
         if item == 0 {
             Ok(self.inner.targets.clone())
+        } else if let Some(formats) = self.inner.item_formats(item) {
+            Ok(formats.clone())
         } else if (item as usize) < self.inner.uris.len() {
             Ok(vec![TYPE_URI.into()])
         } else {
@@ -177,6 +201,21 @@ impl PlatformDataReader {
         data_type: String,
         _progress: Option<Arc<ReadProgress>>,
     ) -> NativeExtensionsResult<Value> {
+        if let Some(formats) = self.inner.item_formats(item) {
+            if !formats.contains(&data_type) {
+                return Ok(Value::Null);
+            }
+            let data = self
+                .reader
+                .get_data(&item_target(item as usize, &data_type))
+                .await?;
+            return Ok(match data {
+                Some(data) if data_type == TYPE_TEXT || data_type == TYPE_URI => {
+                    String::from_utf8(data).ok().into()
+                }
+                data => data.into(),
+            });
+        }
         let item = item as usize;
         if data_type == TYPE_URI && item < self.inner.uris.len() {
             Ok(self.inner.uris[item].clone().into())
@@ -184,9 +223,12 @@ impl PlatformDataReader {
             let target = Atom::intern(&data_type);
             let is_text = target_includes_text(&target);
             if is_text {
-                Ok(self.reader.get_text().await.into())
+                Ok(self.reader.get_text().await?.into())
+            } else if !self.inner.targets.contains(&data_type) {
+                // Requesting target that is not offered might never complete.
+                Ok(Value::Null)
             } else {
-                Ok(self.reader.get_data(&data_type).await.into())
+                Ok(self.reader.get_data(&data_type).await?.into())
             }
         } else {
             Ok(Value::Null)
@@ -217,6 +259,36 @@ impl PlatformDataReader {
         }))
     }
 
+    pub fn format_conversions() -> Vec<FormatConversion> {
+        vec![
+            FormatConversion {
+                source: "text/html",
+                target: TYPE_TEXT,
+                convert: format_conversion::html_to_text,
+            },
+            FormatConversion {
+                source: "text/rtf",
+                target: TYPE_TEXT,
+                convert: format_conversion::rtf_to_text,
+            },
+            FormatConversion {
+                source: "application/rtf",
+                target: TYPE_TEXT,
+                convert: format_conversion::rtf_to_text,
+            },
+            FormatConversion {
+                source: "image/bmp",
+                target: "image/png",
+                convert: format_conversion::bmp_to_png,
+            },
+            FormatConversion {
+                source: "text/x-moz-url",
+                target: TYPE_URI,
+                convert: moz_url_to_uri_list,
+            },
+        ]
+    }
+
     pub fn item_format_is_synthesized(
         &self,
         _item: i64,
@@ -261,25 +333,48 @@ impl PlatformDataReader {
     }
 }
 
+impl Inner {
+    fn number_of_items(&self) -> usize {
+        // uris from urilist are represented as separate items, as well as
+        // additional items advertised through item targets.
+        (1 + self.items.len()).max(self.uris.len())
+    }
+
+    fn item_formats(&self, item: i64) -> Option<&Vec<String>> {
+        if item > 0 {
+            self.items.get(item as usize - 1).filter(|f| !f.is_empty())
+        } else {
+            None
+        }
+    }
+}
+
 struct ClipboardReader {
     clipboard: Clipboard,
 }
 
+// GTK answers requests for clipboard owned by this process synchronously so
+// lazy data must be resolved before requesting it.
 impl ClipboardReader {
     async fn get_targets(&self) -> Vec<String> {
         self.clipboard.get_targets().await
     }
 
     async fn get_uri_list(&self) -> Vec<String> {
+        let _resolved = resolve_lazy_data_for_target(SELECTION_CLIPBOARD, TYPE_URI).await;
         self.clipboard.get_uri_list().await
     }
 
-    async fn get_text(&self) -> Option<String> {
-        self.clipboard.get_text().await
+    async fn get_text(&self) -> NativeExtensionsResult<Option<String>> {
+        let resolved = resolve_lazy_data_for_target(SELECTION_CLIPBOARD, TYPE_TEXT).await;
+        resolved.check()?;
+        Ok(self.clipboard.get_text().await)
     }
 
-    async fn get_data(&self, ty: &str) -> Option<Vec<u8>> {
-        self.clipboard.get_data(ty).await
+    async fn get_data(&self, ty: &str) -> NativeExtensionsResult<Option<Vec<u8>>> {
+        let resolved = resolve_lazy_data_for_target(SELECTION_CLIPBOARD, ty).await;
+        resolved.check()?;
+        Ok(self.clipboard.get_data(ty).await)
     }
 }
 
@@ -340,32 +435,48 @@ impl WidgetReader {
         }
     }
 
+    /// Dragging within this process is handled synchronously by GTK so
+    /// lazy data must be resolved before requesting it.
+    async fn resolve_lazy_data(&self, target: &str) -> ResolvedLazyData {
+        resolve_lazy_data_for_target(&drag_selection(&self.drag_context), target).await
+    }
+
     async fn get_uri_list(&self) -> Vec<String> {
+        let _resolved = self.resolve_lazy_data(TYPE_URI).await;
         let (future, completer) = FutureCompleter::new();
         self.request_data_if_needed(Atom::intern(TYPE_URI), completer);
         let data: SelectionData = future.await;
         data.uris().iter().map(|a| a.as_str().to_owned()).collect()
     }
 
-    async fn get_text(&self) -> Option<String> {
+    async fn get_text(&self) -> NativeExtensionsResult<Option<String>> {
         let first_text_type = self
             .drag_context
             .list_targets()
             .iter()
             .find(|t| target_includes_text(t))
-            .cloned()?;
+            .cloned();
+        let Some(first_text_type) = first_text_type else {
+            return Ok(None);
+        };
+        let resolved = self
+            .resolve_lazy_data(first_text_type.name().as_str())
+            .await;
+        resolved.check()?;
         let (future, completer) = FutureCompleter::new();
         self.request_data_if_needed(first_text_type, completer);
         let data: SelectionData = future.await;
-        data.text().map(|t| t.as_str().to_owned())
+        Ok(data.text().map(|t| t.as_str().to_owned()))
     }
 
-    async fn get_data(&self, format: &str) -> Option<Vec<u8>> {
+    async fn get_data(&self, format: &str) -> NativeExtensionsResult<Option<Vec<u8>>> {
+        let resolved = self.resolve_lazy_data(format).await;
+        resolved.check()?;
         let format = Atom::intern(format);
         let (future, completer) = FutureCompleter::new();
         self.request_data_if_needed(format, completer);
         let data: SelectionData = future.await;
-        Some(data.data())
+        Ok(Some(data.data()))
     }
 
     fn drag_data_received(
@@ -409,6 +520,14 @@ impl Drop for WidgetReader {
     }
 }
 
+/// `text/x-moz-url` contains UTF-16 encoded URL followed by newline and title.
+fn moz_url_to_uri_list(value: Value) -> Option<Value> {
+    match format_conversion::utf16_to_utf8(value)? {
+        Value::String(s) => s.lines().next().map(|url| url.to_owned().into()),
+        _ => None,
+    }
+}
+
 fn mime_from_name(name: &str) -> String {
     let ext = Path::new(name).extension();
     mime_guess::from_path(name)
@@ -3,6 +3,7 @@ use std::{
     collections::HashMap,
     rc::{Rc, Weak},
     sync::{self, Arc, Mutex},
+    thread,
 };
 
 use async_trait::async_trait;
@@ -12,11 +13,15 @@ use irondash_message_channel::{
     IsolateId, Late, MethodCall, PlatformError, PlatformResult, RegisteredAsyncMethodHandler,
     TryFromValue, Value,
 };
-use irondash_run_loop::{util::Capsule, RunLoop, RunLoopSender};
+use irondash_run_loop::{
+    util::{Capsule, FutureCompleter},
+    RunLoop, RunLoopSender,
+};
 
 use crate::{
     context::Context,
     error::{NativeExtensionsError, NativeExtensionsResult},
+    format_conversion::{FormatConversion, FormatConversionRegistry},
     log::OkLog,
     platform::PlatformDataReader,
     util::{DropNotifier, NextId},
@@ -38,6 +43,7 @@ pub struct DataReaderManager {
     readers: RefCell<HashMap<DataReaderId, ReaderEntry>>,
     progresses: RefCell<HashMap<(IsolateId, i64), sync::Weak<ReadProgress>>>,
     virtual_file_readers: RefCell<HashMap<(IsolateId, i64), Rc<dyn VirtualFileReader>>>,
+    format_conversions: FormatConversionRegistry,
 }
 
 struct ReaderEntry {
@@ -148,6 +154,9 @@ impl DataReaderManager {
             readers: RefCell::new(HashMap::new()),
             progresses: RefCell::new(HashMap::new()),
             virtual_file_readers: RefCell::new(HashMap::new()),
+            format_conversions: FormatConversionRegistry::new(
+                PlatformDataReader::format_conversions(),
+            ),
         }
         .register("DataReaderManager")
     }
@@ -274,7 +283,7 @@ impl DataReaderManager {
         let reader = self.get_reader(request.reader_handle)?;
         let start = std::time::Instant::now();
         for item_handle in request.item_handles {
-            let formats = reader.get_formats_for_item(item_handle).await?;
+            let mut formats = reader.get_formats_for_item(item_handle).await?;
             let mut synthesized_formats = Vec::new();
             let mut read_virtual_file_formats = Vec::new();
             let mut copy_virtual_file_formats = Vec::new();
@@ -295,6 +304,10 @@ impl DataReaderManager {
                     copy_virtual_file_formats.push(format.clone());
                 }
             }
+            for format in self.format_conversions.synthesized_formats(&formats) {
+                synthesized_formats.push(format.clone());
+                formats.push(format);
+            }
             let suggested_name = reader.get_suggested_name_for_item(item_handle).await?;
             let file_uri_format =
                 if copy_virtual_file_formats.is_empty() && read_virtual_file_formats.is_empty() {
@@ -327,9 +340,50 @@ impl DataReaderManager {
     ) -> NativeExtensionsResult<Value> {
         let reader = self.get_reader(request.reader_handle)?;
         let progress = self.new_read_progress(isolate_id, request.progress_id);
-        reader
-            .get_data_for_item(request.item_handle, request.format, Some(progress))
-            .await
+        let value = reader
+            .get_data_for_item(
+                request.item_handle,
+                request.format.clone(),
+                Some(progress.clone()),
+            )
+            .await?;
+        if value != Value::Null {
+            return Ok(value);
+        }
+        // Format might be synthesized; convert from source format lazily.
+        let formats = reader.get_formats_for_item(request.item_handle).await?;
+        if formats.contains(&request.format) {
+            return Ok(value);
+        }
+        match self.format_conversions.find(&formats, &request.format) {
+            Some(conversion) => {
+                let value = reader
+                    .get_data_for_item(
+                        request.item_handle,
+                        conversion.source.to_owned(),
+                        Some(progress),
+                    )
+                    .await?;
+                Ok(Self::convert_value(conversion, value).await)
+            }
+            None => Ok(value),
+        }
+    }
+
+    /// Conversions may decode and encode images so they run on worker thread.
+    async fn convert_value(conversion: &FormatConversion, value: Value) -> Value {
+        let convert = conversion.convert;
+        let (future, completer) = FutureCompleter::new();
+        let mut completer = Capsule::new(completer);
+        let sender = RunLoop::current().new_sender();
+        thread::spawn(move || {
+            let res = convert(value).unwrap_or(Value::Null);
+            sender.send(move || {
+                let completer = completer.take().unwrap();
+                completer.complete(res);
+            });
+        });
+        future.await
     }
 
     fn cancel_progress(
@@ -12,7 +12,7 @@ use rand::{distributions::Alphanumeric, Rng};
 
 use crate::log::OkLog;
 
-trait Segment {
+pub(crate) trait Segment {
     /// Writes data to segment. Error is returned if segment already reached
     /// or exceeded its capacity.
     fn write(&self, data: &[u8]) -> Result<(), ()>;
@@ -149,14 +149,14 @@ struct FileSegmentInner {
     completed: bool,
 }
 
-struct FileSegment {
+pub(crate) struct FileSegment {
     max_file_length: u64,
     inner: Mutex<FileSegmentInner>,
     condition: Condvar,
 }
 
 impl FileSegment {
-    fn new(max_file_length: u64) -> FileSegment {
+    pub(crate) fn new(max_file_length: u64) -> FileSegment {
         FileSegment {
             max_file_length,
             inner: Mutex::new(FileSegmentInner {
@@ -1,11 +1,58 @@
 use crate::{
-    api_model::{ImageData, TargettedImage},
+    api_model::{ImageData, Rect, ShadowStyle, TargettedImage},
     blur::blur_image_data,
+    log::OkLog,
 };
 
-fn inflate_image_data(source: &ImageData, padding: i32) -> ImageData {
-    let new_width = source.width + 2 * padding;
-    let new_height = source.height + 2 * padding;
+/// Largest radius supported by [`blur_image_data`].
+const MAX_BLUR_RADIUS: usize = 254;
+
+/// Largest spread and offset in physical pixels. Keeps the inflated image
+/// within reasonable size for arbitrary styles.
+const MAX_SPREAD: i32 = 254;
+const MAX_OFFSET: i32 = 1024;
+
+/// Shadow geometry in physical pixels.
+struct ShadowMetrics {
+    radius: usize,
+    spread: i32,
+    offset_x: i32,
+    offset_y: i32,
+}
+
+impl ShadowMetrics {
+    fn new(style: &ShadowStyle, scale: f64) -> Self {
+        let px = |v: f64| (v * scale).round() as i32;
+        Self {
+            radius: (px(style.radius).max(0) as usize).min(MAX_BLUR_RADIUS),
+            spread: px(style.spread).clamp(-MAX_SPREAD, MAX_SPREAD),
+            offset_x: px(style.offset_x).clamp(-MAX_OFFSET, MAX_OFFSET),
+            offset_y: px(style.offset_y).clamp(-MAX_OFFSET, MAX_OFFSET),
+        }
+    }
+
+    /// Padding needed around the source image to fit the shadow.
+    fn insets(&self) -> Insets {
+        let extent = (self.radius as i32 + self.spread).max(0);
+        Insets {
+            left: (extent - self.offset_x).max(0),
+            top: (extent - self.offset_y).max(0),
+            right: (extent + self.offset_x).max(0),
+            bottom: (extent + self.offset_y).max(0),
+        }
+    }
+}
+
+struct Insets {
+    left: i32,
+    top: i32,
+    right: i32,
+    bottom: i32,
+}
+
+fn inflate_image_data(source: &ImageData, insets: &Insets) -> ImageData {
+    let new_width = source.width + insets.left + insets.right;
+    let new_height = source.height + insets.top + insets.bottom;
     let mut res = ImageData {
         width: new_width,
         height: new_height,
@@ -16,7 +63,7 @@ fn inflate_image_data(source: &ImageData, padding: i32) -> ImageData {
 
     let line_length = (source.width * 4) as usize;
     for y in 0..source.height {
-        let dest_start = ((y + padding) * res.bytes_per_row + padding * 4) as usize;
+        let dest_start = ((y + insets.top) * res.bytes_per_row + insets.left * 4) as usize;
         let src_start = (y * source.bytes_per_row) as usize;
         res.data[dest_start..dest_start + line_length]
             .copy_from_slice(&source.data[src_start..src_start + line_length]);
@@ -24,25 +71,136 @@ fn inflate_image_data(source: &ImageData, padding: i32) -> ImageData {
     res
 }
 
-fn draw_shadow(image: &mut ImageData, radius: i32) {
-    assert!(image.bytes_per_row == image.width * 4);
+/// Grows (`dilate`) or shrinks the mask by `radius` pixels in each direction.
+fn morph_mask(mask: &mut [u8], width: usize, height: usize, radius: usize, dilate: bool) {
+    if radius == 0 {
+        return;
+    }
+    let mut morph = LineMorph::new(radius, dilate);
+    let mut line = Vec::with_capacity(width.max(height));
+    for row in mask.chunks_exact_mut(width) {
+        line.clear();
+        line.extend_from_slice(row);
+        morph.apply(&line, row);
+    }
+    let mut column = vec![0u8; height];
+    for x in 0..width {
+        line.clear();
+        line.extend((0..height).map(|y| mask[y * width + x]));
+        morph.apply(&line, &mut column);
+        for (y, value) in column.iter().enumerate() {
+            mask[y * width + x] = *value;
+        }
+    }
+}
+
+/// Sliding window maximum (or minimum) over window of `2 * radius + 1`
+/// values centered on each value, using van Herk/Gil-Werman algorithm.
+/// Values outside of the line are transparent.
+struct LineMorph {
+    radius: usize,
+    dilate: bool,
+    padded: Vec<u8>,
+    prefix: Vec<u8>,
+    suffix: Vec<u8>,
+}
 
+impl LineMorph {
+    fn new(radius: usize, dilate: bool) -> Self {
+        Self {
+            radius,
+            dilate,
+            padded: Vec::new(),
+            prefix: Vec::new(),
+            suffix: Vec::new(),
+        }
+    }
+
+    fn pick(&self, a: u8, b: u8) -> u8 {
+        if self.dilate {
+            a.max(b)
+        } else {
+            a.min(b)
+        }
+    }
+
+    fn apply(&mut self, src: &[u8], dst: &mut [u8]) {
+        let window = 2 * self.radius + 1;
+        self.padded.clear();
+        self.padded.resize(self.radius, 0);
+        self.padded.extend_from_slice(src);
+        self.padded.resize(src.len() + 2 * self.radius, 0);
+        let len = self.padded.len();
+
+        // Running value from start of each block of `window` values and
+        // from end of each block.
+        self.prefix.clone_from(&self.padded);
+        self.suffix.clone_from(&self.padded);
+        for i in 1..len {
+            if i % window != 0 {
+                self.prefix[i] = self.pick(self.prefix[i - 1], self.padded[i]);
+            }
+        }
+        for i in (0..len - 1).rev() {
+            if (i + 1) % window != 0 {
+                self.suffix[i] = self.pick(self.suffix[i + 1], self.padded[i]);
+            }
+        }
+        // Window starting at i spans at most two blocks.
+        for (i, value) in dst.iter_mut().enumerate() {
+            *value = self.pick(self.suffix[i], self.prefix[i + window - 1]);
+        }
+    }
+}
+
+/// Expects image without padding between rows, as created by
+/// [`inflate_image_data`], with enough room around the source for the shadow.
+fn draw_shadow(image: &mut ImageData, style: &ShadowStyle, metrics: &ShadowMetrics) {
+    debug_assert_eq!(image.bytes_per_row, image.width * 4);
+
+    let width = image.width as usize;
+    let height = image.height as usize;
+    if width == 0 || height == 0 {
+        return;
+    }
     let data = &mut image.data;
 
-    let mut shadow = vec![0u8; (image.width * image.height) as usize];
+    // Source alpha moved by shadow offset.
+    let mut shadow = vec![0u8; width * height];
+    for y in 0..height as i32 {
+        for x in 0..width as i32 {
+            let src_x = x - metrics.offset_x;
+            let src_y = y - metrics.offset_y;
+            if src_x >= 0 && src_y >= 0 && src_x < width as i32 && src_y < height as i32 {
+                shadow[(y as usize) * width + x as usize] =
+                    data[((src_y as usize) * width + src_x as usize) * 4 + 3];
+            }
+        }
+    }
 
-    (0..data.len() / 4).for_each(|i| {
-        shadow[i] = data[i * 4 + 3] / 2;
-    });
+    if metrics.spread != 0 {
+        morph_mask(
+            &mut shadow,
+            width,
+            height,
+            metrics.spread.unsigned_abs() as usize,
+            metrics.spread > 0,
+        );
+    }
+
+    let [color_a, color_r, color_g, color_b] = (style.color as u32).to_be_bytes();
+    let factor = style.opacity.clamp(0.0, 1.0) * f64::from(color_a) / 255.0;
+    shadow
+        .iter_mut()
+        .for_each(|a| *a = (f64::from(*a) * factor) as u8);
+
+    if metrics.radius > 0 {
+        blur_image_data(&mut shadow, 0, 0, width, height, metrics.radius);
+    }
 
-    blur_image_data(
-        &mut shadow,
-        0,
-        0,
-        image.width as usize,
-        image.height as usize,
-        radius as usize,
-    );
+    let r1 = f64::from(color_r) / 255.0;
+    let g1 = f64::from(color_g) / 255.0;
+    let b1 = f64::from(color_b) / 255.0;
 
     (0..data.len() / 4).for_each(|i| {
         let index = i * 4;
@@ -51,9 +209,9 @@ fn draw_shadow(image: &mut ImageData, radius: i32) {
             // full opacity, no shadow
         } else if a0_ == 0 {
             // zero opacity, only shadow
-            data[index] = 0;
-            data[index + 1] = 0;
-            data[index + 2] = 0;
+            data[index] = color_r;
+            data[index + 1] = color_g;
+            data[index + 2] = color_b;
             data[index + 3] = shadow[i];
         } else {
             // blend
@@ -64,9 +222,9 @@ fn draw_shadow(image: &mut ImageData, radius: i32) {
             let a1 = f64::from(shadow[i]) / 255.0;
 
             let a = a0 + a1 * (1.0 - a0);
-            let r = (r0 * a0) / a;
-            let g = (g0 * a0) / a;
-            let b = (b0 * a0) / a;
+            let r = (r0 * a0 + r1 * a1 * (1.0 - a0)) / a;
+            let g = (g0 * a0 + g1 * a1 * (1.0 - a0)) / a;
+            let b = (b0 * a0 + b1 * a1 * (1.0 - a0)) / a;
 
             data[index] = (r * 255.0) as u8;
             data[index + 1] = (g * 255.0) as u8;
@@ -77,18 +235,231 @@ fn draw_shadow(image: &mut ImageData, radius: i32) {
 }
 
 pub trait WithShadow {
-    fn with_shadow(&self, radius: i32) -> Self;
+    fn with_shadow(&self, style: &ShadowStyle) -> Self;
 }
 
 impl WithShadow for TargettedImage {
-    fn with_shadow(&self, radius: i32) -> Self {
-        let adjusted_radius =
-            ((radius as f64) * self.image_data.device_pixel_ratio.unwrap_or(1.0)) as i32;
-        let mut image_data = inflate_image_data(&self.image_data, adjusted_radius);
-        draw_shadow(&mut image_data, adjusted_radius);
+    fn with_shadow(&self, style: &ShadowStyle) -> Self {
+        if self.image_data.validate().ok_log().is_none() {
+            return TargettedImage {
+                image_data: ImageData::blank(
+                    self.image_data.width,
+                    self.image_data.height,
+                    self.image_data.device_pixel_ratio,
+                ),
+                rect: self.rect.clone(),
+            };
+        }
+        let scale = self.image_data.device_pixel_ratio.unwrap_or(1.0);
+        let metrics = ShadowMetrics::new(style, scale);
+        let insets = metrics.insets();
+        let mut image_data = inflate_image_data(&self.image_data, &insets);
+        draw_shadow(&mut image_data, style, &metrics);
         TargettedImage {
             image_data,
-            rect: self.rect.inflated(radius as f64, radius as f64),
+            rect: Rect::xywh(
+                self.rect.x - insets.left as f64 / scale,
+                self.rect.y - insets.top as f64 / scale,
+                self.rect.width + (insets.left + insets.right) as f64 / scale,
+                self.rect.height + (insets.top + insets.bottom) as f64 / scale,
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::api_model::{ImageData, Rect, ShadowStyle, TargettedImage};
+
+    use super::{morph_mask, ShadowMetrics, WithShadow, MAX_BLUR_RADIUS, MAX_OFFSET, MAX_SPREAD};
+
+    // Single opaque white pixel.
+    fn pixel(device_pixel_ratio: Option<f64>) -> TargettedImage {
+        TargettedImage {
+            image_data: ImageData::new(1, 1, 4, vec![255, 255, 255, 255], device_pixel_ratio)
+                .unwrap(),
+            rect: Rect::xywh(10.0, 10.0, 1.0, 1.0),
+        }
+    }
+
+    fn base_style() -> ShadowStyle {
+        ShadowStyle {
+            color: 0xFF102030,
+            offset_x: 0.0,
+            offset_y: 0.0,
+            opacity: 1.0,
+            radius: 0.0,
+            spread: 0.0,
+        }
+    }
+
+    fn alpha(image: &ImageData) -> Vec<u8> {
+        image.data.chunks_exact(4).map(|p| p[3]).collect()
+    }
+
+    #[test]
+    fn test_offset() {
+        let style = ShadowStyle {
+            offset_x: 2.0,
+            offset_y: -1.0,
+            ..base_style()
+        };
+        let res = pixel(None).with_shadow(&style);
+        assert_eq!(res.rect, Rect::xywh(10.0, 9.0, 3.0, 2.0));
+        assert_eq!(res.image_data.width, 3);
+        assert_eq!(res.image_data.height, 2);
+        assert_eq!(alpha(&res.image_data), [0, 0, 255, 255, 0, 0]);
+        // Source pixel is kept, shadow uses style color.
+        assert_eq!(res.image_data.data[12..16], [255, 255, 255, 255]);
+        assert_eq!(res.image_data.data[8..12], [0x10, 0x20, 0x30, 255]);
+    }
+
+    #[test]
+    fn test_device_pixel_ratio() {
+        let style = ShadowStyle {
+            offset_x: 1.0,
+            ..base_style()
+        };
+        let mut image = pixel(Some(2.0));
+        image.image_data = ImageData::new(2, 2, 8, vec![255; 16], Some(2.0)).unwrap();
+        let res = image.with_shadow(&style);
+        // Offset is 2 physical pixels, 1 logical pixel.
+        assert_eq!(res.rect, Rect::xywh(10.0, 10.0, 2.0, 1.0));
+        assert_eq!(res.image_data.width, 4);
+        assert_eq!(
+            alpha(&res.image_data),
+            [255, 255, 255, 255, 255, 255, 255, 255]
+        );
+        assert_eq!(res.image_data.data[8..12], [0x10, 0x20, 0x30, 255]);
+    }
+
+    #[test]
+    fn test_spread() {
+        let style = ShadowStyle {
+            spread: 1.0,
+            ..base_style()
+        };
+        let res = pixel(None).with_shadow(&style);
+        assert_eq!(res.rect, Rect::xywh(9.0, 9.0, 3.0, 3.0));
+        assert_eq!(alpha(&res.image_data), [255; 9]);
+
+        // Negative spread shrinks the shadow below the source.
+        let style = ShadowStyle {
+            spread: -1.0,
+            offset_x: 1.0,
+            ..base_style()
+        };
+        let res = pixel(None).with_shadow(&style);
+        assert_eq!(alpha(&res.image_data), [255, 0]);
+    }
+
+    #[test]
+    fn test_opacity() {
+        let style = ShadowStyle {
+            color: 0x80000000,
+            opacity: 0.5,
+            offset_x: 1.0,
+            ..base_style()
+        };
+        let res = pixel(None).with_shadow(&style);
+        assert_eq!(alpha(&res.image_data), [255, 64]);
+    }
+
+    #[test]
+    fn test_default() {
+        let image = TargettedImage {
+            image_data: ImageData::new(10, 10, 40, vec![255; 400], None).unwrap(),
+            rect: Rect::xywh(10.0, 10.0, 10.0, 10.0),
+        };
+        let res = image.with_shadow(&ShadowStyle::default());
+        assert_eq!(res.rect, Rect::xywh(0.0, 0.0, 30.0, 30.0));
+        let alpha = alpha(&res.image_data);
+        assert_eq!(alpha[15 * 30 + 15], 255);
+        // Black shadow fades out from the image edge and is centered.
+        assert_eq!(res.image_data.data[(15 * 30 + 9) * 4..][..3], [0, 0, 0]);
+        assert!(alpha[15 * 30 + 9] > alpha[15 * 30 + 2]);
+        assert!(alpha[15 * 30 + 9] < 128);
+        assert_eq!(alpha[15 * 30 + 9], alpha[15 * 30 + 20]);
+        assert_eq!(alpha[9 * 30 + 15], alpha[20 * 30 + 15]);
+    }
+
+    #[test]
+    fn test_large_metrics() {
+        let style = ShadowStyle {
+            radius: f64::MAX,
+            spread: 1e12,
+            offset_x: -1e12,
+            offset_y: f64::INFINITY,
+            ..base_style()
+        };
+        let metrics = ShadowMetrics::new(&style, 2.0);
+        assert_eq!(metrics.radius, MAX_BLUR_RADIUS);
+        assert_eq!(metrics.spread, MAX_SPREAD);
+        assert_eq!(
+            (metrics.offset_x, metrics.offset_y),
+            (-MAX_OFFSET, MAX_OFFSET)
+        );
+        let extent = MAX_BLUR_RADIUS as i32 + MAX_SPREAD;
+        let insets = metrics.insets();
+        assert_eq!((insets.left, insets.right), (extent + MAX_OFFSET, 0));
+        assert_eq!((insets.top, insets.bottom), (0, extent + MAX_OFFSET));
+    }
+
+    #[test]
+    fn test_invalid_image() {
+        let mut image = pixel(None);
+        image.image_data.data.truncate(2);
+        let res = image.with_shadow(&ShadowStyle::default());
+        assert_eq!(res.rect, image.rect);
+        assert_eq!(res.image_data.data, [0, 0, 0, 0]);
+    }
+
+    fn morph_mask_naive(
+        mask: &[u8],
+        width: usize,
+        height: usize,
+        radius: usize,
+        dilate: bool,
+    ) -> Vec<u8> {
+        let value = |x: isize, y: isize| {
+            if x < 0 || y < 0 || x >= width as isize || y >= height as isize {
+                0
+            } else {
+                mask[y as usize * width + x as usize]
+            }
+        };
+        let r = radius as isize;
+        let mut res = Vec::new();
+        for y in 0..height as isize {
+            for x in 0..width as isize {
+                let window = (-r..=r).flat_map(|dy| (-r..=r).map(move |dx| (dx, dy)));
+                let values = window.map(|(dx, dy)| value(x + dx, y + dy));
+                res.push(if dilate {
+                    values.max().unwrap()
+                } else {
+                    values.min().unwrap()
+                });
+            }
+        }
+        res
+    }
+
+    #[test]
+    fn test_morph_mask() {
+        let (width, height) = (13, 7);
+        let mask: Vec<u8> = (0..width * height)
+            .map(|i| ((i * 73 + 11) % 256) as u8)
+            .collect();
+        for radius in [0, 1, 2, 3, 6, 20] {
+            for dilate in [true, false] {
+                let mut res = mask.clone();
+                morph_mask(&mut res, width, height, radius, dilate);
+                assert_eq!(
+                    res,
+                    morph_mask_naive(&mask, width, height, radius, dilate),
+                    "radius {radius}, dilate {dilate}"
+                );
+            }
         }
     }
 }
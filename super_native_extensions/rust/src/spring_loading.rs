@@ -0,0 +1,216 @@
+use std::time::Duration;
+
+use irondash_message_channel::{IntoValue, TryFromValue};
+
+use crate::{
+    api_model::{DropOperation, Point},
+    drop_manager::DropSessionId,
+};
+
+#[derive(TryFromValue, Clone, Debug)]
+#[irondash(rename_all = "camelCase")]
+pub struct SpringLoadingConfiguration {
+    pub enabled: bool,
+    /// Time in seconds the pointer must rest within region before
+    /// spring-load event is sent.
+    pub delay: f64,
+    /// Maximum distance from the location where region was entered for
+    /// the pointer to be still considered in the same region.
+    pub region_size: f64,
+}
+
+impl Default for SpringLoadingConfiguration {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            delay: 0.7,
+            region_size: 8.0,
+        }
+    }
+}
+
+#[derive(IntoValue, Debug, Clone, PartialEq)]
+#[irondash(rename_all = "camelCase")]
+pub struct SpringLoadEvent {
+    pub session_id: DropSessionId,
+    pub location_in_view: Point,
+    /// Whether the event was triggered by user (i.e. pressing a key) rather
+    /// than by hovering.
+    pub forced: bool,
+}
+
+/// Tracks pointer location during drop session and decides when to send
+/// spring-load event. Timers are scheduled by the caller; each region gets
+/// new generation so that timers from previous regions can be ignored.
+pub struct SpringLoadTracker {
+    configuration: SpringLoadingConfiguration,
+    session_id: Option<DropSessionId>,
+    anchor: Point,
+    location: Point,
+    generation: u64,
+    fired: bool,
+    /// Whether drop target accepted the drop in current region.
+    accepted: bool,
+}
+
+impl SpringLoadTracker {
+    pub fn new(configuration: SpringLoadingConfiguration) -> Self {
+        Self {
+            configuration,
+            session_id: None,
+            anchor: Point::default(),
+            location: Point::default(),
+            generation: 0,
+            fired: false,
+            accepted: false,
+        }
+    }
+
+    pub fn set_configuration(&mut self, configuration: SpringLoadingConfiguration) {
+        self.configuration = configuration;
+        self.reset();
+    }
+
+    pub fn delay(&self) -> Duration {
+        Duration::from_secs_f64(self.configuration.delay.max(0.0))
+    }
+
+    /// Called for every drop update. Returns generation for which a timer
+    /// should be scheduled when pointer entered a new region.
+    pub fn update(&mut self, session_id: DropSessionId, location: Point) -> Option<u64> {
+        if !self.configuration.enabled {
+            return None;
+        }
+        let new_region = self.session_id != Some(session_id)
+            || (location.x - self.anchor.x).abs() > self.configuration.region_size
+            || (location.y - self.anchor.y).abs() > self.configuration.region_size;
+        self.location = location;
+        if new_region {
+            self.session_id = Some(session_id);
+            self.anchor = self.location.clone();
+            self.generation += 1;
+            self.fired = false;
+            self.accepted = false;
+            Some(self.generation)
+        } else {
+            None
+        }
+    }
+
+    /// Called with drop operation returned for drop update. Hovering only
+    /// spring-loads regions where the drop is not accepted.
+    pub fn update_operation(&mut self, session_id: DropSessionId, operation: DropOperation) {
+        if self.session_id == Some(session_id) {
+            self.accepted = !matches!(
+                operation,
+                DropOperation::None | DropOperation::UserCancelled | DropOperation::Forbidden
+            );
+        }
+    }
+
+    /// Called when timer scheduled for `generation` fires.
+    pub fn timer_fired(&mut self, generation: u64) -> Option<SpringLoadEvent> {
+        if generation != self.generation || self.fired || self.accepted {
+            return None;
+        }
+        self.fire(false)
+    }
+
+    /// Sends spring-load event immediately for current region.
+    pub fn force(&mut self, session_id: DropSessionId) -> Option<SpringLoadEvent> {
+        if !self.configuration.enabled || self.session_id != Some(session_id) {
+            return None;
+        }
+        self.fire(true)
+    }
+
+    /// Called when drop is performed or pointer leaves the drop target.
+    pub fn reset(&mut self) {
+        self.session_id = None;
+        self.generation += 1;
+        self.fired = false;
+    }
+
+    fn fire(&mut self, forced: bool) -> Option<SpringLoadEvent> {
+        self.fired = true;
+        Some(SpringLoadEvent {
+            session_id: self.session_id?,
+            location_in_view: self.location.clone(),
+            forced,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        api_model::{DropOperation, Point},
+        drop_manager::DropSessionId,
+    };
+
+    use super::{SpringLoadTracker, SpringLoadingConfiguration};
+
+    fn point(x: f64, y: f64) -> Point {
+        Point { x, y }
+    }
+
+    fn session() -> DropSessionId {
+        1i64.into()
+    }
+
+    #[test]
+    fn test_fires_once_per_region() {
+        let mut tracker = SpringLoadTracker::new(SpringLoadingConfiguration::default());
+        let generation = tracker.update(session(), point(10.0, 10.0)).unwrap();
+        assert_eq!(tracker.update(session(), point(12.0, 13.0)), None);
+        let event = tracker.timer_fired(generation).unwrap();
+        assert_eq!(event.location_in_view, point(12.0, 13.0));
+        assert!(!event.forced);
+        assert_eq!(tracker.timer_fired(generation), None);
+
+        let next = tracker.update(session(), point(40.0, 10.0)).unwrap();
+        assert_eq!(tracker.timer_fired(generation), None);
+        assert!(tracker.timer_fired(next).is_some());
+    }
+
+    #[test]
+    fn test_reset() {
+        let mut tracker = SpringLoadTracker::new(SpringLoadingConfiguration::default());
+        let generation = tracker.update(session(), point(10.0, 10.0)).unwrap();
+        tracker.reset();
+        assert_eq!(tracker.timer_fired(generation), None);
+        assert_eq!(tracker.force(session()), None);
+    }
+
+    #[test]
+    fn test_force() {
+        let mut tracker = SpringLoadTracker::new(SpringLoadingConfiguration::default());
+        let generation = tracker.update(session(), point(10.0, 10.0)).unwrap();
+        let event = tracker.force(session()).unwrap();
+        assert!(event.forced);
+        assert_eq!(tracker.timer_fired(generation), None);
+    }
+
+    #[test]
+    fn test_accepted_region() {
+        let mut tracker = SpringLoadTracker::new(SpringLoadingConfiguration::default());
+        let generation = tracker.update(session(), point(10.0, 10.0)).unwrap();
+        tracker.update_operation(session(), DropOperation::Copy);
+        assert_eq!(tracker.timer_fired(generation), None);
+        assert!(tracker.force(session()).is_some());
+
+        let next = tracker.update(session(), point(40.0, 10.0)).unwrap();
+        tracker.update_operation(session(), DropOperation::None);
+        assert!(tracker.timer_fired(next).is_some());
+    }
+
+    #[test]
+    fn test_disabled() {
+        let mut tracker = SpringLoadTracker::new(SpringLoadingConfiguration {
+            enabled: false,
+            ..Default::default()
+        });
+        assert_eq!(tracker.update(session(), point(10.0, 10.0)), None);
+        assert_eq!(tracker.force(session()), None);
+    }
+}
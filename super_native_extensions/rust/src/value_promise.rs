@@ -64,8 +64,12 @@ pub enum PromiseResult<T> {
 #[derive(Debug, TryFromValue, PartialEq, Eq)]
 #[irondash(tag = "type", rename_all = "camelCase")]
 pub enum ValuePromiseResult {
-    Ok { value: Value },
+    Ok {
+        value: Value,
+    },
     Cancelled,
+    /// Provider did not respond in time. Never sent by Dart.
+    TimedOut,
 }
 
 pub type ValuePromise = Promise<ValuePromiseResult>;
@@ -25,6 +25,7 @@ use windows::{
 use crate::{
     api_model::ImageData,
     error::{NativeExtensionsError, NativeExtensionsResult},
+    image_data::{PixelFormat, PixelLayout},
 };
 
 const INTERNAL_PREFIX: &str = "NativeShell_CF_";
@@ -106,6 +107,13 @@ pub fn image_data_to_hbitmap(image: &ImageData) -> NativeExtensionsResult<HBITMA
         bmiColors: Default::default(),
     };
 
+    // Bottom-up DIB with straight alpha.
+    let data = image.to_layout(PixelLayout {
+        format: PixelFormat::BGRA_STRAIGHT,
+        bytes_per_row: (image.width * 4) as usize,
+        bottom_up: true,
+    })?;
+
     unsafe {
         let dc = GetDC(HWND(0));
 
@@ -120,35 +128,7 @@ pub fn image_data_to_hbitmap(image: &ImageData) -> NativeExtensionsResult<HBITMA
             0,
         )?;
 
-        // Bitmap needs to be flipped and unpremultiplied
-
-        let dst_stride = (image.width * 4) as isize;
-        let ptr = ptr as *mut u8;
-        for y in 0..image.height as isize {
-            let src_line = image
-                .data
-                .as_ptr()
-                .offset((image.height as isize - y - 1) * image.bytes_per_row as isize);
-
-            let dst_line = ptr.offset(y * dst_stride);
-
-            for x in (0..dst_stride).step_by(4) {
-                let (r, g, b, a) = (
-                    *src_line.offset(x) as i32,
-                    *src_line.offset(x + 1) as i32,
-                    *src_line.offset(x + 2) as i32,
-                    *src_line.offset(x + 3) as i32,
-                );
-
-                // ByteFormat.rawStraightRgba already has unpremultiplied alpha
-                // but channel order is different.
-
-                *dst_line.offset(x) = b as u8;
-                *dst_line.offset(x + 1) = g as u8;
-                *dst_line.offset(x + 2) = r as u8;
-                *dst_line.offset(x + 3) = a as u8;
-            }
-        }
+        std::ptr::copy_nonoverlapping(data.as_ptr(), ptr as *mut u8, data.len());
 
         ReleaseDC(HWND(0), dc);
 
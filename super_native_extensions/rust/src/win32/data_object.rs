@@ -15,8 +15,8 @@ use windows::{
     core::{implement, HRESULT, HSTRING},
     Win32::{
         Foundation::{
-            GlobalFree, BOOL, DATA_S_SAMEFORMATETC, DV_E_FORMATETC, E_NOTIMPL, E_OUTOFMEMORY,
-            HGLOBAL, OLE_E_ADVISENOTSUPPORTED, POINT, S_FALSE, S_OK,
+            GlobalFree, BOOL, DATA_S_SAMEFORMATETC, DV_E_FORMATETC, ERROR_TIMEOUT, E_NOTIMPL,
+            E_OUTOFMEMORY, HGLOBAL, OLE_E_ADVISENOTSUPPORTED, POINT, S_FALSE, S_OK,
         },
         System::{
             Com::{
@@ -40,6 +40,7 @@ use windows::{
 use crate::{
     api_model::{DataProviderValueId, DataRepresentation, VirtualFileStorage},
     data_provider_manager::{DataProviderHandle, PlatformDataProviderDelegate, VirtualFileResult},
+    error::{NativeExtensionsError, NativeExtensionsResult},
     log::OkLog,
     segmented_queue::{new_segmented_queue, QueueConfiguration},
     util::DropNotifier,
@@ -119,7 +120,7 @@ impl DataObject {
         &self,
         provider: &PlatformDataProvider,
         id: DataProviderValueId,
-    ) -> Option<Vec<u8>> {
+    ) -> NativeExtensionsResult<Option<Vec<u8>>> {
         let delegate = provider.delegate.upgrade();
         if let Some(delegate) = delegate {
             let data = delegate.get_lazy_data(provider.isolate_id, id, None);
@@ -127,20 +128,27 @@ impl DataObject {
             loop {
                 match data.try_take() {
                     Some(ValuePromiseResult::Ok { value }) => {
-                        return value.coerce_to_data(StringFormat::Utf16NullTerminated)
+                        return Ok(value.coerce_to_data(StringFormat::Utf16NullTerminated))
+                    }
+                    Some(ValuePromiseResult::Cancelled) => return Ok(None),
+                    Some(ValuePromiseResult::TimedOut) => {
+                        return Err(NativeExtensionsError::DataProviderTimedOut)
                     }
-                    Some(ValuePromiseResult::Cancelled) => return None,
                     None => RunLoop::current()
                         .platform_run_loop
                         .poll_once(&mut poll_session),
                 }
             }
         } else {
-            None
+            Ok(None)
         }
     }
 
-    fn data_for_format(&self, format: u32, index: usize) -> Option<Vec<u8>> {
+    fn data_for_format(
+        &self,
+        format: u32,
+        index: usize,
+    ) -> NativeExtensionsResult<Option<Vec<u8>>> {
         let provider = self.providers.get(index).as_ref().cloned();
         if let Some(provider) = provider {
             let provider = &provider.provider;
@@ -149,7 +157,7 @@ impl DataObject {
                 match representation {
                     DataRepresentation::Simple { format, data } => {
                         if &format_string == format {
-                            return data.coerce_to_data(StringFormat::Utf16NullTerminated);
+                            return Ok(data.coerce_to_data(StringFormat::Utf16NullTerminated));
                         }
                     }
                     DataRepresentation::Lazy { format, id } => {
@@ -160,10 +168,8 @@ impl DataObject {
                     _ => {}
                 }
             }
-            None
-        } else {
-            None
         }
+        Ok(None)
     }
 
     /// Bundles slice of utf16 encoded string into CF_HDROP
@@ -191,7 +197,11 @@ impl DataObject {
     fn data_for_hdrop(&self) -> Option<Vec<u8>> {
         let n_items = self.providers.len();
         let files: Vec<_> = (0..n_items)
-            .filter_map(|i| self.data_for_format(CF_HDROP.0 as u32, i))
+            .filter_map(|i| {
+                self.data_for_format(CF_HDROP.0 as u32, i)
+                    .ok_log()
+                    .flatten()
+            })
             .collect();
         if files.is_empty() {
             None
@@ -506,24 +516,27 @@ impl IDataObject_Impl for DataObject {
 
         let needs_generate_bitmap = self.needs_synthesize_bitmap();
 
-        let data = self
-            .extra_data
-            .borrow()
-            .get(&format.cfFormat)
-            .cloned()
-            .or_else(|| {
-                if format.cfFormat as u32 == format_file_descriptor {
-                    self.data_for_file_group_descritor()
-                } else if format.cfFormat == CF_HDROP.0 {
-                    self.data_for_hdrop()
-                } else if needs_generate_bitmap && format.cfFormat == CF_DIB.0 {
-                    self.synthesize_bitmap_data(false).ok_log()
-                } else if needs_generate_bitmap && format.cfFormat == CF_DIBV5.0 {
-                    self.synthesize_bitmap_data(true).ok_log()
-                } else {
-                    self.data_for_format(format.cfFormat as u32, 0)
+        let extra_data = self.extra_data.borrow().get(&format.cfFormat).cloned();
+        let data = match extra_data {
+            Some(data) => Some(data),
+            None if format.cfFormat as u32 == format_file_descriptor => {
+                self.data_for_file_group_descritor()
+            }
+            None if format.cfFormat == CF_HDROP.0 => self.data_for_hdrop(),
+            None if needs_generate_bitmap && format.cfFormat == CF_DIB.0 => {
+                self.synthesize_bitmap_data(false).ok_log()
+            }
+            None if needs_generate_bitmap && format.cfFormat == CF_DIBV5.0 => {
+                self.synthesize_bitmap_data(true).ok_log()
+            }
+            None => match self.data_for_format(format.cfFormat as u32, 0) {
+                // Lets the consumer tell unresponsive provider from missing data.
+                Err(NativeExtensionsError::DataProviderTimedOut) => {
+                    return Err(ERROR_TIMEOUT.to_hresult().into());
                 }
-            });
+                res => res.ok_log().flatten(),
+            },
+        };
 
         // println!("DATA {:?} {:?}", data, format_to_string(format.cfFormat as u32));
 
@@ -26,7 +26,9 @@ use windows::{
 };
 
 use crate::{
-    api_model::{DataProviderId, DragConfiguration, DragRequest, DropOperation, Point},
+    api_model::{
+        DataProviderId, DragConfiguration, DragRequest, DropOperation, Point, TargettedImage,
+    },
     drag_manager::{
         DataProviderEntry, DragSessionId, PlatformDragContextDelegate, PlatformDragContextId,
     },
@@ -45,6 +47,8 @@ use super::{
 struct DragSession {
     id: DragSessionId,
     configuration: DragConfiguration,
+    /// Set by `cancel_drag`, checked in `QueryContinueDrag`.
+    cancel_requested: Rc<Cell<bool>>,
 }
 
 pub struct PlatformDragContext {
@@ -61,6 +65,7 @@ pub struct DropSource {
     last_reported_location: RefCell<Point>,
     session_id: DragSessionId,
     cancelled: Rc<Cell<bool>>,
+    cancel_requested: Rc<Cell<bool>>,
 }
 
 #[allow(non_snake_case)]
@@ -69,12 +74,14 @@ impl DropSource {
         platform_context: Weak<PlatformDragContext>,
         session_id: DragSessionId,
         cancelled: Rc<Cell<bool>>,
+        cancel_requested: Rc<Cell<bool>>,
     ) -> IDropSource {
         Self {
             platform_context,
             session_id,
             last_reported_location: RefCell::new(Point::default()),
             cancelled,
+            cancel_requested,
         }
         .into()
     }
@@ -90,6 +97,9 @@ impl IDropSource_Impl for DropSource {
         if fescapepressed.as_bool() {
             self.cancelled.replace(true);
             DRAGDROP_S_CANCEL
+        } else if self.cancel_requested.get() {
+            // Cancelled programmatically, reported as DropOperation::None.
+            DRAGDROP_S_CANCEL
         } else if grfkeystate.0 & MK_LBUTTON.0 == 0 {
             DRAGDROP_S_DROP
         } else {
@@ -145,6 +155,10 @@ impl PlatformDragContext {
         true
     }
 
+    pub fn supports_keyboard_drag() -> bool {
+        false
+    }
+
     pub async fn start_drag(
         &self,
         request: DragRequest,
@@ -184,7 +198,8 @@ impl PlatformDragContext {
             NativeExtensionsError::OtherError("Missing combined drag image".into())
         })?;
 
-        let drag_image = drag_image.with_shadow(10);
+        // Combined image gets single shadow, styled after the first item.
+        let drag_image = drag_image.with_shadow(&request.configuration.shadow_for_item(0));
 
         let data_object = DataObject::create(providers);
         let helper: IDragSourceHelper = create_instance(&CLSID_DragDropHelper)?;
@@ -216,13 +231,20 @@ impl PlatformDragContext {
             allowed_effects |= operation.to_platform().0;
         }
 
+        let cancel_requested = Rc::new(Cell::new(false));
         self.current_session.replace(Some(DragSession {
             id: session_id,
             configuration: request.configuration,
+            cancel_requested: cancel_requested.clone(),
         }));
 
         let cancelled = Rc::new(Cell::new(false));
-        let drop_source = DropSource::create(self.weak_self.clone(), session_id, cancelled.clone());
+        let drop_source = DropSource::create(
+            self.weak_self.clone(),
+            session_id,
+            cancelled.clone(),
+            cancel_requested,
+        );
         let mut effects_out = DROPEFFECT_NONE;
         unsafe {
             let _ = DoDragDrop(
@@ -245,7 +267,7 @@ impl PlatformDragContext {
             } else {
                 operation
             };
-            delegate.drag_session_did_end_with_operation(self.id, session_id, operation);
+            delegate.drag_session_did_end_with_operation(self.id, session_id, operation, None);
             for c in delegate.get_platform_drop_contexts() {
                 c.local_dragging_did_end()?;
             }
@@ -278,4 +300,32 @@ impl PlatformDragContext {
         }
         Err(NativeExtensionsError::DragSessionNotFound)
     }
+
+    pub fn get_active_sessions(&self) -> Vec<DragSessionId> {
+        self.current_session
+            .borrow()
+            .as_ref()
+            .map(|s| s.id)
+            .into_iter()
+            .collect()
+    }
+
+    /// Cancellation takes effect next time `DoDragDrop` queries drop source.
+    pub fn cancel_drag(&self, session_id: DragSessionId) -> NativeExtensionsResult<()> {
+        match self.current_session.borrow().as_ref() {
+            Some(session) if session.id == session_id => {
+                session.cancel_requested.set(true);
+                Ok(())
+            }
+            _ => Err(NativeExtensionsError::DragSessionNotFound),
+        }
+    }
+
+    pub fn update_drag_image(
+        &self,
+        _session_id: DragSessionId,
+        _image: TargettedImage,
+    ) -> NativeExtensionsResult<()> {
+        Err(NativeExtensionsError::UnsupportedOperation)
+    }
 }
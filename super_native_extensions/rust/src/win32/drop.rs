@@ -258,6 +258,11 @@ impl PlatformDropContext {
             accepted_operation,
             items,
             reader: Some(session.registered_reader.clone()),
+            auto_scroll_velocity: None,
+            modifiers: None,
+            pointer_button: None,
+            location_on_screen: None,
+            source_drag_session_id: None,
         })
     }
 
@@ -8,7 +8,7 @@ use std::{
 use irondash_message_channel::Late;
 use irondash_run_loop::{platform::MessageListener, RunLoop};
 use windows::Win32::{
-    Foundation::HWND,
+    Foundation::{ERROR_HOTKEY_ALREADY_REGISTERED, HWND},
     UI::{
         Input::KeyboardAndMouse::{
             GetAsyncKeyState, MapVirtualKeyW, RegisterHotKey, UnregisterHotKey, HOT_KEY_MODIFIERS,
@@ -19,7 +19,7 @@ use windows::Win32::{
 };
 
 use crate::{
-    error::NativeExtensionsResult,
+    error::{NativeExtensionsError, NativeExtensionsResult},
     hot_key_manager::{HotKeyCreateRequest, HotKeyHandle, HotKeyManagerDelegate},
 };
 
@@ -74,7 +74,13 @@ impl PlatformHotKeyManager {
         self.next_id.replace(id + 1);
         unsafe {
             let vk = MapVirtualKeyW(request.platform_code as u32, MAPVK_VSC_TO_VK);
-            RegisterHotKey(Self::hwnd(), id, modifiers, vk)?;
+            RegisterHotKey(Self::hwnd(), id, modifiers, vk).map_err(|e| {
+                if e.code() == ERROR_HOTKEY_ALREADY_REGISTERED.to_hresult() {
+                    NativeExtensionsError::HotKeyAlreadyRegistered
+                } else {
+                    e.into()
+                }
+            })?;
         }
         self.hot_keys.borrow_mut().insert(id, (handle, request));
         Ok(())
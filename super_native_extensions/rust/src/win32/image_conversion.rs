@@ -1,5 +1,6 @@
 use std::{ptr::null_mut, slice};
 
+use irondash_message_channel::Value;
 use windows::{
     core::PWSTR,
     Win32::{
@@ -18,11 +19,32 @@ use windows::{
             Memory::{GlobalLock, GlobalSize, GlobalUnlock},
             Variant::{VariantInit, VT_BOOL},
         },
+        UI::Shell::SHCreateMemStream,
     },
 };
 
+use crate::log::OkLog;
+
 use super::common::create_instance;
 
+/// Format conversion from CF_DIB or CF_DIBV5 data to PNG. Called on worker
+/// thread.
+pub fn dib_to_png(value: Value) -> Option<Value> {
+    let data = match value {
+        Value::U8List(data) => data,
+        _ => return None,
+    };
+    let mut bmp = Vec::<u8>::new();
+    bmp.extend_from_slice(&[0x42, 0x4D]); // BM
+    bmp.extend_from_slice(&((data.len() + 14) as u32).to_le_bytes()); // File size
+    bmp.extend_from_slice(&[0, 0]); // reserved 1
+    bmp.extend_from_slice(&[0, 0]); // reserved 2
+    bmp.extend_from_slice(&[0, 0, 0, 0]); // data starting address; not required by decoder
+    bmp.extend_from_slice(&data);
+    let stream = unsafe { SHCreateMemStream(Some(&bmp)) }?;
+    convert_to_png(stream).ok_log().map(Value::U8List)
+}
+
 /// Convert image from input_stream to PNG
 pub fn convert_to_png(input_stream: IStream) -> windows::core::Result<Vec<u8>> {
     let factory: IWICImagingFactory = create_instance(&CLSID_WICImagingFactory)?;
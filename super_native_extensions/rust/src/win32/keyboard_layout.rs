@@ -185,6 +185,11 @@ impl PlatformKeyboardLayout {
             logical_alt: None,
             logical_alt_shift: None,
             logical_meta: None,
+            is_dead_key: false,
+            dead_key: None,
+            dead_key_shift: None,
+            dead_key_alt: None,
+            dead_key_alt_shift: None,
         };
 
         let virtual_code = MapVirtualKeyW(entry.platform as u32, MAPVK_VSC_TO_VK);
@@ -22,7 +22,7 @@ use std::{
 };
 use threadpool::ThreadPool;
 use windows::{
-    core::{w, HSTRING},
+    core::HSTRING,
     Win32::{
         Foundation::S_OK,
         Storage::FileSystem::{
@@ -36,10 +36,7 @@ use windows::{
             },
             DataExchange::RegisterClipboardFormatW,
             Memory::{GlobalLock, GlobalSize, GlobalUnlock},
-            Ole::{
-                OleGetClipboard, ReleaseStgMedium, CF_DIB, CF_DIBV5, CF_HDROP, CF_TIFF,
-                CF_UNICODETEXT,
-            },
+            Ole::{OleGetClipboard, ReleaseStgMedium, CF_HDROP, CF_TIFF, CF_UNICODETEXT},
         },
         UI::Shell::{
             SHCreateMemStream, CFSTR_FILECONTENTS, CFSTR_FILEDESCRIPTOR, DROPFILES,
@@ -50,6 +47,7 @@ use windows::{
 
 use crate::{
     error::{NativeExtensionsError, NativeExtensionsResult},
+    format_conversion::{self, FormatConversion},
     log::OkLog,
     platform_impl::platform::common::make_format_with_tymed_index,
     reader_manager::{ReadProgress, VirtualFileReader},
@@ -62,7 +60,7 @@ use super::{
         read_stream_fully,
     },
     data_object::{DataObject, GetData},
-    image_conversion::convert_to_png,
+    image_conversion,
 };
 
 pub struct PlatformDataReader {
@@ -105,7 +103,7 @@ impl PlatformDataReader {
     }
 
     /// Returns formats that DataObject can provide.
-    fn data_object_formats_raw(&self) -> NativeExtensionsResult<Vec<u32>> {
+    fn data_object_formats(&self) -> NativeExtensionsResult<Vec<u32>> {
         let formats = self.formats_raw.clone().take();
         match formats {
             Some(formats) => Ok(formats),
@@ -128,24 +126,6 @@ impl PlatformDataReader {
         }
     }
 
-    fn need_to_synthesize_png(&self) -> NativeExtensionsResult<bool> {
-        let png = unsafe { RegisterClipboardFormatW(w!("PNG")) };
-        let formats = self.data_object_formats_raw()?;
-        let has_dib =
-            formats.contains(&(CF_DIBV5.0 as u32)) || formats.contains(&(CF_DIB.0 as u32));
-        let has_png = formats.contains(&png);
-        Ok(has_dib && !has_png)
-    }
-
-    fn data_object_formats(&self) -> NativeExtensionsResult<Vec<u32>> {
-        let mut res = self.data_object_formats_raw()?;
-        if self.need_to_synthesize_png()? {
-            let png = unsafe { RegisterClipboardFormatW(w!("PNG")) };
-            res.push(png);
-        }
-        Ok(res)
-    }
-
     pub fn get_formats_for_item_sync(&self, item: i64) -> NativeExtensionsResult<Vec<String>> {
         let mut formats = if item == 0 {
             self.data_object_formats()?
@@ -175,12 +155,54 @@ impl PlatformDataReader {
         self.get_formats_for_item_sync(item)
     }
 
+    pub fn format_conversions() -> Vec<FormatConversion> {
+        // Names returned by format_to_string for CF_DIB, CF_UNICODETEXT,
+        // CF_HDROP and CF_DIBV5.
+        const DIB: &str = "NativeShell_CF_8";
+        const UNICODE_TEXT: &str = "NativeShell_CF_13";
+        const HDROP: &str = "NativeShell_CF_15";
+        const DIBV5: &str = "NativeShell_CF_17";
+        vec![
+            FormatConversion {
+                source: "HTML Format",
+                target: UNICODE_TEXT,
+                convert: format_conversion::html_to_text,
+            },
+            FormatConversion {
+                source: "Rich Text Format",
+                target: UNICODE_TEXT,
+                convert: format_conversion::rtf_to_text,
+            },
+            FormatConversion {
+                source: HDROP,
+                target: "text/uri-list",
+                convert: format_conversion::file_paths_to_uri_list,
+            },
+            FormatConversion {
+                source: "text/uri-list",
+                target: HDROP,
+                convert: format_conversion::uri_list_to_file_paths,
+            },
+            // Prefer DIBV5 with alpha channel.
+            FormatConversion {
+                source: DIBV5,
+                target: "PNG",
+                convert: image_conversion::dib_to_png,
+            },
+            FormatConversion {
+                source: DIB,
+                target: "PNG",
+                convert: image_conversion::dib_to_png,
+            },
+        ]
+    }
+
     pub fn item_format_is_synthesized(
         &self,
         _item: i64,
-        format: &str,
+        _format: &str,
     ) -> NativeExtensionsResult<bool> {
-        Ok(format == "PNG" && self.need_to_synthesize_png()?)
+        Ok(false)
     }
 
     pub async fn can_copy_virtual_file_for_item(
@@ -218,45 +240,6 @@ impl PlatformDataReader {
         Ok(None)
     }
 
-    async fn generate_png(&self) -> NativeExtensionsResult<Vec<u8>> {
-        let formats = self.data_object_formats()?;
-        // prefer DIBV5 with alpha channel
-        let data = if formats.contains(&(CF_DIBV5.0 as u32)) {
-            Ok(self.data_object.get_data(CF_DIBV5.0 as u32)?)
-        } else if formats.contains(&(CF_DIB.0 as u32)) {
-            Ok(self.data_object.get_data(CF_DIB.0 as u32)?)
-        } else {
-            Err(NativeExtensionsError::OtherError(
-                "No DIB or DIBV5 data found in data object".into(),
-            ))
-        }?;
-        let mut bmp = Vec::<u8>::new();
-        bmp.extend_from_slice(&[0x42, 0x4D]); // BM
-        bmp.extend_from_slice(&((data.len() + 14) as u32).to_le_bytes()); // File size
-        bmp.extend_from_slice(&[0, 0]); // reserved 1
-        bmp.extend_from_slice(&[0, 0]); // reserved 2
-        bmp.extend_from_slice(&[0, 0, 0, 0]); // data starting address; not required by decoder
-        bmp.extend_from_slice(&data);
-
-        let (future, completer) = FutureCompleter::new();
-
-        let mut completer = Capsule::new(completer);
-        let sender = RunLoop::current().new_sender();
-
-        // Do the actual encoding on worker thread
-        thread::spawn(move || {
-            let stream = unsafe { SHCreateMemStream(Some(&bmp)) };
-            let stream = stream.unwrap();
-            let res = convert_to_png(stream).map_err(NativeExtensionsError::from);
-            sender.send(move || {
-                let completer = completer.take().unwrap();
-                completer.complete(res);
-            });
-        });
-
-        future.await
-    }
-
     pub async fn get_data_for_item(
         &self,
         item: i64,
@@ -264,7 +247,6 @@ impl PlatformDataReader {
         _progress: Option<Arc<ReadProgress>>,
     ) -> NativeExtensionsResult<Value> {
         let format = format_from_string(&data_type);
-        let png = unsafe { RegisterClipboardFormatW(w!("PNG")) };
         if format == CF_HDROP.0 as u32 {
             let hdrop = self.hdrop_for_item(item)?;
             if let Some(hdrop) = hdrop {
@@ -272,9 +254,6 @@ impl PlatformDataReader {
             } else {
                 Ok(Value::Null)
             }
-        } else if format == png && self.need_to_synthesize_png()? {
-            let png_data = self.generate_png().await?;
-            Ok(png_data.into())
         } else {
             let formats = self.data_object_formats()?;
             if formats.contains(&format) {
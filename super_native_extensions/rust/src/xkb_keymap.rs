@@ -0,0 +1,1612 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use crate::error::{NativeExtensionsError, NativeExtensionsResult};
+
+/// Keymap loaded from XKB text format, either compiled keymap (as printed by
+/// `xkbcomp` or `xkbcli compile-keymap`) or keycodes and symbols files from
+/// XKB data directory with includes resolved. Only keycodes and symbols are
+/// interpreted; levels are taken in order they are listed for each group,
+/// which matches level layout of the standard key types.
+pub struct XkbKeymap {
+    /// Symbols for each keycode, indexed by group and level.
+    keys: HashMap<u32, Vec<Vec<Option<Symbol>>>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Symbol {
+    Char(u32),
+    /// Dead key with its combining character.
+    Dead(u32),
+}
+
+/// Returns content of XKB file for given section kind and file name.
+pub type XkbIncludeResolver<'a> = dyn Fn(XkbSectionKind, &str) -> Option<String> + 'a;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum XkbSectionKind {
+    Keycodes,
+    Symbols,
+    Other,
+}
+
+impl XkbKeymap {
+    pub fn parse_with_resolver(
+        text: &str,
+        resolver: &XkbIncludeResolver,
+    ) -> NativeExtensionsResult<Self> {
+        let sections = Parser::new(tokenize(text)?).parse_file()?;
+        let mut builder = Builder::new(resolver);
+        for section in &sections {
+            builder.apply_section(section, Merge::Override, 0, 0)?;
+        }
+        Ok(builder.finish())
+    }
+
+    /// Loads keymap for comma separated layouts and variants (i.e. "us,de"
+    /// and ",nodeadkeys") from XKB data directory. Each layout becomes one
+    /// group.
+    pub fn from_system(layouts: &str, variants: &str) -> NativeExtensionsResult<Self> {
+        let root = std::env::var_os("XKB_CONFIG_ROOT")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from("/usr/share/X11/xkb"));
+        let resolver = move |kind: XkbSectionKind, file: &str| {
+            let dir = match kind {
+                XkbSectionKind::Keycodes => "keycodes",
+                XkbSectionKind::Symbols => "symbols",
+                XkbSectionKind::Other => return None,
+            };
+            std::fs::read_to_string(root.join(dir).join(file)).ok()
+        };
+        let mut variants = variants.split(',');
+        let symbols: Vec<String> = layouts
+            .split(',')
+            .enumerate()
+            .map(|(index, layout)| {
+                let mut part = layout.trim().to_owned();
+                match variants.next().map(|v| v.trim()) {
+                    Some(variant) if !variant.is_empty() => {
+                        part.push_str(&format!("({variant})"));
+                    }
+                    _ => {}
+                }
+                if index > 0 {
+                    part.push_str(&format!(":{}", index + 1));
+                }
+                part
+            })
+            .collect();
+        let keymap = format!(
+            "xkb_keymap {{ \
+                xkb_keycodes {{ include \"evdev+aliases(qwerty)\" }}; \
+                xkb_symbols {{ include \"{}\" }}; \
+            }};",
+            symbols.join("+")
+        );
+        Self::parse_with_resolver(&keymap, &resolver)
+    }
+
+    /// Loads keymap for layout configured through `XKB_DEFAULT_LAYOUT` or
+    /// in `/etc/default/keyboard`.
+    pub fn from_configured_layout() -> Option<Self> {
+        let (layouts, variants) = match std::env::var("XKB_DEFAULT_LAYOUT") {
+            Ok(layouts) if !layouts.is_empty() => (
+                layouts,
+                std::env::var("XKB_DEFAULT_VARIANT").unwrap_or_default(),
+            ),
+            _ => {
+                let config = std::fs::read_to_string("/etc/default/keyboard").ok()?;
+                parse_keyboard_config(&config)?
+            }
+        };
+        Self::from_system(&layouts, &variants).ok()
+    }
+
+    pub fn group_count(&self) -> usize {
+        self.keys.values().map(|g| g.len()).max().unwrap_or(0)
+    }
+
+    fn symbol(&self, keycode: u32, group: u8, level: i32) -> Option<Symbol> {
+        let groups = self.keys.get(&keycode)?;
+        if groups.is_empty() {
+            return None;
+        }
+        // Keys with fewer groups wrap around (XKB default).
+        let levels = &groups[group as usize % groups.len()];
+        *levels.get(level as usize)?
+    }
+
+    /// Returns printable character produced by the key at given group and
+    /// level.
+    pub fn character(&self, keycode: u32, group: u8, level: i32) -> Option<u32> {
+        match self.symbol(keycode, group, level)? {
+            Symbol::Char(c) if c >= 0x20 => Some(c),
+            _ => None,
+        }
+    }
+
+    /// Returns combining character if the key is a dead key at given level.
+    pub fn dead_key(&self, keycode: u32, group: u8, level: i32) -> Option<u32> {
+        match self.symbol(keycode, group, level)? {
+            Symbol::Dead(c) => Some(c),
+            _ => None,
+        }
+    }
+}
+
+/// Returns layouts and variants from `/etc/default/keyboard` style
+/// configuration.
+pub fn parse_keyboard_config(config: &str) -> Option<(String, String)> {
+    let mut layouts = None;
+    let mut variants = String::new();
+    for line in config.lines() {
+        let line = line.trim();
+        if let Some((key, value)) = line.split_once('=') {
+            let value = value.trim().trim_matches('"').to_owned();
+            match key.trim() {
+                "XKBLAYOUT" => layouts = Some(value),
+                "XKBVARIANT" => variants = value,
+                _ => {}
+            }
+        }
+    }
+    layouts.filter(|l| !l.is_empty()).map(|l| (l, variants))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Str(String),
+    KeyName(String),
+    Punct(char),
+}
+
+fn parse_error(message: &str) -> NativeExtensionsError {
+    NativeExtensionsError::OtherError(format!("Invalid XKB keymap: {message}"))
+}
+
+fn tokenize(text: &str) -> NativeExtensionsResult<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {}
+            '#' => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'/') => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut last = ' ';
+                for c in chars.by_ref() {
+                    if last == '*' && c == '/' {
+                        break;
+                    }
+                    last = c;
+                }
+            }
+            '"' => {
+                let mut s = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => {
+                            if let Some(c) = chars.next() {
+                                s.push(c);
+                            }
+                        }
+                        Some(c) => s.push(c),
+                        None => return Err(parse_error("unterminated string")),
+                    }
+                }
+                tokens.push(Token::Str(s));
+            }
+            '<' => {
+                let mut s = String::new();
+                loop {
+                    match chars.next() {
+                        Some('>') => break,
+                        Some(c) => s.push(c),
+                        None => return Err(parse_error("unterminated key name")),
+                    }
+                }
+                tokens.push(Token::KeyName(s));
+            }
+            c if c.is_alphanumeric() || c == '_' => {
+                let mut s = String::from(c);
+                while let Some(c) = chars.peek() {
+                    if c.is_alphanumeric() || *c == '_' {
+                        s.push(*c);
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                tokens.push(Token::Ident(s));
+            }
+            c => tokens.push(Token::Punct(c)),
+        }
+    }
+    Ok(tokens)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Merge {
+    Override,
+    Augment,
+}
+
+impl Merge {
+    fn from_keyword(keyword: &str) -> Option<Self> {
+        match keyword {
+            "include" | "override" | "replace" => Some(Merge::Override),
+            "augment" => Some(Merge::Augment),
+            _ => None,
+        }
+    }
+}
+
+enum Statement {
+    Include {
+        merge: Merge,
+        spec: String,
+    },
+    Keycode {
+        name: String,
+        code: u32,
+    },
+    Alias {
+        alias: String,
+        name: String,
+    },
+    Key {
+        merge: Merge,
+        name: String,
+        groups: Vec<KeyGroup>,
+    },
+}
+
+/// Explicit group index (if specified) and levels.
+type KeyGroup = (Option<usize>, Vec<Option<Symbol>>);
+
+struct Section {
+    kind: XkbSectionKind,
+    name: Option<String>,
+    is_default: bool,
+    statements: Vec<Statement>,
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn new(tokens: Vec<Token>) -> Self {
+        Self { tokens, pos: 0 }
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> NativeExtensionsResult<Token> {
+        let token = self
+            .tokens
+            .get(self.pos)
+            .cloned()
+            .ok_or_else(|| parse_error("unexpected end of file"))?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn is_punct(&self, c: char) -> bool {
+        self.peek() == Some(&Token::Punct(c))
+    }
+
+    fn skip_punct(&mut self, c: char) -> bool {
+        let res = self.is_punct(c);
+        if res {
+            self.pos += 1;
+        }
+        res
+    }
+
+    fn expect_punct(&mut self, c: char) -> NativeExtensionsResult<()> {
+        if self.skip_punct(c) {
+            Ok(())
+        } else {
+            Err(parse_error(&format!("expected '{c}'")))
+        }
+    }
+
+    /// Skips tokens until the end of current statement (`;` or `,` outside
+    /// of brackets, or closing brace of enclosing block which is not
+    /// consumed).
+    fn skip_statement(&mut self, separators: &[char]) -> NativeExtensionsResult<()> {
+        let mut depth = 0;
+        loop {
+            match self.peek() {
+                None => return Ok(()),
+                Some(Token::Punct(c)) => {
+                    let c = *c;
+                    if depth == 0 && (c == '}' || c == ']') {
+                        return Ok(());
+                    }
+                    self.pos += 1;
+                    match c {
+                        '{' | '[' | '(' => depth += 1,
+                        '}' | ']' | ')' => depth -= 1,
+                        c if depth == 0 && separators.contains(&c) => return Ok(()),
+                        _ => {}
+                    }
+                }
+                Some(_) => self.pos += 1,
+            }
+        }
+    }
+
+    fn parse_file(&mut self) -> NativeExtensionsResult<Vec<Section>> {
+        let mut sections = Vec::new();
+        let mut is_default = false;
+        while let Some(token) = self.peek().cloned() {
+            match token {
+                Token::Ident(ident) => {
+                    self.pos += 1;
+                    match ident.as_str() {
+                        "xkb_keymap" | "xkb_semantics" | "xkb_layout" => {
+                            if let Some(Token::Str(_)) = self.peek() {
+                                self.pos += 1;
+                            }
+                            self.expect_punct('{')?;
+                            let nested = self.parse_file()?;
+                            self.expect_punct('}')?;
+                            self.skip_punct(';');
+                            sections.extend(nested);
+                        }
+                        "default" => is_default = true,
+                        ident if ident.starts_with("xkb_") => {
+                            let kind = match ident {
+                                "xkb_keycodes" => XkbSectionKind::Keycodes,
+                                "xkb_symbols" => XkbSectionKind::Symbols,
+                                _ => XkbSectionKind::Other,
+                            };
+                            let name = match self.peek() {
+                                Some(Token::Str(name)) => {
+                                    let name = name.clone();
+                                    self.pos += 1;
+                                    Some(name)
+                                }
+                                _ => None,
+                            };
+                            self.expect_punct('{')?;
+                            let statements = self.parse_section_body(kind)?;
+                            self.expect_punct('}')?;
+                            self.skip_punct(';');
+                            sections.push(Section {
+                                kind,
+                                name,
+                                is_default,
+                                statements,
+                            });
+                            is_default = false;
+                        }
+                        // Flags such as "partial" or "alphanumeric_keys".
+                        _ => {}
+                    }
+                }
+                Token::Punct('}') => break,
+                _ => {
+                    self.pos += 1;
+                }
+            }
+        }
+        Ok(sections)
+    }
+
+    fn parse_section_body(
+        &mut self,
+        kind: XkbSectionKind,
+    ) -> NativeExtensionsResult<Vec<Statement>> {
+        let mut statements = Vec::new();
+        loop {
+            match self.peek().cloned() {
+                None => return Err(parse_error("unexpected end of section")),
+                Some(Token::Punct('}')) => return Ok(statements),
+                Some(Token::Ident(ident)) if kind != XkbSectionKind::Other => {
+                    if let Some(statement) = self.parse_statement(kind, &ident)? {
+                        statements.push(statement);
+                    }
+                }
+                Some(Token::KeyName(name)) if kind == XkbSectionKind::Keycodes => {
+                    self.pos += 1;
+                    self.expect_punct('=')?;
+                    let code = match self.next()? {
+                        Token::Ident(code) => code.parse().ok(),
+                        _ => None,
+                    };
+                    if let Some(code) = code {
+                        statements.push(Statement::Keycode { name, code });
+                    }
+                    self.skip_statement(&[';'])?;
+                }
+                Some(_) => {
+                    self.pos += 1;
+                    self.skip_statement(&[';'])?;
+                }
+            }
+        }
+    }
+
+    fn parse_statement(
+        &mut self,
+        kind: XkbSectionKind,
+        ident: &str,
+    ) -> NativeExtensionsResult<Option<Statement>> {
+        self.pos += 1;
+        let mut merge = Merge::Override;
+        if let Some(m) = Merge::from_keyword(ident) {
+            merge = m;
+            match self.peek().cloned() {
+                Some(Token::Str(spec)) => {
+                    self.pos += 1;
+                    self.skip_punct(';');
+                    return Ok(Some(Statement::Include { merge, spec }));
+                }
+                Some(Token::Ident(ident)) => {
+                    // i.e. "override key <AE01> ..."
+                    self.pos += 1;
+                    return self.parse_merged_statement(kind, merge, &ident);
+                }
+                _ => {}
+            }
+        }
+        self.parse_merged_statement(kind, merge, ident)
+    }
+
+    fn parse_merged_statement(
+        &mut self,
+        kind: XkbSectionKind,
+        merge: Merge,
+        ident: &str,
+    ) -> NativeExtensionsResult<Option<Statement>> {
+        match (kind, ident) {
+            (XkbSectionKind::Keycodes, "alias") => {
+                let alias = self.next()?;
+                self.expect_punct('=')?;
+                let name = self.next()?;
+                self.skip_statement(&[';'])?;
+                match (alias, name) {
+                    (Token::KeyName(alias), Token::KeyName(name)) => {
+                        Ok(Some(Statement::Alias { alias, name }))
+                    }
+                    _ => Err(parse_error("invalid alias")),
+                }
+            }
+            (XkbSectionKind::Symbols, "key") if matches!(self.peek(), Some(Token::KeyName(_))) => {
+                let name = match self.next()? {
+                    Token::KeyName(name) => name,
+                    _ => unreachable!(),
+                };
+                self.expect_punct('{')?;
+                let groups = self.parse_key_body()?;
+                self.expect_punct('}')?;
+                self.skip_punct(';');
+                Ok(Some(Statement::Key {
+                    merge,
+                    name,
+                    groups,
+                }))
+            }
+            _ => {
+                self.skip_statement(&[';'])?;
+                Ok(None)
+            }
+        }
+    }
+
+    fn parse_key_body(&mut self) -> NativeExtensionsResult<Vec<KeyGroup>> {
+        let mut groups = Vec::new();
+        loop {
+            match self.peek().cloned() {
+                None => return Err(parse_error("unexpected end of key")),
+                Some(Token::Punct('}')) => return Ok(groups),
+                Some(Token::Punct(',')) => {
+                    self.pos += 1;
+                }
+                Some(Token::Punct('[')) => {
+                    self.pos += 1;
+                    if groups.len() >= MAX_GROUPS {
+                        return Err(parse_error("too many groups"));
+                    }
+                    groups.push((None, self.parse_levels()?));
+                }
+                Some(Token::Ident(ident)) if ident == "symbols" => {
+                    self.pos += 1;
+                    let mut group = None;
+                    if self.skip_punct('[') {
+                        if let Token::Ident(spec) = self.next()? {
+                            group = parse_group_index(&spec)?;
+                        }
+                        self.expect_punct(']')?;
+                    }
+                    self.expect_punct('=')?;
+                    self.expect_punct('[')?;
+                    groups.push((group, self.parse_levels()?));
+                }
+                Some(_) => {
+                    // type, actions, virtualMods, repeat, ...
+                    self.pos += 1;
+                    self.skip_statement(&[','])?;
+                }
+            }
+        }
+    }
+
+    /// Parses keysyms after opening bracket up to and including closing
+    /// bracket.
+    fn parse_levels(&mut self) -> NativeExtensionsResult<Vec<Option<Symbol>>> {
+        let mut levels = Vec::new();
+        loop {
+            match self.next()? {
+                Token::Punct(']') => return Ok(levels),
+                Token::Punct(',') => {}
+                Token::Ident(name) => levels.push(keysym_to_symbol(&name)),
+                Token::Punct('{') => {
+                    // Multiple keysyms for single level do not produce
+                    // single character.
+                    let mut count = 0;
+                    let mut first = None;
+                    loop {
+                        match self.next()? {
+                            Token::Punct('}') => break,
+                            Token::Ident(name) => {
+                                count += 1;
+                                first = keysym_to_symbol(&name);
+                            }
+                            _ => {}
+                        }
+                    }
+                    levels.push(if count == 1 { first } else { None });
+                }
+                _ => return Err(parse_error("invalid keysym")),
+            }
+        }
+    }
+}
+
+/// Parses "Group2", "group2" or "2" into zero based group index.
+/// XKB supports at most four groups.
+const MAX_GROUPS: usize = 4;
+
+/// Zero based index from group name such as "Group2" or "2".
+fn parse_group_index(spec: &str) -> NativeExtensionsResult<Option<usize>> {
+    let digits = spec.trim_start_matches(|c: char| c.is_alphabetic());
+    let index = digits.parse::<usize>().ok().and_then(|i| i.checked_sub(1));
+    match index {
+        Some(index) if index >= MAX_GROUPS => Err(parse_error(&format!("invalid group {spec}"))),
+        index => Ok(index),
+    }
+}
+
+struct Builder<'a> {
+    resolver: &'a XkbIncludeResolver<'a>,
+    keycodes: HashMap<String, u32>,
+    aliases: HashMap<String, String>,
+    symbols: HashMap<String, Vec<Vec<Option<Symbol>>>>,
+}
+
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+impl<'a> Builder<'a> {
+    fn new(resolver: &'a XkbIncludeResolver<'a>) -> Self {
+        Self {
+            resolver,
+            keycodes: HashMap::new(),
+            aliases: HashMap::new(),
+            symbols: HashMap::new(),
+        }
+    }
+
+    fn apply_section(
+        &mut self,
+        section: &Section,
+        merge: Merge,
+        group_offset: usize,
+        depth: usize,
+    ) -> NativeExtensionsResult<()> {
+        if depth > MAX_INCLUDE_DEPTH {
+            return Err(parse_error("include depth exceeded"));
+        }
+        for statement in &section.statements {
+            match statement {
+                Statement::Include {
+                    merge: include_merge,
+                    spec,
+                } => {
+                    let include_merge = if merge == Merge::Augment {
+                        Merge::Augment
+                    } else {
+                        *include_merge
+                    };
+                    self.apply_include(section.kind, spec, include_merge, group_offset, depth)?;
+                }
+                Statement::Keycode { name, code } => {
+                    if merge == Merge::Override || !self.keycodes.contains_key(name) {
+                        self.keycodes.insert(name.clone(), *code);
+                    }
+                }
+                Statement::Alias { alias, name } => {
+                    if merge == Merge::Override || !self.aliases.contains_key(alias) {
+                        self.aliases.insert(alias.clone(), name.clone());
+                    }
+                }
+                Statement::Key {
+                    merge: key_merge,
+                    name,
+                    groups,
+                } => {
+                    let key_merge = if merge == Merge::Augment {
+                        Merge::Augment
+                    } else {
+                        *key_merge
+                    };
+                    let key = self.symbols.entry(name.clone()).or_default();
+                    for (index, (group, levels)) in groups.iter().enumerate() {
+                        let group = group.unwrap_or(index) + group_offset;
+                        // Groups shifted past the limit by include are dropped.
+                        if group >= MAX_GROUPS {
+                            continue;
+                        }
+                        if key.len() <= group {
+                            key.resize(group + 1, Vec::new());
+                        }
+                        let current = &mut key[group];
+                        if key_merge == Merge::Override {
+                            *current = levels.clone();
+                            continue;
+                        }
+                        if current.len() < levels.len() {
+                            current.resize(levels.len(), None);
+                        }
+                        for (level, keysym) in levels.iter().enumerate() {
+                            if current[level].is_none() {
+                                current[level] = *keysym;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Applies include specification such as "pc+us+de(nodeadkeys):2".
+    fn apply_include(
+        &mut self,
+        kind: XkbSectionKind,
+        spec: &str,
+        merge: Merge,
+        group_offset: usize,
+        depth: usize,
+    ) -> NativeExtensionsResult<()> {
+        let mut part_merge = merge;
+        let mut rest = spec;
+        while !rest.is_empty() {
+            let end = rest.find(['+', '|']).unwrap_or(rest.len());
+            let part = &rest[..end];
+            let (part, group) = match part.split_once(':') {
+                Some((part, group)) => (part, parse_group_index(group)?),
+                None => (part, None),
+            };
+            let (file, name) = match part.split_once('(') {
+                Some((file, name)) => (file, Some(name.trim_end_matches(')'))),
+                None => (part, None),
+            };
+            let text = (self.resolver)(kind, file)
+                .ok_or_else(|| parse_error(&format!("missing include {file}")))?;
+            let sections = Parser::new(tokenize(&text)?).parse_file()?;
+            let section = sections
+                .iter()
+                .filter(|s| s.kind == kind)
+                .find(|s| match name {
+                    Some(name) => s.name.as_deref() == Some(name),
+                    None => s.is_default,
+                })
+                .or_else(|| {
+                    sections
+                        .iter()
+                        .find(|s| s.kind == kind)
+                        .filter(|_| name.is_none())
+                })
+                .ok_or_else(|| parse_error(&format!("missing section {part}")))?;
+            self.apply_section(
+                section,
+                part_merge,
+                group_offset + group.unwrap_or(0),
+                depth + 1,
+            )?;
+            if end == rest.len() {
+                break;
+            }
+            part_merge = if rest[end..].starts_with('|') {
+                Merge::Augment
+            } else {
+                Merge::Override
+            };
+            rest = &rest[end + 1..];
+        }
+        Ok(())
+    }
+
+    fn finish(self) -> XkbKeymap {
+        let mut keys = HashMap::new();
+        for (name, groups) in self.symbols {
+            let name = self.aliases.get(&name).unwrap_or(&name);
+            if let Some(code) = self.keycodes.get(name) {
+                keys.insert(*code, groups);
+            }
+        }
+        XkbKeymap { keys }
+    }
+}
+
+/// Latin-1 keysym names starting at U+00A0. Keysym value of these matches
+/// the code point.
+const LATIN1_NAMES: &[&str] = &[
+    "nobreakspace",
+    "exclamdown",
+    "cent",
+    "sterling",
+    "currency",
+    "yen",
+    "brokenbar",
+    "section",
+    "diaeresis",
+    "copyright",
+    "ordfeminine",
+    "guillemotleft",
+    "notsign",
+    "hyphen",
+    "registered",
+    "macron",
+    "degree",
+    "plusminus",
+    "twosuperior",
+    "threesuperior",
+    "acute",
+    "mu",
+    "paragraph",
+    "periodcentered",
+    "cedilla",
+    "onesuperior",
+    "masculine",
+    "guillemotright",
+    "onequarter",
+    "onehalf",
+    "threequarters",
+    "questiondown",
+    "Agrave",
+    "Aacute",
+    "Acircumflex",
+    "Atilde",
+    "Adiaeresis",
+    "Aring",
+    "AE",
+    "Ccedilla",
+    "Egrave",
+    "Eacute",
+    "Ecircumflex",
+    "Ediaeresis",
+    "Igrave",
+    "Iacute",
+    "Icircumflex",
+    "Idiaeresis",
+    "ETH",
+    "Ntilde",
+    "Ograve",
+    "Oacute",
+    "Ocircumflex",
+    "Otilde",
+    "Odiaeresis",
+    "multiply",
+    "Oslash",
+    "Ugrave",
+    "Uacute",
+    "Ucircumflex",
+    "Udiaeresis",
+    "Yacute",
+    "THORN",
+    "ssharp",
+    "agrave",
+    "aacute",
+    "acircumflex",
+    "atilde",
+    "adiaeresis",
+    "aring",
+    "ae",
+    "ccedilla",
+    "egrave",
+    "eacute",
+    "ecircumflex",
+    "ediaeresis",
+    "igrave",
+    "iacute",
+    "icircumflex",
+    "idiaeresis",
+    "eth",
+    "ntilde",
+    "ograve",
+    "oacute",
+    "ocircumflex",
+    "otilde",
+    "odiaeresis",
+    "division",
+    "oslash",
+    "ugrave",
+    "uacute",
+    "ucircumflex",
+    "udiaeresis",
+    "yacute",
+    "thorn",
+    "ydiaeresis",
+];
+
+/// Keysym names for printable ASCII characters other than letters and
+/// digits.
+const ASCII_NAMES: &[(&str, char)] = &[
+    ("space", ' '),
+    ("exclam", '!'),
+    ("quotedbl", '"'),
+    ("numbersign", '#'),
+    ("dollar", '$'),
+    ("percent", '%'),
+    ("ampersand", '&'),
+    ("apostrophe", '\''),
+    ("parenleft", '('),
+    ("parenright", ')'),
+    ("asterisk", '*'),
+    ("plus", '+'),
+    ("comma", ','),
+    ("minus", '-'),
+    ("period", '.'),
+    ("slash", '/'),
+    ("colon", ':'),
+    ("semicolon", ';'),
+    ("less", '<'),
+    ("equal", '='),
+    ("greater", '>'),
+    ("question", '?'),
+    ("at", '@'),
+    ("bracketleft", '['),
+    ("backslash", '\\'),
+    ("bracketright", ']'),
+    ("asciicircum", '^'),
+    ("underscore", '_'),
+    ("grave", '`'),
+    ("braceleft", '{'),
+    ("bar", '|'),
+    ("braceright", '}'),
+    ("asciitilde", '~'),
+];
+
+/// Other commonly used keysym names.
+const OTHER_NAMES: &[(&str, u32)] = &[
+    // Aliases
+    ("quoteright", 0x27),
+    ("quoteleft", 0x60),
+    ("guillemetleft", 0xab),
+    ("guillemetright", 0xbb),
+    ("ordmasculine", 0xba),
+    ("Eth", 0xd0),
+    ("Ooblique", 0xd8),
+    ("Thorn", 0xde),
+    ("ooblique", 0xf8),
+    // Latin 2
+    ("Aogonek", 0x104),
+    ("breve", 0x2d8),
+    ("Lstroke", 0x141),
+    ("Lcaron", 0x13d),
+    ("Sacute", 0x15a),
+    ("Scaron", 0x160),
+    ("Scedilla", 0x15e),
+    ("Tcaron", 0x164),
+    ("Zacute", 0x179),
+    ("Zcaron", 0x17d),
+    ("Zabovedot", 0x17b),
+    ("aogonek", 0x105),
+    ("ogonek", 0x2db),
+    ("lstroke", 0x142),
+    ("lcaron", 0x13e),
+    ("sacute", 0x15b),
+    ("caron", 0x2c7),
+    ("scaron", 0x161),
+    ("scedilla", 0x15f),
+    ("tcaron", 0x165),
+    ("zacute", 0x17a),
+    ("doubleacute", 0x2dd),
+    ("zcaron", 0x17e),
+    ("zabovedot", 0x17c),
+    ("Racute", 0x154),
+    ("Abreve", 0x102),
+    ("Lacute", 0x139),
+    ("Cacute", 0x106),
+    ("Ccaron", 0x10c),
+    ("Eogonek", 0x118),
+    ("Ecaron", 0x11a),
+    ("Dcaron", 0x10e),
+    ("Dstroke", 0x110),
+    ("Nacute", 0x143),
+    ("Ncaron", 0x147),
+    ("Odoubleacute", 0x150),
+    ("Rcaron", 0x158),
+    ("Uring", 0x16e),
+    ("Udoubleacute", 0x170),
+    ("Tcedilla", 0x162),
+    ("racute", 0x155),
+    ("abreve", 0x103),
+    ("lacute", 0x13a),
+    ("cacute", 0x107),
+    ("ccaron", 0x10d),
+    ("eogonek", 0x119),
+    ("ecaron", 0x11b),
+    ("dcaron", 0x10f),
+    ("dstroke", 0x111),
+    ("nacute", 0x144),
+    ("ncaron", 0x148),
+    ("odoubleacute", 0x151),
+    ("rcaron", 0x159),
+    ("uring", 0x16f),
+    ("udoubleacute", 0x171),
+    ("tcedilla", 0x163),
+    ("abovedot", 0x2d9),
+    // Latin 3
+    ("Gbreve", 0x11e),
+    ("gbreve", 0x11f),
+    ("Iabovedot", 0x130),
+    ("idotless", 0x131),
+    // Latin 9
+    ("OE", 0x152),
+    ("oe", 0x153),
+    ("Ydiaeresis", 0x178),
+    // Greek
+    ("Greek_OMEGA", 0x3a9),
+    ("Greek_mu", 0x3bc),
+    ("Greek_pi", 0x3c0),
+    // Publishing and technical
+    ("EuroSign", 0x20ac),
+    ("oneeighth", 0x215b),
+    ("threeeighths", 0x215c),
+    ("fiveeighths", 0x215d),
+    ("seveneighths", 0x215e),
+    ("trademark", 0x2122),
+    ("leftarrow", 0x2190),
+    ("uparrow", 0x2191),
+    ("rightarrow", 0x2192),
+    ("downarrow", 0x2193),
+    ("ellipsis", 0x2026),
+    ("endash", 0x2013),
+    ("emdash", 0x2014),
+    ("leftsinglequotemark", 0x2018),
+    ("rightsinglequotemark", 0x2019),
+    ("singlelowquotemark", 0x201a),
+    ("leftdoublequotemark", 0x201c),
+    ("rightdoublequotemark", 0x201d),
+    ("doublelowquotemark", 0x201e),
+];
+
+/// Lowercase Cyrillic keysym names. Uppercase variants use capitalized
+/// suffix.
+const CYRILLIC_NAMES: &[(&str, u32)] = &[
+    ("a", 0x430),
+    ("be", 0x431),
+    ("ve", 0x432),
+    ("ghe", 0x433),
+    ("de", 0x434),
+    ("ie", 0x435),
+    ("io", 0x451),
+    ("zhe", 0x436),
+    ("ze", 0x437),
+    ("i", 0x438),
+    ("shorti", 0x439),
+    ("ka", 0x43a),
+    ("el", 0x43b),
+    ("em", 0x43c),
+    ("en", 0x43d),
+    ("o", 0x43e),
+    ("pe", 0x43f),
+    ("er", 0x440),
+    ("es", 0x441),
+    ("te", 0x442),
+    ("u", 0x443),
+    ("ef", 0x444),
+    ("ha", 0x445),
+    ("tse", 0x446),
+    ("che", 0x447),
+    ("sha", 0x448),
+    ("shcha", 0x449),
+    ("hardsign", 0x44a),
+    ("yeru", 0x44b),
+    ("softsign", 0x44c),
+    ("e", 0x44d),
+    ("yu", 0x44e),
+    ("ya", 0x44f),
+];
+
+/// Dead keysyms with their values and combining characters.
+const DEAD_KEYS: &[(&str, u32, u32)] = &[
+    ("dead_grave", 0xfe50, 0x300),
+    ("dead_acute", 0xfe51, 0x301),
+    ("dead_circumflex", 0xfe52, 0x302),
+    ("dead_tilde", 0xfe53, 0x303),
+    ("dead_macron", 0xfe54, 0x304),
+    ("dead_breve", 0xfe55, 0x306),
+    ("dead_abovedot", 0xfe56, 0x307),
+    ("dead_diaeresis", 0xfe57, 0x308),
+    ("dead_abovering", 0xfe58, 0x30a),
+    ("dead_doubleacute", 0xfe59, 0x30b),
+    ("dead_caron", 0xfe5a, 0x30c),
+    ("dead_cedilla", 0xfe5b, 0x327),
+    ("dead_ogonek", 0xfe5c, 0x328),
+    ("dead_iota", 0xfe5d, 0x345),
+    ("dead_voiced_sound", 0xfe5e, 0x3099),
+    ("dead_semivoiced_sound", 0xfe5f, 0x309a),
+    ("dead_belowdot", 0xfe60, 0x323),
+    ("dead_hook", 0xfe61, 0x309),
+    ("dead_horn", 0xfe62, 0x31b),
+    ("dead_stroke", 0xfe63, 0x338),
+    ("dead_abovecomma", 0xfe64, 0x313),
+    ("dead_abovereversedcomma", 0xfe65, 0x314),
+    ("dead_doublegrave", 0xfe66, 0x30f),
+    ("dead_belowring", 0xfe67, 0x325),
+    ("dead_belowmacron", 0xfe68, 0x331),
+    ("dead_belowcircumflex", 0xfe69, 0x32d),
+    ("dead_belowtilde", 0xfe6a, 0x330),
+    ("dead_belowbreve", 0xfe6b, 0x32e),
+    ("dead_belowdiaeresis", 0xfe6c, 0x324),
+    ("dead_invertedbreve", 0xfe6d, 0x311),
+    ("dead_belowcomma", 0xfe6e, 0x326),
+];
+
+/// Returns combining character for dead keysym.
+pub fn dead_key_combining_char(keysym: u32) -> Option<u32> {
+    DEAD_KEYS
+        .iter()
+        .find(|(_, k, _)| *k == keysym)
+        .map(|(_, _, c)| *c)
+}
+
+fn keysym_to_symbol(name: &str) -> Option<Symbol> {
+    let dead_key = match name.strip_prefix("0x") {
+        Some(hex) => dead_key_combining_char(u32::from_str_radix(hex, 16).ok()?),
+        None => DEAD_KEYS
+            .iter()
+            .find(|(n, _, _)| *n == name)
+            .map(|(_, _, c)| *c),
+    };
+    match dead_key {
+        Some(c) => Some(Symbol::Dead(c)),
+        None => keysym_to_unicode(name).map(Symbol::Char),
+    }
+}
+
+/// Returns unicode code point for keysym name. Returns None for keysyms
+/// that don't produce a character (including dead keys) and for names not
+/// known to the parser.
+fn keysym_to_unicode(name: &str) -> Option<u32> {
+    let mut chars = name.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return if c.is_ascii_alphanumeric() {
+            Some(c as u32)
+        } else {
+            None
+        };
+    }
+    if let Some(hex) = name.strip_prefix('U') {
+        if hex.len() >= 4 && hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return u32::from_str_radix(hex, 16).ok();
+        }
+    }
+    if let Some(hex) = name.strip_prefix("0x") {
+        let keysym = u32::from_str_radix(hex, 16).ok()?;
+        return match keysym {
+            0x20..=0x7e | 0xa0..=0xff => Some(keysym),
+            0x01000100..=0x0110ffff => Some(keysym - 0x01000000),
+            _ => None,
+        };
+    }
+    if let Some(suffix) = name.strip_prefix("Cyrillic_") {
+        let lowercase = suffix.to_lowercase();
+        let (_, code) = CYRILLIC_NAMES.iter().find(|(n, _)| *n == lowercase)?;
+        return if suffix == lowercase {
+            Some(*code)
+        } else if *code == 0x451 {
+            Some(0x401)
+        } else {
+            Some(code - 0x20)
+        };
+    }
+    if let Some((_, c)) = ASCII_NAMES.iter().find(|(n, _)| *n == name) {
+        return Some(*c as u32);
+    }
+    if let Some(index) = LATIN1_NAMES.iter().position(|n| *n == name) {
+        return Some(0xa0 + index as u32);
+    }
+    OTHER_NAMES
+        .iter()
+        .find(|(n, _)| *n == name)
+        .map(|(_, code)| *code)
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use super::{keysym_to_unicode, parse_keyboard_config, XkbKeymap, XkbSectionKind};
+
+    const KEYMAP: &str = r#"
+xkb_keymap {
+xkb_keycodes "evdev+aliases(qwerty)" {
+    minimum = 8;
+    maximum = 255;
+    <AE01> = 10;
+    <AE02> = 11;
+    <AD01> = 24;
+    <AD03> = 26;
+    <AC01> = 38;
+    <BKSL> = 51;
+    <SPCE> = 65;
+    <LFSH> = 50;
+    indicator 1 = "Caps Lock";
+    alias <AC12> = <BKSL>;
+};
+xkb_types "complete" {
+    virtual_modifiers NumLock,Alt,LevelThree;
+    type "FOUR_LEVEL" {
+        modifiers= Shift+LevelThree;
+        map[None]= Level1;
+        map[Shift]= Level2;
+        level_name[Level1]= "Base";
+    };
+};
+xkb_compatibility "complete" {
+    interpret Shift_L+AnyOf(all) {
+        action= SetMods(modifiers=Shift,clearLocks);
+    };
+};
+xkb_symbols "pc+us+de:2" {
+    name[group1]="English (US)";
+    name[group2]="German";
+    key <AE01> {
+        type[group2]= "FOUR_LEVEL",
+        symbols[Group1]= [ 1, exclam ],
+        symbols[Group2]= [ 1, exclam, onesuperior, exclamdown ]
+    };
+    key <AE02> { [ 2, at ], [ 2, quotedbl, twosuperior, oneeighth ] };
+    key <AD01> {
+        type= "FOUR_LEVEL_SEMIALPHABETIC",
+        symbols[Group1]= [ q, Q ],
+        symbols[Group2]= [ q, Q, at, Greek_OMEGA ],
+        actions[Group1]= [ NoAction(), NoAction() ]
+    };
+    key <AD03> { [ e, E ], [ e, E, EuroSign, EuroSign ] };
+    key <AC12> { [ backslash, bar ], [ numbersign, apostrophe, rightsinglequotemark, dead_breve ] };
+    key <SPCE> { [ space ] };
+    key <LFSH> { [ Shift_L ] };
+    modifier_map Shift { <LFSH> };
+};
+xkb_geometry "pc(pc105)" {
+    width= 470;
+};
+};
+"#;
+
+    fn chars(keymap: &XkbKeymap, keycode: u32, group: u8) -> Vec<Option<char>> {
+        (0..4)
+            .map(|level| {
+                keymap
+                    .character(keycode, group, level)
+                    .and_then(char::from_u32)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_compiled_keymap() {
+        let keymap = XkbKeymap::parse_with_resolver(KEYMAP, &|_, _| None).unwrap();
+        assert_eq!(keymap.group_count(), 2);
+        assert_eq!(chars(&keymap, 10, 0), [Some('1'), Some('!'), None, None]);
+        assert_eq!(
+            chars(&keymap, 10, 1),
+            [Some('1'), Some('!'), Some('¹'), Some('¡')]
+        );
+        assert_eq!(
+            chars(&keymap, 11, 1),
+            [Some('2'), Some('"'), Some('²'), Some('⅛')]
+        );
+        assert_eq!(
+            chars(&keymap, 24, 1),
+            [Some('q'), Some('Q'), Some('@'), Some('Ω')]
+        );
+        assert_eq!(
+            chars(&keymap, 26, 1),
+            [Some('e'), Some('E'), Some('€'), Some('€')]
+        );
+        // Alias and dead key
+        assert_eq!(chars(&keymap, 51, 0), [Some('\\'), Some('|'), None, None]);
+        assert_eq!(
+            chars(&keymap, 51, 1),
+            [Some('#'), Some('\''), Some('’'), None]
+        );
+        // Single group wraps around
+        assert_eq!(chars(&keymap, 65, 1), [Some(' '), None, None, None]);
+        assert_eq!(chars(&keymap, 50, 0), [None, None, None, None]);
+        assert_eq!(chars(&keymap, 38, 0), [None, None, None, None]);
+    }
+
+    fn resolver(kind: XkbSectionKind, file: &str) -> Option<String> {
+        let files: HashMap<_, _> = [
+            (
+                (XkbSectionKind::Keycodes, "evdev"),
+                r#"
+                default xkb_keycodes "evdev" {
+                    <AE01> = 10; <AE02> = 11; <AD01> = 24; <AD06> = 29;
+                    <AC01> = 38; <AB01> = 52; <AB02> = 53; <BKSL> = 51;
+                };"#,
+            ),
+            (
+                (XkbSectionKind::Keycodes, "aliases"),
+                r#"
+                default xkb_keycodes "qwerty" {
+                    alias <AC12> = <BKSL>;
+                };
+                xkb_keycodes "azerty" {
+                    alias <LatQ> = <AC01>;
+                };"#,
+            ),
+            (
+                (XkbSectionKind::Symbols, "latin"),
+                r#"
+                default partial alphanumeric_keys
+                xkb_symbols "basic" {
+                    key <AE01> { [ 1, exclam, onesuperior, exclamdown ] };
+                    key <AE02> { [ 2, at, twosuperior, oneeighth ] };
+                    key <AD01> { [ q, Q, at, Greek_OMEGA ] };
+                    key <AD06> { [ y, Y, leftarrow, yen ] };
+                    key <AB01> { [ z, Z, guillemotleft, less ] };
+                };
+                partial alphanumeric_keys
+                xkb_symbols "type4" {
+                    include "latin(basic)"
+                    key <AB02> { [ x, X, guillemotright, greater ] };
+                };"#,
+            ),
+            (
+                (XkbSectionKind::Symbols, "us"),
+                r#"
+                default partial alphanumeric_keys
+                xkb_symbols "basic" {
+                    name[Group1]= "English (US)";
+                    key <AE01> { [ 1, exclam ] };
+                    key <AE02> { [ 2, at ] };
+                    key <AD01> { [ q, Q ] };
+                    key <AD06> { [ y, Y ] };
+                    key <AC01> { [ a, A ] };
+                    key <AB01> { [ z, Z ] };
+                    key <BKSL> { [ backslash, bar ] };
+                };"#,
+            ),
+            (
+                (XkbSectionKind::Symbols, "de"),
+                r#"
+                default partial alphanumeric_keys
+                xkb_symbols "basic" {
+                    include "latin(type4)"
+                    name[Group1]="German";
+                    key <AE02> { [ 2, quotedbl, twosuperior, oneeighth ] };
+                    key <AD06> { [ z, Z, leftarrow, yen ] };
+                    key <AB01> { [ y, Y, guillemotright, U203A ] };
+                    key <BKSL> { [ numbersign, apostrophe, rightsinglequotemark ] };
+                    augment key <AC01> { [ x, X, ae, AE ] };
+                };
+                partial alphanumeric_keys
+                xkb_symbols "nodeadkeys" {
+                    include "de(basic)"
+                    key <AD01> { [ q, Q, at, U2126 ] };
+                };"#,
+            ),
+            (
+                (XkbSectionKind::Symbols, "ru"),
+                r#"
+                default partial alphanumeric_keys
+                xkb_symbols "basic" {
+                    key <AE02> { [ 2, quotedbl ] };
+                    key <AD01> { [ Cyrillic_shorti, Cyrillic_SHORTI ] };
+                    key <AC01> { [ Cyrillic_ef, Cyrillic_EF ] };
+                    key <AB01> { [ Cyrillic_ya, Cyrillic_YA ] };
+                };"#,
+            ),
+        ]
+        .into_iter()
+        .collect();
+        files.get(&(kind, file)).map(|f| f.to_string())
+    }
+
+    fn load(symbols: &str) -> XkbKeymap {
+        let keymap = format!(
+            r#"xkb_keymap {{
+                xkb_keycodes {{ include "evdev+aliases(qwerty)" }};
+                xkb_symbols {{ include "{symbols}" }};
+            }};"#
+        );
+        XkbKeymap::parse_with_resolver(&keymap, &resolver).unwrap()
+    }
+
+    #[test]
+    fn test_includes() {
+        let keymap = load("de");
+        assert_eq!(keymap.group_count(), 1);
+        // From latin(basic)
+        assert_eq!(
+            chars(&keymap, 10, 0),
+            [Some('1'), Some('!'), Some('¹'), Some('¡')]
+        );
+        // From latin(type4)
+        assert_eq!(
+            chars(&keymap, 53, 0),
+            [Some('x'), Some('X'), Some('»'), Some('>')]
+        );
+        // Overridden by de(basic)
+        assert_eq!(
+            chars(&keymap, 11, 0),
+            [Some('2'), Some('"'), Some('²'), Some('⅛')]
+        );
+        assert_eq!(
+            chars(&keymap, 52, 0),
+            [Some('y'), Some('Y'), Some('»'), Some('›')]
+        );
+        // Only defined in de(basic)
+        assert_eq!(
+            chars(&keymap, 51, 0),
+            [Some('#'), Some('\''), Some('’'), None]
+        );
+        // Augmented key
+        assert_eq!(
+            chars(&keymap, 38, 0),
+            [Some('x'), Some('X'), Some('æ'), Some('Æ')]
+        );
+
+        let keymap = load("de(nodeadkeys)");
+        assert_eq!(
+            chars(&keymap, 24, 0),
+            [Some('q'), Some('Q'), Some('@'), Some('\u{2126}')]
+        );
+    }
+
+    /// Unmodified files from XKB data directory.
+    fn system_resolver(kind: XkbSectionKind, file: &str) -> Option<String> {
+        let content = match (kind, file) {
+            (XkbSectionKind::Keycodes, "evdev") => {
+                include_str!("../test_data/xkb/keycodes/evdev")
+            }
+            (XkbSectionKind::Keycodes, "aliases") => {
+                include_str!("../test_data/xkb/keycodes/aliases")
+            }
+            (XkbSectionKind::Symbols, "us") => include_str!("../test_data/xkb/symbols/us"),
+            (XkbSectionKind::Symbols, "de") => include_str!("../test_data/xkb/symbols/de"),
+            (XkbSectionKind::Symbols, "latin") => include_str!("../test_data/xkb/symbols/latin"),
+            (XkbSectionKind::Symbols, "kpdl") => include_str!("../test_data/xkb/symbols/kpdl"),
+            (XkbSectionKind::Symbols, "level3") => {
+                include_str!("../test_data/xkb/symbols/level3")
+            }
+            _ => return None,
+        };
+        Some(content.to_owned())
+    }
+
+    fn load_system(symbols: &str) -> XkbKeymap {
+        let keymap = format!(
+            r#"xkb_keymap {{
+                xkb_keycodes {{ include "evdev+aliases(qwerty)" }};
+                xkb_symbols {{ include "{symbols}" }};
+            }};"#
+        );
+        XkbKeymap::parse_with_resolver(&keymap, &system_resolver).unwrap()
+    }
+
+    #[test]
+    fn test_system_files() {
+        let keymap = load_system("us+de:2");
+        assert_eq!(keymap.group_count(), 2);
+        // <AD06>
+        assert_eq!(chars(&keymap, 29, 0), [Some('y'), Some('Y'), None, None]);
+        assert_eq!(
+            chars(&keymap, 29, 1),
+            [Some('z'), Some('Z'), Some('←'), Some('¥')]
+        );
+        // <AE07>, from latin(type4)
+        assert_eq!(chars(&keymap, 16, 0), [Some('7'), Some('&'), None, None]);
+        assert_eq!(
+            chars(&keymap, 16, 1),
+            [Some('7'), Some('/'), Some('{'), Some('⅞')]
+        );
+        // <AE11>, explicit key type with five levels
+        assert_eq!(
+            chars(&keymap, 20, 1),
+            [Some('ß'), Some('?'), Some('\\'), Some('¿')]
+        );
+        // <AD01>, from latin
+        assert_eq!(
+            chars(&keymap, 24, 1),
+            [Some('q'), Some('Q'), Some('@'), Some('Ω')]
+        );
+        // <TLDE> and <AE12> are dead keys in de(basic)
+        assert_eq!(
+            keymap.dead_key(49, 1, 0).and_then(char::from_u32),
+            Some('\u{302}')
+        );
+        assert_eq!(
+            keymap.dead_key(21, 1, 0).and_then(char::from_u32),
+            Some('\u{301}')
+        );
+        assert_eq!(
+            keymap.dead_key(21, 1, 1).and_then(char::from_u32),
+            Some('\u{300}')
+        );
+        assert_eq!(keymap.dead_key(49, 0, 0), None);
+
+        let keymap = load_system("de(nodeadkeys)");
+        assert_eq!(
+            chars(&keymap, 49, 0),
+            [Some('^'), Some('°'), Some('¬'), Some('¬')]
+        );
+        assert_eq!(keymap.dead_key(49, 0, 0), None);
+        // Inherited from de(basic)
+        assert_eq!(
+            chars(&keymap, 29, 0),
+            [Some('z'), Some('Z'), Some('←'), Some('¥')]
+        );
+    }
+
+    #[test]
+    fn test_multiple_groups() {
+        let keymap = load("us+de:2+ru:3");
+        assert_eq!(keymap.group_count(), 3);
+        assert_eq!(chars(&keymap, 29, 0), [Some('y'), Some('Y'), None, None]);
+        assert_eq!(
+            chars(&keymap, 29, 1),
+            [Some('z'), Some('Z'), Some('←'), Some('¥')]
+        );
+        assert_eq!(chars(&keymap, 38, 2), [Some('ф'), Some('Ф'), None, None]);
+        assert_eq!(chars(&keymap, 52, 2), [Some('я'), Some('Я'), None, None]);
+        // Augmenting layout does not override symbols of previous layout.
+        let keymap = load("us|de");
+        assert_eq!(
+            chars(&keymap, 11, 0),
+            [Some('2'), Some('@'), Some('²'), Some('⅛')]
+        );
+    }
+
+    #[test]
+    fn test_keysym_names() {
+        let c = |name| keysym_to_unicode(name).and_then(char::from_u32);
+        assert_eq!(c("a"), Some('a'));
+        assert_eq!(c("Z"), Some('Z'));
+        assert_eq!(c("7"), Some('7'));
+        assert_eq!(c("grave"), Some('`'));
+        assert_eq!(c("Adiaeresis"), Some('Ä'));
+        assert_eq!(c("ydiaeresis"), Some('ÿ'));
+        assert_eq!(c("ssharp"), Some('ß'));
+        assert_eq!(c("scaron"), Some('š'));
+        assert_eq!(c("EuroSign"), Some('€'));
+        assert_eq!(c("U20AC"), Some('€'));
+        assert_eq!(c("0x10020ac"), Some('€'));
+        assert_eq!(c("0xe4"), Some('ä'));
+        assert_eq!(c("Cyrillic_io"), Some('ё'));
+        assert_eq!(c("Cyrillic_IO"), Some('Ё'));
+        assert_eq!(c("Cyrillic_SHCHA"), Some('Щ'));
+        assert_eq!(c("dead_acute"), None);
+        assert_eq!(c("Shift_L"), None);
+        assert_eq!(c("NoSymbol"), None);
+    }
+
+    #[test]
+    fn test_dead_keys() {
+        let keymap = XkbKeymap::parse_with_resolver(
+            r#"xkb_keymap {
+                xkb_keycodes { <TLDE> = 49; <AE12> = 21; };
+                xkb_symbols {
+                    key <TLDE> { [ dead_circumflex, degree, U2032, U2033 ] };
+                    key <AE12> { [ dead_acute, dead_grave, 0xfe5b, dead_ogonek ] };
+                };
+            };"#,
+            &resolver,
+        )
+        .unwrap();
+        let dead = |keycode, level| keymap.dead_key(keycode, 0, level).and_then(char::from_u32);
+        assert_eq!(
+            chars(&keymap, 49, 0),
+            [None, Some('°'), Some('′'), Some('″')]
+        );
+        assert_eq!(dead(49, 0), Some('\u{302}'));
+        assert_eq!(dead(49, 1), None);
+        assert_eq!(chars(&keymap, 21, 0), [None, None, None, None]);
+        assert_eq!(dead(21, 0), Some('\u{301}'));
+        assert_eq!(dead(21, 1), Some('\u{300}'));
+        assert_eq!(dead(21, 2), Some('\u{327}'));
+        assert_eq!(dead(21, 3), Some('\u{328}'));
+    }
+
+    #[test]
+    fn test_invalid_keymap() {
+        assert!(XkbKeymap::parse_with_resolver(
+            "xkb_keymap { xkb_symbols { key <AE01> { [ 1, ",
+            &resolver
+        )
+        .is_err());
+        assert!(
+            XkbKeymap::parse_with_resolver(r#"xkb_symbols { include "missing" };"#, &resolver)
+                .is_err()
+        );
+        assert!(XkbKeymap::parse_with_resolver(
+            "xkb_keymap { xkb_symbols { key <AE01> { symbols[Group5] = [ 1 ] }; }; };",
+            &resolver
+        )
+        .is_err());
+        assert!(XkbKeymap::parse_with_resolver(
+            "xkb_keymap { xkb_symbols { key <AE01> { [1], [2], [3], [4], [5] }; }; };",
+            &resolver
+        )
+        .is_err());
+        assert!(XkbKeymap::parse_with_resolver(
+            r#"xkb_keymap { xkb_symbols { include "us:1000000" }; };"#,
+            &resolver
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_override_replaces_group() {
+        let keymap = XkbKeymap::parse_with_resolver(
+            r#"xkb_keymap {
+                xkb_keycodes { <AE01> = 10; <AE02> = 11; };
+                xkb_symbols {
+                    key <AE01> { [ 1, exclam, onesuperior, exclamdown ] };
+                    key <AE02> { [ 2, at ] };
+                    override key <AE01> { [ a, A ] };
+                    augment key <AE02> { [ b, B, twosuperior ] };
+                };
+            };"#,
+            &resolver,
+        )
+        .unwrap();
+        assert_eq!(chars(&keymap, 10, 0), [Some('a'), Some('A'), None, None]);
+        assert_eq!(
+            chars(&keymap, 11, 0),
+            [Some('2'), Some('@'), Some('²'), None]
+        );
+    }
+
+    #[test]
+    fn test_keyboard_config() {
+        let config = "# KEYBOARD CONFIGURATION FILE\n\
+            XKBMODEL=\"pc105\"\n\
+            XKBLAYOUT=\"us,de\"\n\
+            XKBVARIANT=\",nodeadkeys\"\n\
+            XKBOPTIONS=\"grp:alt_shift_toggle\"\n";
+        assert_eq!(
+            parse_keyboard_config(config),
+            Some(("us,de".into(), ",nodeadkeys".into()))
+        );
+        assert_eq!(parse_keyboard_config("XKBMODEL=\"pc105\""), None);
+    }
+}
@@ -0,0 +1,67 @@
+import 'package:flutter/services.dart';
+import 'package:super_native_extensions/src/keyboard_layout.dart';
+import 'package:super_native_extensions/src/keyboard_layout_model.dart'
+    as model;
+import 'package:test/test.dart';
+
+model.KeyboardLayout _layout() => model.KeyboardLayout(keys: [
+      model.KeyboardKey(
+        platform: 16,
+        physical: PhysicalKeyboardKey.keyY.usbHidUsage,
+        logical: LogicalKeyboardKey.keyZ.keyId,
+        logicalShift: LogicalKeyboardKey.keyZ.keyId,
+      ),
+      model.KeyboardKey(
+        platform: 6,
+        physical: PhysicalKeyboardKey.keyZ.usbHidUsage,
+        logical: LogicalKeyboardKey.keyY.keyId,
+        logicalShift: LogicalKeyboardKey.keyY.keyId,
+      ),
+      model.KeyboardKey(
+        platform: 21,
+        physical: PhysicalKeyboardKey.equal.usbHidUsage,
+        logical: LogicalKeyboardKey.quoteSingle.keyId,
+        logicalShift: LogicalKeyboardKey.backquote.keyId,
+        logicalAlt: LogicalKeyboardKey.at.keyId,
+        isDeadKey: true,
+        deadKeyShift: 0x0301,
+      ),
+    ]);
+
+void main() {
+  test('model snapshot round trip', () {
+    final snapshot = _layout().toSnapshot();
+    final decoded = model.KeyboardLayout.fromSnapshot(snapshot);
+    expect(decoded.keys.length, equals(3));
+    // Keys are sorted by physical key code.
+    expect(decoded.keys.map((k) => k.physical).toList(), [
+      PhysicalKeyboardKey.keyZ.usbHidUsage,
+      PhysicalKeyboardKey.keyY.usbHidUsage,
+      PhysicalKeyboardKey.equal.usbHidUsage,
+    ]);
+    final deadKey = decoded.keys.last;
+    expect(deadKey.serialize(), equals(_layout().keys.last.serialize()));
+    expect(decoded.toSnapshot(), equals(snapshot));
+  });
+
+  test('layout snapshot round trip', () {
+    final layout = KeyboardLayout.fromModel(_layout());
+    final decoded = KeyboardLayout.fromSnapshot(layout.toSnapshot());
+    expect(decoded.toSnapshot(), equals(layout.toSnapshot()));
+    expect(decoded.getPlatformKeyCode(LogicalKeyboardKey.keyZ), equals(16));
+    expect(decoded.getPhysicalKeyForPlatformKeyCode(6),
+        equals(PhysicalKeyboardKey.keyZ));
+    expect(decoded.isDeadKey(PhysicalKeyboardKey.equal), isTrue);
+    expect(
+        decoded.getDeadKeyCombiningCharacter(PhysicalKeyboardKey.equal,
+            shift: true),
+        equals(0x0301));
+  });
+
+  test('unsupported snapshot', () {
+    expect(() => model.KeyboardLayout.fromSnapshot('{"version": 2}'),
+        throwsFormatException);
+    expect(() => model.KeyboardLayout.fromSnapshot('[]'),
+        throwsFormatException);
+  });
+}